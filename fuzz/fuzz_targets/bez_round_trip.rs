@@ -0,0 +1,8 @@
+#![no_main]
+
+use iconimation::fuzz::{bez_round_trip, FuzzPath};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|path: FuzzPath| {
+    let _ = bez_round_trip(&path);
+});
@@ -0,0 +1,9 @@
+#![no_main]
+
+use iconimation::fuzz::{replace_in_template, FuzzAnimation};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (&[u8], &[u8], u16, FuzzAnimation)| {
+    let (template, font, gid, animation) = input;
+    let _ = replace_in_template(template, font, gid, animation);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use iconimation::fuzz::subpath_from_json;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|json: &[u8]| {
+    let _ = subpath_from_json(json);
+});
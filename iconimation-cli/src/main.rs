@@ -1,16 +1,51 @@
-use std::{fs, path::Path};
+use std::{
+    fs,
+    io::{self, Cursor, Read},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
 
 use bodymovin::Bodymovin as Lottie;
+use clap::ArgGroup;
 use clap::Parser;
+use clap::Subcommand;
 use clap::ValueEnum;
-use iconimation::animate::Animation;
+use iconimation::animate::{Animation, AxisRanges, PartOptions, RevealDirection};
+use iconimation::animated_svg::to_animated_svg;
+use iconimation::codepoints::IconNames;
+use iconimation::compare::side_by_side;
+use iconimation::compat::support_matrix;
+use iconimation::css::to_css_animation;
 use iconimation::debug_pen::DebugPen;
-use iconimation::default_template;
-use iconimation::Template;
-use kurbo::Point;
-use kurbo::Rect;
-use skrifa::raw::FontRef;
-use skrifa::raw::TableProvider;
+use iconimation::dotlottie::DotLottieSink;
+use iconimation::error::Error;
+use iconimation::evaluate::{extract_shapes, snapshot};
+use iconimation::font_drawbox;
+use iconimation::glyph_id_for_name;
+use iconimation::lottie_for_glyph;
+use iconimation::lottie_for_svg;
+use iconimation::lottie_for_text;
+use iconimation::mapped_glyphs;
+use iconimation::output::{to_json, OutputOptions};
+use iconimation::placement::{parse_slot, Drawbox, FitMode, Placement, VerticalAlign, PLACEHOLDER};
+use iconimation::pool::TemplatePool;
+use iconimation::report::structure;
+use iconimation::searched_layers;
+use iconimation::semantics::Semantics;
+use iconimation::sink::{FsSink, Sink, WriterSink};
+use iconimation::style::{
+    BurstStyle, DashStyle, FillColor, FillStyle, LineCap, LineJoin, ShadowStyle, StrokeStyle, Style,
+};
+use iconimation::svg::{path_icon, read_svg, SvgIcon};
+use iconimation::text::{gid_for_sequence, layout_by_advance, Direction, Line};
+use iconimation::tgs::to_tgs;
+use iconimation::validate::validate_template;
+use iconimation::DesignLocation;
+use iconimation::Glyph;
+use serde::Deserialize;
+use serde_json::json;
+use skrifa::raw::{FontRef, TableProvider};
+use skrifa::GlyphId;
 use skrifa::MetadataProvider;
 
 /// Clap-friendly version of [Animation]
@@ -21,94 +56,1658 @@ pub enum CliAnimation {
     PulseParts,
     TwirlWhole,
     TwirlParts,
+    Shake,
+    WeightMorph,
+    FillToggle,
+    GradeHover,
+    /// Morph along the ranges given by --animate-axes
+    AxisMorph,
+    /// Cross-fade between the ends of the ranges given by --animate-axes
+    AxisFade,
+    /// Animate the parts declared in the --parts config
+    Annotated,
+    /// Cross-fade from --codepoint to --swap-to
+    Swap,
+    /// Fade in from a blur; the blur is a layer effect, not every player draws it
+    BlurIn,
+    /// Slide a mask across the icon, in --reveal-direction, to uncover it
+    Reveal,
+    /// Uncover the icon through a growing circle, a track matte layer
+    CircleReveal,
+    /// Pick from the glyph name, such as twirl for *refresh*, per built-in rules and --semantics
+    Auto,
 }
 
 impl CliAnimation {
-    fn to_lib(&self) -> Animation {
-        match self {
+    /// The animation to make, `auto` picking it for [CliAnimation::Auto]
+    fn to_lib(
+        &self,
+        gid: GlyphId,
+        swap_to: Option<GlyphId>,
+        args: &Args,
+        auto: impl FnOnce() -> Result<Animation, Error>,
+    ) -> Result<Animation, Error> {
+        let animate_axes = || -> Result<AxisRanges, Error> {
+            args.animate_axes
+                .as_deref()
+                .ok_or(Error::MissingArgument("--animate-axes", "animate axes"))?
+                .parse()
+        };
+        let parts = PartOptions {
+            min_part_area: args.min_part_area,
+        };
+        Ok(match self {
             CliAnimation::None => Animation::None,
             CliAnimation::PulseWhole => Animation::PulseWhole,
-            CliAnimation::PulseParts => Animation::PulseParts,
+            CliAnimation::PulseParts => Animation::PulseParts {
+                compensate_stroke: args.compensate_stroke,
+                parts,
+            },
             CliAnimation::TwirlWhole => Animation::TwirlWhole,
-            CliAnimation::TwirlParts => Animation::TwirlParts,
-        }
+            CliAnimation::TwirlParts => Animation::TwirlParts { parts },
+            CliAnimation::Shake => Animation::Shake,
+            CliAnimation::WeightMorph => Animation::WeightMorph,
+            CliAnimation::FillToggle => Animation::FillToggle,
+            CliAnimation::GradeHover => Animation::GradeHover,
+            CliAnimation::AxisMorph => Animation::AxisMorph {
+                ranges: animate_axes()?.0,
+                samples: args.morph_samples,
+            },
+            CliAnimation::Annotated => {
+                let parts = args
+                    .parts
+                    .as_ref()
+                    .ok_or(Error::MissingArgument("--parts", "annotate"))?;
+                Animation::Annotated {
+                    annotations: read_file(parts)?
+                        .parse()
+                        .map_err(|e| parse_error(parts, e))?,
+                }
+            }
+            CliAnimation::AxisFade => Animation::AxisFade {
+                ranges: animate_axes()?.0,
+            },
+            CliAnimation::BlurIn => Animation::BlurIn,
+            CliAnimation::Reveal => Animation::Reveal {
+                direction: args.reveal_direction.into(),
+            },
+            CliAnimation::CircleReveal => Animation::CircleReveal,
+            CliAnimation::Swap => Animation::Swap {
+                from: gid,
+                to: swap_to.ok_or(Error::MissingArgument("--swap-to", "swap"))?,
+            },
+            CliAnimation::Auto => auto()?,
+        })
     }
 }
 
-#[derive(Parser)]
+#[derive(Subcommand, Clone)]
+enum Command {
+    /// Print the font's upem, variation axes and named instances as json
+    InspectFont {
+        font: String,
+        /// Which font of a collection, such as a .ttc, to read
+        #[arg(long, default_value_t = 0)]
+        font_index: u32,
+    },
+    /// Print each mapped codepoint that has an outline, one per line, such as 0xe855
+    ListGlyphs {
+        font: String,
+        /// Which font of a collection, such as a .ttc, to read
+        #[arg(long, default_value_t = 0)]
+        font_index: u32,
+        /// Follow each codepoint with a tab and the glyph name from the post table
+        #[arg(long)]
+        names: bool,
+    },
+    /// Print how well each animation plays under each player profile as json
+    SupportMatrix,
+    /// Print every placeholder in a template, where it is, its boxes, its timing and what's
+    /// wrong with it, as json; fails if there are none or any have problems
+    ValidateTemplate {
+        /// A Lottie file, or builtin:NAME
+        template: String,
+        #[arg(long, default_value = PLACEHOLDER)]
+        placeholder_name: String,
+    },
+    /// Print an outline of a Lottie file, its layers, groups and shapes with their timing and
+    /// what they animate, flagging placeholders, to see why a template isn't matching
+    Explain {
+        /// A Lottie file, or builtin:NAME
+        template: String,
+        #[arg(long, default_value = PLACEHOLDER)]
+        placeholder_name: String,
+    },
+    /// Write the shapes a Lottie file draws, generated or a template, as a still SVG, such as to
+    /// check what went into a template or to use the icon where it needn't move
+    ExtractSvg {
+        lottie: String,
+        /// How far into the animation, from its in point
+        #[arg(long, default_value_t = 0.0)]
+        seconds: f64,
+        /// Only what's within placeholders, named as --placeholder-name says, rather than
+        /// everything drawn
+        #[arg(long)]
+        placeholders: bool,
+        #[arg(long, default_value = PLACEHOLDER)]
+        placeholder_name: String,
+        #[arg(long, default_value = "extracted.svg")]
+        out_file: String,
+    },
+}
+
+/// Generate an animation, unless a subcommand is given
+#[derive(Parser, Clone)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+#[clap(group(ArgGroup::new("glyph").required(true).args(["codepoint", "character", "glyph_name", "icon", "gid", "emoji", "text", "svg", "path", "manifest"])))]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Whether to emit additional debug info
     #[arg(long)]
     debug: bool,
 
-    #[clap(value_enum, required(true))]
+    #[clap(value_enum)]
+    #[arg(long, required_unless_present = "manifest")]
+    animation: Option<CliAnimation>,
+
+    /// A yaml (or json) list of jobs, each with a glyph, output path and, optionally, animation,
+    /// template and axes; other arguments apply to every job
     #[arg(long)]
-    animation: CliAnimation,
+    manifest: Option<String>,
+
+    /// Such as 0xE87C, U+E87C, 59516 or the character itself; repeat, separate with commas, or
+    /// give a range such as 0xE000-0xE0FF to generate several icons in one run
+    #[arg(long, value_delimiter = ',')]
+    codepoint: Vec<String>,
 
+    /// Select the glyph by the character it's mapped to, such as ❤
+    #[arg(long = "char")]
+    character: Option<char>,
+
+    /// Select the glyph by its name in the post table, such as arrow_forward, rather than by codepoint
     #[arg(long)]
-    codepoint: String,
+    glyph_name: Option<String>,
+
+    /// Select glyphs by icon name, such as home, rather than by codepoint; repeat or separate
+    /// with commas for several. Names are looked up in --codepoints, falling back to glyph names
+    #[arg(long, value_delimiter = ',')]
+    icon: Vec<String>,
 
+    /// A Material Symbols .codepoints file naming icons for --icon, by default the one beside
+    /// the font such as MaterialSymbolsOutlined[FILL,GRAD,opsz,wght].codepoints
+    #[arg(long)]
+    codepoints: Option<String>,
+
+    /// Select the glyph by id, such as for unmapped ligature components
+    #[arg(long)]
+    gid: Option<u16>,
+
+    /// Animate a short word or label, its glyphs laid out on a line by their advance widths and
+    /// scaled together to fit the placeholder
+    #[arg(long)]
+    text: Option<String>,
+
+    /// Animate a standalone SVG icon rather than a font's glyph, its filled paths drawn in its
+    /// view box as a glyph in the em; no font is needed
+    #[arg(long)]
+    svg: Option<String>,
+
+    /// Animate a path in SVG d syntax, such as "M4 4 L20 4 L12 20 Z", in a box from 0,0 to its
+    /// furthest point; handy to try things out without a font
+    #[arg(long)]
+    path: Option<String>,
+
+    /// Select the one glyph the font draws an emoji sequence with, such as 👩‍💻 or, as
+    /// codepoints, "U+1F469 U+200D U+1F4BB"; a character and VS15 or VS16, such as
+    /// "U+2764 U+FE0F", picks its text or emoji form, longer sequences need the shaping feature
+    #[arg(long)]
+    emoji: Option<String>,
+
+    /// Shape --text with the font's kerning, ligatures and mark positioning, such as to select
+    /// icons by ligature name; needs the shaping feature
+    #[arg(long, requires = "text")]
+    shape: bool,
+
+    /// Which way --text runs, by default as its first letter does; right-to-left text is set
+    /// with its first character rightmost
+    #[arg(long, value_enum, default_value = "auto")]
+    direction: CliDirection,
+
+    /// The codepoint to cross-fade to when using the swap animation, in any --codepoint syntax
+    #[arg(long)]
+    swap_to: Option<String>,
+
+    /// Where in design space to draw the glyph, such as wght=700; repeat for each axis
+    #[arg(long = "axis")]
+    axes: Vec<String>,
+
+    /// The ranges to animate along with axis-morph or axis-fade, such as "wght:100..700,FILL:0..1"
+    #[arg(long)]
+    animate_axes: Option<String>,
+
+    /// How many locations to sample, including both ends, with the axis-morph animation
+    #[arg(long)]
+    #[clap(default_value = "5")]
+    morph_samples: usize,
+
+    /// Which way the reveal animation uncovers the icon
+    #[arg(long, value_enum, default_value = "left-to-right")]
+    reveal_direction: CliRevealDirection,
+
+    /// With pulse-parts, scale thin parts less so stroke weights stay consistent
+    #[arg(long)]
+    compensate_stroke: bool,
+
+    /// With pulse-parts or twirl-parts, merge parts smaller than this fraction of the icon's
+    /// bounding box, such as 0.01, into the nearest larger part
+    #[arg(long)]
+    #[clap(default_value = "0")]
+    min_part_area: f64,
+
+    /// With --animation auto, a json array of {"pattern": "*refresh*", "animation": "twirl-whole"}
+    /// tried before the built-in rules
+    #[arg(long)]
+    semantics: Option<String>,
+
+    /// Fill with this color, such as #1A73E8, rather than a different color per part
+    #[arg(long)]
+    color: Option<String>,
+
+    /// Fill parts with these colors, cycling through "#EF5350,#AB47BC" or assigning
+    /// "0=#EF5350,2=#AB47BC" by part index
+    #[arg(long, conflicts_with = "color")]
+    part_colors: Option<String>,
+
+    /// Fill with a gradient across the glyph, top to bottom through "#1A73E8,#A142F4" or out
+    /// from the center with "radial:#1A73E8,#A142F4"; stops may have offsets, as in "#1A73E8@0.2"
+    #[arg(long, conflicts_with_all = ["color", "part_colors"])]
+    gradient: Option<String>,
+
+    /// Fill opacity, from 0 to 100
+    #[arg(long)]
+    #[clap(default_value = "100")]
+    opacity: f64,
+
+    /// Stroke the outline this wide, in the fill color, over the fill
+    #[arg(long)]
+    stroke: Option<f64>,
+
+    /// Only stroke, don't fill, for outline style icons
+    #[arg(long, requires = "stroke")]
+    stroke_only: bool,
+
+    /// Swell the stroke to this width halfway through the animation and back
+    #[arg(long, requires = "stroke")]
+    stroke_width_to: Option<f64>,
+
+    /// Dash the stroke, dash and gap lengths such as "4,2", the dashes marching along the outline
+    #[arg(long, requires = "stroke")]
+    dash: Option<String>,
+
+    /// How many dash and gap lengths the dashes march over the animation; 0 holds still
+    #[arg(long, requires = "dash")]
+    #[clap(default_value = "1")]
+    dash_cycles: f64,
+
+    #[arg(long, value_enum, default_value = "round")]
+    stroke_cap: CliLineCap,
+
+    #[arg(long, value_enum, default_value = "round")]
+    stroke_join: CliLineJoin,
+
+    /// Cast a shadow offset by x,y, such as "2,3", beneath the glyph
+    #[arg(long)]
+    shadow: Option<String>,
+
+    /// How far the shadow's edge spreads
+    #[arg(long, requires = "shadow")]
+    #[clap(default_value = "4")]
+    shadow_softness: f64,
+
+    /// Shadow opacity, from 0 to 100
+    #[arg(long, requires = "shadow")]
+    #[clap(default_value = "50")]
+    shadow_opacity: f64,
+
+    #[arg(long, requires = "shadow")]
+    #[clap(default_value = "#000000")]
+    shadow_color: String,
+
+    /// Emit the shadow as a drop shadow layer effect, which blurs, rather than as a copy of
+    /// the glyph's shapes; not every player draws effects
+    #[arg(long, requires = "shadow")]
+    shadow_effect: bool,
+
+    /// Burst this many ticks out from behind the glyph as it enters
+    #[arg(long)]
+    burst: Option<u32>,
+
+    #[arg(long, requires = "burst")]
+    burst_color: Option<String>,
+
+    /// The CPAL palette to color color glyphs from
+    #[arg(long)]
+    #[clap(default_value = "0")]
+    palette: u16,
+
+    /// Shift color glyphs from --palette to this palette over the animation, such as from a
+    /// light to a dark theme
+    #[arg(long)]
+    palette_to: Option<u16>,
+
+    /// A json file declaring the icon's parts, by contour index, and how each moves
+    #[arg(long)]
+    parts: Option<String>,
+
+    /// A Lottie file with a placeholder to put the icon in, or one of builtin:plain,
+    /// builtin:circle-badge, builtin:rounded-card and builtin:notification-dot
     #[arg(long)]
     template: Option<String>,
 
+    /// The name of groups, or whole shape layers, in the template to place the glyph into
+    #[arg(long, default_value = PLACEHOLDER)]
+    placeholder_name: String,
+
+    /// Place another icon in a numbered placeholder, such as 2=0xE87D for "placeholder:2";
+    /// repeat for each. Placeholders not bound get --codepoint
+    #[arg(long)]
+    slot: Vec<String>,
+
+    /// The box glyphs are drawn into: em, an upem square, metrics, from the descender to the
+    /// ascender, or bounds, each glyph's own bounding box
+    #[arg(long, value_enum, default_value = "em")]
+    drawbox: CliDrawbox,
+
+    /// Where glyphs sit vertically in the drawbox
+    #[arg(long, value_enum, default_value = "baseline")]
+    vertical_align: CliVerticalAlign,
+
+    /// How the font's drawbox is scaled into placeholders that don't say with fit=
+    #[arg(long, value_enum, default_value = "contain")]
+    fit: CliFitMode,
+
+    /// Center icons on the centroid of their area rather than their bounds, so lopsided icons
+    /// such as a play triangle look centered
+    #[arg(long)]
+    optical_center: bool,
+
+    /// Inset placeholders that don't say with pad=, in their layer's units such as 16 or as a
+    /// percentage such as 10%, to leave room for animations that grow the icon
+    #[arg(long, default_value = "0")]
+    padding: String,
+
+    /// Also animate into this template and output both side by side, --template on the left
+    #[arg(long)]
+    compare_template: Option<String>,
+
+    /// A font file, or - to read it from stdin
+    #[arg(long, required_unless_present_any = ["family", "svg", "path"])]
+    font: Option<String>,
+
+    /// Fetch the font from Google Fonts, such as "Material Symbols Outlined" or, for a variable
+    /// font, with axis ranges as in "Material Symbols Outlined:wght,FILL@100..700,0..1"; fetched
+    /// fonts are cached. Needs the google_fonts feature
+    #[arg(long, conflicts_with = "font")]
+    family: Option<String>,
+
+    /// Which font of a collection, such as a .ttc, to read
+    #[arg(long, default_value_t = 0)]
+    font_index: u32,
+
+    /// Also write a png thumbnail of the animation here, a path, - or a url as for --out-file
+    #[arg(long)]
+    thumbnail: Option<String>,
+
+    /// The frame to thumbnail; by default the one that differs most from the first
     #[arg(long)]
-    #[clap(required(true))]
-    font: String,
+    thumbnail_frame: Option<f64>,
 
+    #[arg(long)]
+    #[clap(default_value = "256")]
+    thumbnail_size: u32,
+
+    /// Also write a still of the animation this many seconds in, such as 0.5, to
+    /// --snapshot-file; fails for what previews can't draw, such as layer effects
+    #[arg(long)]
+    snapshot: Option<f64>,
+
+    /// Where to write --snapshot, an svg or, by its extension, a png --preview-size pixels
+    /// square; a path, - or a url, "{icon}" replaced, as for --out-file
+    #[arg(long, default_value = "snapshot.svg")]
+    snapshot_file: String,
+
+    /// How many pixels square to render gif, apng, webp, video, sprite sheets and png snapshots
+    #[arg(long, default_value_t = 256)]
+    preview_size: u32,
+
+    /// The frame rate to render gif, apng and webp output at; by default the animation's,
+    /// for gif at most 50
+    #[arg(long)]
+    preview_fps: Option<f64>,
+
+    /// How many frames, evenly spaced, to put in sprite-sheet output
+    #[arg(long, default_value_t = 16)]
+    sprite_frames: usize,
+
+    /// What to draw transparent parts of mp4 output over, which has no transparency
+    #[arg(long, default_value = "#FFFFFF")]
+    video_background: String,
+
+    /// Print a table of every animated property's keyframes, times and eases
+    #[arg(long)]
+    timing_report: bool,
+
+    /// A path, - for stdout or, with the object_store feature, an s3:// or gs:// url
+    ///
+    /// "{icon}" is replaced by the codepoint, such as e855, or glyph name. When generating several
+    /// icons without "{icon}" it's added before the extension, as is done for --thumbnail.
     #[arg(long)]
     #[clap(default_value = "output.json")]
     out_file: String,
+
+    /// Check each Lottie, as it's written, against this JSON schema, such as the Lottie
+    /// specification's lottie.schema.json, and exit with an error listing any violations; needs
+    /// the schema feature
+    #[arg(long)]
+    check_schema: Option<String>,
+
+    /// What to write; by default inferred from the extension of --out-file
+    #[arg(long)]
+    format: Option<OutputFormat>,
+
+    /// With .lottie output, add "intro" and "loop" markers and a state machine that plays intro
+    /// on hover and loop on click
+    #[arg(long)]
+    interactivity: bool,
+
+    /// Write minified json rather than pretty printing it
+    #[arg(long)]
+    compact: bool,
+
+    /// Don't route fill colors through Lottie slots, for players that don't support them
+    #[arg(long)]
+    no_slots: bool,
+
+    /// Round coordinates and other fractional numbers in the output to this many decimals
+    #[arg(long)]
+    precision: Option<u32>,
+
+    /// Leave out defaults, empty groups, duplicate shapes and keyframes that change nothing;
+    /// players fill defaults in but the result may not load back into bodymovin
+    #[arg(long)]
+    optimize: bool,
+}
+
+fn gid_for_codepoint(font: &FontRef, codepoint: u32) -> Result<GlyphId, Error> {
+    font.charmap()
+        .map(codepoint)
+        .ok_or(Error::NoGlyphForCodepoint(codepoint))
+}
+
+/// The icon names of --codepoints, or of the .codepoints file beside the font if there is one
+fn icon_names(args: &Args) -> Result<IconNames, Error> {
+    let path = match (&args.codepoints, &args.font) {
+        (Some(codepoints), _) => PathBuf::from(codepoints),
+        (None, Some(font)) if font != STDIO => IconNames::beside(Path::new(font)),
+        _ => return Ok(IconNames::default()),
+    };
+    if args.codepoints.is_none() && !path.exists() {
+        return Ok(IconNames::default());
+    }
+    let path = path.to_string_lossy();
+    read_file(&path)?.parse().map_err(|e| parse_error(&path, e))
+}
+
+fn gid_for_glyph_name(font: &FontRef, name: &str) -> Result<GlyphId, Error> {
+    glyph_id_for_name(font, name)?.ok_or_else(|| Error::NoGlyphNamed(name.to_string()))
+}
+
+/// The glyphs to animate, by whichever of --codepoint, --char, --glyph-name, --icon, --gid,
+/// --emoji or --text was given, each with a label for output file names such as "e855",
+/// "alarm" or "1f469_200d_1f4bb"; for --text, the glyph of its first character labelled with
+/// the text
+fn selected_gids(
+    font: &FontRef,
+    args: &Args,
+    location: &DesignLocation,
+) -> Result<Vec<(GlyphId, String)>, Error> {
+    Ok(if !args.codepoint.is_empty() {
+        // Ranges, such as a whole private use area, skip codepoints with nothing to draw
+        let mut mapped = None;
+        let mut selected = Vec::new();
+        for codepoint in args.codepoint.iter() {
+            let range = iconimation::parse_codepoint_range(codepoint)?;
+            if range.start() == range.end() {
+                let codepoint = *range.start();
+                selected.push((
+                    gid_for_codepoint(font, codepoint)?,
+                    format!("{codepoint:04x}"),
+                ));
+                continue;
+            }
+            let glyphs = match mapped.take() {
+                Some(glyphs) => glyphs,
+                None => mapped_glyphs(font)?,
+            };
+            selected.extend(
+                glyphs
+                    .iter()
+                    .filter(|(codepoint, _)| range.contains(codepoint))
+                    .map(|(codepoint, gid)| (*gid, format!("{codepoint:04x}"))),
+            );
+            mapped = Some(glyphs);
+        }
+        selected
+    } else if let Some(c) = args.character {
+        vec![(
+            gid_for_codepoint(font, c as u32)?,
+            format!("{:04x}", c as u32),
+        )]
+    } else if let Some(name) = &args.glyph_name {
+        vec![(gid_for_glyph_name(font, name)?, name.clone())]
+    } else if !args.icon.is_empty() {
+        let names = icon_names(args)?;
+        args.icon
+            .iter()
+            .map(|icon| {
+                // Material Symbols name glyphs for their icons too
+                let gid = match names.codepoint(icon) {
+                    Some(codepoint) => gid_for_codepoint(font, codepoint)?,
+                    None => gid_for_glyph_name(font, icon)?,
+                };
+                Ok((gid, icon.clone()))
+            })
+            .collect::<Result<_, Error>>()?
+    } else if let Some(gid) = args.gid {
+        vec![(GlyphId::new(gid), format!("gid{gid}"))]
+    } else if let Some(emoji) = &args.emoji {
+        let sequence = iconimation::parse_sequence(emoji)?;
+        let gid = gid_for_sequence(font, &sequence, location)?;
+        let label: Vec<_> = sequence.iter().map(|c| format!("{c:04x}")).collect();
+        vec![(gid, label.join("_"))]
+    } else if let Some(text) = &args.text {
+        let first = text.chars().next().ok_or(Error::EmptyText)?;
+        vec![(gid_for_codepoint(font, first as u32)?, text.clone())]
+    } else {
+        return Err(Error::MissingArgument(
+            "--codepoint, --char, --glyph-name, --icon, --gid, --emoji or --text",
+            "pick a glyph",
+        ));
+    })
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CliRevealDirection {
+    LeftToRight,
+    RightToLeft,
+    TopToBottom,
+    BottomToTop,
+}
+
+impl From<CliRevealDirection> for RevealDirection {
+    fn from(direction: CliRevealDirection) -> Self {
+        match direction {
+            CliRevealDirection::LeftToRight => RevealDirection::LeftToRight,
+            CliRevealDirection::RightToLeft => RevealDirection::RightToLeft,
+            CliRevealDirection::TopToBottom => RevealDirection::TopToBottom,
+            CliRevealDirection::BottomToTop => RevealDirection::BottomToTop,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CliDrawbox {
+    Em,
+    Metrics,
+    Bounds,
+}
+
+impl From<CliDrawbox> for Drawbox {
+    fn from(drawbox: CliDrawbox) -> Self {
+        match drawbox {
+            CliDrawbox::Em => Drawbox::Em,
+            CliDrawbox::Metrics => Drawbox::Metrics,
+            CliDrawbox::Bounds => Drawbox::Bounds,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CliVerticalAlign {
+    Baseline,
+    CapHeight,
+    BboxCenter,
+}
+
+impl From<CliVerticalAlign> for VerticalAlign {
+    fn from(align: CliVerticalAlign) -> Self {
+        match align {
+            CliVerticalAlign::Baseline => VerticalAlign::Baseline,
+            CliVerticalAlign::CapHeight => VerticalAlign::CapHeight,
+            CliVerticalAlign::BboxCenter => VerticalAlign::BboxCenter,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CliDirection {
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+impl From<CliDirection> for Direction {
+    fn from(direction: CliDirection) -> Self {
+        match direction {
+            CliDirection::Auto => Direction::Auto,
+            CliDirection::Ltr => Direction::LeftToRight,
+            CliDirection::Rtl => Direction::RightToLeft,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CliFitMode {
+    Contain,
+    Cover,
+    Stretch,
+    None,
+}
+
+impl From<CliFitMode> for FitMode {
+    fn from(fit: CliFitMode) -> Self {
+        match fit {
+            CliFitMode::Contain => FitMode::Contain,
+            CliFitMode::Cover => FitMode::Cover,
+            CliFitMode::Stretch => FitMode::Stretch,
+            CliFitMode::None => FitMode::None,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CliLineCap {
+    Butt,
+    Round,
+    Square,
+}
+
+impl From<CliLineCap> for LineCap {
+    fn from(cap: CliLineCap) -> Self {
+        match cap {
+            CliLineCap::Butt => LineCap::Butt,
+            CliLineCap::Round => LineCap::Round,
+            CliLineCap::Square => LineCap::Square,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum CliLineJoin {
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl From<CliLineJoin> for LineJoin {
+    fn from(join: CliLineJoin) -> Self {
+        match join {
+            CliLineJoin::Miter => LineJoin::Miter,
+            CliLineJoin::Round => LineJoin::Round,
+            CliLineJoin::Bevel => LineJoin::Bevel,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Json,
+    /// A Telegram sticker, gzipped json that must be 512x512, at most 60fps and 3s long
+    Tgs,
+    /// A dotLottie bundle; with several icons, one bundle holding them all
+    #[value(name = "lottie")]
+    DotLottie,
+    /// An SVG animated with SMIL, for places that can't embed a Lottie player; gradients,
+    /// images, masks, mattes and effects are refused
+    Svg,
+    /// A CSS stylesheet with @keyframes and, beside it, the static SVG it animates; only for
+    /// animations that move, scale, rotate or fade groups
+    Css,
+    /// A looping GIF, rendered --preview-size pixels square
+    Gif,
+    /// A looping animated PNG, rendered --preview-size pixels square
+    Apng,
+    /// A looping animated WebP, rendered --preview-size pixels square; needs the webp feature
+    Webp,
+    /// H.264 video, rendered --preview-size pixels square over --video-background; needs the
+    /// video feature and ffmpeg
+    Mp4,
+    /// VP9 video, with transparency, rendered --preview-size pixels square; needs the video
+    /// feature and ffmpeg
+    Webm,
+    /// A PNG of --sprite-frames frames in a grid, each --preview-size pixels square, and beside
+    /// it a json index of where each frame is
+    SpriteSheet,
+}
+
+impl OutputFormat {
+    /// --format or else by the extension of `out_file`, json by default
+    fn for_args(args: &Args, out_file: &str) -> Self {
+        args.format.unwrap_or_else(|| {
+            match Path::new(out_file).extension().and_then(|e| e.to_str()) {
+                Some("tgs") => OutputFormat::Tgs,
+                Some("lottie") => OutputFormat::DotLottie,
+                Some("svg") => OutputFormat::Svg,
+                Some("css") => OutputFormat::Css,
+                Some("gif") => OutputFormat::Gif,
+                Some("apng") => OutputFormat::Apng,
+                Some("webp") => OutputFormat::Webp,
+                Some("mp4") => OutputFormat::Mp4,
+                Some("webm") => OutputFormat::Webm,
+                _ => OutputFormat::Json,
+            }
+        })
+    }
+}
+
+/// Where glyphs go in templates, as the placement arguments say
+fn placement(args: &Args) -> Result<Placement, Error> {
+    Ok(Placement {
+        marker: args.placeholder_name.clone(),
+        drawbox: args.drawbox.into(),
+        vertical_align: args.vertical_align.into(),
+        fit: args.fit.into(),
+        optical_center: args.optical_center,
+        padding: args.padding.parse()?,
+        slots: args
+            .slot
+            .iter()
+            .map(|slot| parse_slot(slot))
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn style(args: &Args) -> Result<Style, Error> {
+    let mut fill = FillStyle {
+        opacity: args.opacity,
+        ..Default::default()
+    };
+    if let Some(color) = &args.color {
+        fill.color = FillColor::Solid(color.parse()?);
+    }
+    if let Some(part_colors) = &args.part_colors {
+        fill.color = FillColor::Palette(part_colors.parse()?);
+    }
+    if let Some(gradient) = &args.gradient {
+        fill.color = FillColor::Gradient(gradient.parse()?);
+    }
+    let dash = match &args.dash {
+        Some(dash) => Some(DashStyle {
+            cycles: args.dash_cycles,
+            ..dash.parse()?
+        }),
+        None => None,
+    };
+    let burst_color = match &args.burst_color {
+        Some(color) => color.parse()?,
+        None => BurstStyle::default().color,
+    };
+    let shadow = match &args.shadow {
+        Some(offset) => Some(ShadowStyle {
+            softness: args.shadow_softness,
+            opacity: args.shadow_opacity,
+            color: args.shadow_color.parse()?,
+            effect: args.shadow_effect,
+            ..offset.parse()?
+        }),
+        None => None,
+    };
+    Ok(Style {
+        fill,
+        stroke: args.stroke.map(|width| StrokeStyle {
+            width,
+            width_to: args.stroke_width_to,
+            cap: args.stroke_cap.into(),
+            join: args.stroke_join.into(),
+            replace_fill: args.stroke_only,
+            dash,
+        }),
+        palette: args.palette,
+        palette_to: args.palette_to,
+        burst: args.burst.map(|ticks| BurstStyle {
+            ticks,
+            color: burst_color,
+        }),
+        shadow,
+    })
+}
+
+/// In place of a file, read the font from stdin or write output to stdout
+const STDIO: &str = "-";
+
+/// The bytes of `font_file`, or of stdin if it's "-", unpacked if it's a web font
+///
+/// Stdin is read once and reused, as every job of a manifest reads the same font.
+fn read_font(font_file: &str) -> Result<Vec<u8>, Error> {
+    static STDIN: OnceLock<Vec<u8>> = OnceLock::new();
+    let read_error = |source| Error::ReadFile {
+        path: font_file.to_string(),
+        source,
+    };
+    let bytes = if font_file == STDIO {
+        if STDIN.get().is_none() {
+            let mut bytes = Vec::new();
+            io::stdin().read_to_end(&mut bytes).map_err(read_error)?;
+            let _ = STDIN.set(bytes);
+        }
+        STDIN.get().cloned().unwrap_or_default()
+    } else {
+        fs::read(font_file).map_err(read_error)?
+    };
+    iconimation::webfont::to_sfnt(bytes)
+}
+
+/// The font for `family` from Google Fonts, unpacked if it's a web font
+fn fetch_family(family: &str) -> Result<Vec<u8>, Error> {
+    #[cfg(feature = "google_fonts")]
+    {
+        use iconimation::google_fonts;
+        let bytes = google_fonts::fetch_family(family, &google_fonts::default_cache_dir())?;
+        iconimation::webfont::to_sfnt(bytes)
+    }
+    #[cfg(not(feature = "google_fonts"))]
+    Err(Error::FeatureDisabled(
+        format!("--family {family:?}"),
+        "google_fonts",
+    ))
+}
+
+/// Font `index` of `font_bytes`, 0 being the only font of files that aren't collections
+fn font_ref(font_bytes: &[u8], index: u32) -> Result<FontRef, Error> {
+    FontRef::from_index(font_bytes, index).map_err(Error::ReadError)
+}
+
+/// Where to write `out_file` and the name to write it as
+///
+/// Bucket urls are recognized by their scheme before anything else, whatever they end in.
+#[cfg_attr(not(feature = "object_store"), allow(unused_variables))]
+fn sink_for(out_file: &str) -> Result<(Box<dyn Sink>, String), Error> {
+    if out_file == STDIO {
+        return Ok((
+            Box::new(WriterSink::new(io::stdout())),
+            out_file.to_string(),
+        ));
+    }
+    if let Some((scheme, path)) = out_file
+        .split_once("://")
+        .filter(|(scheme, _)| ["s3", "gs"].contains(scheme))
+    {
+        let Some((bucket, name)) = path
+            .rsplit_once('/')
+            .filter(|(bucket, name)| !bucket.is_empty() && !name.is_empty())
+        else {
+            return Err(Error::BadUrl(format!(
+                "{out_file}, expected {scheme}://bucket/name"
+            )));
+        };
+        #[cfg(feature = "object_store")]
+        {
+            let sink =
+                iconimation::sink::ObjectStoreSink::from_url(&format!("{scheme}://{bucket}"))?;
+            return Ok((Box::new(sink), name.to_string()));
+        }
+        #[cfg(not(feature = "object_store"))]
+        return Err(Error::FeatureDisabled(
+            format!("writing to {out_file}"),
+            "object_store",
+        ));
+    }
+    Ok((Box::new(FsSink::new(".")), out_file.to_string()))
+}
+
+/// The font's upem, axes and named instances, with names resolved from the name table
+fn font_info(font: &FontRef) -> serde_json::Value {
+    let name = |id| {
+        font.localized_strings(id)
+            .english_or_first()
+            .map(|s| s.to_string())
+    };
+    let axes: Vec<_> = font
+        .axes()
+        .iter()
+        .map(|axis| {
+            json!({
+                "tag": axis.tag().to_string(),
+                "name": name(axis.name_id()),
+                "min": axis.min_value(),
+                "default": axis.default_value(),
+                "max": axis.max_value(),
+                "hidden": axis.is_hidden(),
+            })
+        })
+        .collect();
+    let instances: Vec<_> = font
+        .named_instances()
+        .iter()
+        .map(|instance| {
+            let location: serde_json::Map<_, _> = font
+                .axes()
+                .iter()
+                .zip(instance.user_coords())
+                .map(|(axis, value)| (axis.tag().to_string(), json!(value)))
+                .collect();
+            json!({
+                "name": name(instance.subfamily_name_id()),
+                "postscript_name": instance.postscript_name_id().and_then(name),
+                "location": location,
+            })
+        })
+        .collect();
+    json!({
+        "upem": font.head().map(|head| head.units_per_em()).ok(),
+        "axes": axes,
+        "instances": instances,
+    })
+}
+
+fn inspect_font(font_file: &str, font_index: u32) -> Result<(), Error> {
+    let font_bytes = read_font(font_file)?;
+    let font = font_ref(&font_bytes, font_index)?;
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&font_info(&font)).map_err(Error::JsonError)?
+    );
+    Ok(())
+}
+
+fn list_glyphs(font_file: &str, font_index: u32, names: bool) -> Result<(), Error> {
+    let font_bytes = read_font(font_file)?;
+    let font = font_ref(&font_bytes, font_index)?;
+    let post = font.post().ok();
+    for (codepoint, gid) in mapped_glyphs(&font)? {
+        let name = post.as_ref().and_then(|post| post.glyph_name(gid));
+        match name {
+            Some(name) if names => println!("0x{codepoint:04x}\t{name}"),
+            _ => println!("0x{codepoint:04x}"),
+        }
+    }
+    Ok(())
+}
+
+fn validate(template: &str, placeholder_name: &str) -> Result<(), Error> {
+    let template = TemplatePool::new().instantiate(template)?;
+    let placement = Placement {
+        marker: placeholder_name.to_string(),
+        ..Default::default()
+    };
+    let found = validate_template(&template, &placement);
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&found).map_err(Error::JsonError)?
+    );
+    if found.is_empty() {
+        return Err(Error::NoPlaceholder {
+            marker: placement.marker,
+            searched_layers: searched_layers(&template),
+        });
+    }
+    let problems = found.iter().filter(|p| !p.problems.is_empty()).count();
+    if problems > 0 {
+        return Err(Error::PlaceholderProblems {
+            problems,
+            placeholders: found.len(),
+        });
+    }
+    Ok(())
+}
+
+fn explain(template: &str, placeholder_name: &str) -> Result<(), Error> {
+    let template = TemplatePool::new().get(template)?;
+    let placement = Placement {
+        marker: placeholder_name.to_string(),
+        ..Default::default()
+    };
+    print!("{}", structure(template.json(), &placement));
+    Ok(())
+}
+
+fn extract_svg(
+    lottie: &str,
+    seconds: f64,
+    placeholders: bool,
+    placeholder_name: &str,
+    out_file: &str,
+) -> Result<(), Error> {
+    let json = serde_json::from_str(&read_file(lottie)?).map_err(|e| parse_error(lottie, e))?;
+    let placement = Placement {
+        marker: placeholder_name.to_string(),
+        ..Default::default()
+    };
+    let frame = extract_shapes(json, placeholders.then_some(&placement), seconds)?;
+    if frame.fills.is_empty() {
+        eprintln!("Nothing is drawn at {seconds}s");
+    }
+    write_output(out_file, frame.to_svg().as_bytes())
+}
+
+/// The text of the file at `path`
+fn read_file(path: &str) -> Result<String, Error> {
+    fs::read_to_string(path).map_err(|source| Error::ReadFile {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// That the file at `path` didn't parse, with why
+fn parse_error(path: &str, reason: impl std::fmt::Display) -> Error {
+    Error::ParseFile {
+        path: path.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+fn write_file(path: impl AsRef<Path>, bytes: impl AsRef<[u8]>) -> Result<(), Error> {
+    let path = path.as_ref();
+    fs::write(path, bytes).map_err(|source| Error::WriteFile {
+        path: path.to_string_lossy().into_owned(),
+        source,
+    })
 }
 
 fn main() {
     let args = Args::parse();
+    if let Err(e) = run(args) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    match &args.command {
+        Some(Command::InspectFont { font, font_index }) => inspect_font(font, *font_index),
+        Some(Command::ListGlyphs {
+            font,
+            font_index,
+            names,
+        }) => list_glyphs(font, *font_index, *names),
+        Some(Command::SupportMatrix) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&support_matrix()).map_err(Error::JsonError)?
+            );
+            Ok(())
+        }
+        Some(Command::ValidateTemplate {
+            template,
+            placeholder_name,
+        }) => validate(template, placeholder_name),
+        Some(Command::Explain {
+            template,
+            placeholder_name,
+        }) => explain(template, placeholder_name),
+        Some(Command::ExtractSvg {
+            lottie,
+            seconds,
+            placeholders,
+            placeholder_name,
+            out_file,
+        }) => extract_svg(lottie, *seconds, *placeholders, placeholder_name, out_file),
+        None => match args.manifest.clone() {
+            Some(manifest) => run_manifest(&args, &manifest),
+            None => generate(args, &TemplatePool::new()),
+        },
+    }
+}
+
+/// One entry of a --manifest
+#[derive(Deserialize)]
+struct Job {
+    codepoint: Option<String>,
+    glyph_name: Option<String>,
+    /// Such as home, see --icon
+    icon: Option<String>,
+    animation: Option<String>,
+    template: Option<String>,
+    /// Such as ["wght=700", "FILL=1"]
+    #[serde(default)]
+    axes: Vec<String>,
+    out: String,
+}
+
+/// `global` axis settings, as for --axis, with those of `job` in place of any on the same axis
+fn merge_axes(global: &[String], job: &[String]) -> Result<Vec<String>, Error> {
+    let global: DesignLocation = global.join(",").parse()?;
+    let job: DesignLocation = job.join(",").parse()?;
+    Ok(global
+        .0
+        .iter()
+        .filter(|setting| !job.0.iter().any(|s| s.selector == setting.selector))
+        .chain(job.0.iter())
+        .map(|setting| format!("{}={}", setting.selector, setting.value))
+        .collect())
+}
+
+/// Run each job of a manifest, carrying on past failures, and report how it went
+fn run_manifest(args: &Args, manifest: &str) -> Result<(), Error> {
+    let jobs: Vec<Job> =
+        serde_yaml::from_str(&read_file(manifest)?).map_err(|e| parse_error(manifest, e))?;
+
+    let templates = TemplatePool::new();
+    let mut failures = Vec::new();
+    for job in jobs.iter() {
+        let mut job_args = args.clone();
+        job_args.manifest = None;
+        // The job picks its own glyph, none of the global ways to pick one may outrank it
+        job_args.codepoint = job.codepoint.iter().cloned().collect();
+        job_args.glyph_name = job.glyph_name.clone();
+        job_args.icon = job.icon.iter().cloned().collect();
+        job_args.character = None;
+        job_args.gid = None;
+        job_args.text = None;
+        job_args.emoji = None;
+        job_args.svg = None;
+        job_args.path = None;
+        if let Some(animation) = &job.animation {
+            let Ok(animation) = CliAnimation::from_str(animation, true) else {
+                failures.push((
+                    &job.out,
+                    Error::UnknownAnimation(animation.clone()).to_string(),
+                ));
+                continue;
+            };
+            job_args.animation = Some(animation);
+        }
+        if job.template.is_some() {
+            job_args.template = job.template.clone();
+        }
+        match merge_axes(&args.axes, &job.axes) {
+            Ok(axes) => job_args.axes = axes,
+            Err(e) => {
+                failures.push((&job.out, e.to_string()));
+                continue;
+            }
+        }
+        job_args.out_file = job.out.clone();
 
-    assert!(
-        args.codepoint.starts_with("0x"),
-        "Codepoint must start with 0x"
+        if job_args.animation.is_none() {
+            failures.push((&job.out, "no animation".to_string()));
+            continue;
+        }
+        if job_args.codepoint.is_empty()
+            && job_args.glyph_name.is_none()
+            && job_args.icon.is_empty()
+        {
+            failures.push((&job.out, "no codepoint, glyph_name or icon".to_string()));
+            continue;
+        }
+        if let Err(e) = generate(job_args, &templates) {
+            failures.push((&job.out, e.to_string()));
+        }
+    }
+
+    eprintln!(
+        "{} of {} jobs succeeded",
+        jobs.len() - failures.len(),
+        jobs.len()
     );
-    let codepoint = u32::from_str_radix(&args.codepoint[2..], 16).unwrap();
+    for (out, message) in failures.iter() {
+        eprintln!("  FAILED {out}: {message}");
+    }
+    if !failures.is_empty() {
+        return Err(Error::JobsFailed {
+            failed: failures.len(),
+            jobs: jobs.len(),
+        });
+    }
+    Ok(())
+}
+
+fn generate(args: Args, templates: &TemplatePool) -> Result<(), Error> {
+    // Drawings need no font
+    let drawing = match (&args.svg, &args.path) {
+        (Some(svg), _) => Some(read_svg_file(svg)?),
+        (None, Some(path)) => Some((path_icon(path)?, "path".to_string())),
+        (None, None) => None,
+    };
+    if let Some((svg_icon, icon)) = drawing {
+        let (out_file, lottie) = generate_drawing(&args, templates, &svg_icon, &icon)?;
+        return write_lotties(&args, templates, [Ok((out_file, icon, lottie))]);
+    }
 
-    let font_file = Path::new(args.font.as_str());
-    let font_bytes = fs::read(font_file).unwrap();
-    let font = FontRef::new(&font_bytes).unwrap();
-    let upem = font.head().unwrap().units_per_em() as f64;
-    let font_drawbox: Rect = (Point::ZERO, Point::new(upem, upem)).into();
-    let outline_loader = font.outline_glyphs();
+    // clap requires these when there's no subcommand
+    let font_bytes = match &args.family {
+        Some(family) => fetch_family(family)?,
+        None => read_font(
+            args.font
+                .as_deref()
+                .ok_or(Error::MissingArgument("--font or --family", "draw glyphs"))?,
+        )?,
+    };
+    let font = font_ref(&font_bytes, args.font_index)?;
+    let location: DesignLocation = args.axes.join(",").parse()?;
 
-    let gid = font
-        .charmap()
-        .map(codepoint)
-        .unwrap_or_else(|| panic!("No gid for 0x{codepoint:04x}"));
-    let glyph = outline_loader
-        .get(gid)
-        .unwrap_or_else(|| panic!("No outline for 0x{codepoint:04x} (gid {gid})"));
+    let selected = selected_gids(&font, &args, &location)?;
+    let many = selected.len() > 1;
+    // Documents back to back on stdout can't be told apart; a .lottie holds them all
+    if many
+        && args.out_file == STDIO
+        && OutputFormat::for_args(&args, &args.out_file) != OutputFormat::DotLottie
+    {
+        return Err(Error::ManyToStdout(selected.len()));
+    }
+    let lotties = selected.into_iter().map(|(gid, icon)| {
+        let (out_file, lottie) =
+            generate_one(&args, templates, &font, &location, gid, &icon, many)?;
+        Ok((out_file, icon, lottie))
+    });
+    write_lotties(&args, templates, lotties)
+}
+
+/// Write each Lottie, with where it should go and the icon it animates, as --out-file and
+/// --format say: every icon into the one .lottie or each to a file of its own
+fn write_lotties(
+    args: &Args,
+    templates: &TemplatePool,
+    lotties: impl IntoIterator<Item = Result<(String, String, Lottie), Error>>,
+) -> Result<(), Error> {
+    if OutputFormat::for_args(args, &args.out_file) == OutputFormat::DotLottie {
+        let options = output_options(args, templates, true)?;
+        let mut bundle = DotLottieSink::new(Cursor::new(Vec::new()));
+        if args.interactivity {
+            bundle = bundle.with_interactivity();
+        }
+        for generated in lotties {
+            let (_, icon, lottie) = generated?;
+            check_schema(args, templates, &icon, &lottie)?;
+            bundle.write(&icon, to_json(&lottie, &options)?.as_bytes())?;
+        }
+        let bytes = bundle.into_inner()?.into_inner();
+        return write_output(&args.out_file, &bytes);
+    }
+
+    for generated in lotties {
+        let (out_file, icon, lottie) = generated?;
+        check_schema(args, templates, &icon, &lottie)?;
+        let options = output_options(args, templates, args.compact)?;
+        let format = OutputFormat::for_args(args, &out_file);
+        let bytes = match format {
+            OutputFormat::Json | OutputFormat::DotLottie => {
+                to_json(&lottie, &options)?.into_bytes()
+            }
+            OutputFormat::Tgs => to_tgs(&lottie, &options)?,
+            OutputFormat::Svg => to_animated_svg(&lottie, &options)?.into_bytes(),
+            OutputFormat::Css => {
+                let animation = to_css_animation(&lottie, &css_prefix(&icon), &options)?;
+                // The stylesheet goes to out_file, the SVG it animates beside it
+                let svg_file = Path::new(&out_file).with_extension("svg");
+                write_output(&svg_file.to_string_lossy(), animation.svg.as_bytes())?;
+                animation.css.into_bytes()
+            }
+            OutputFormat::Gif => {
+                iconimation::raster::gif(&lottie, args.preview_size, args.preview_fps)?
+            }
+            OutputFormat::Apng => {
+                iconimation::raster::apng(&lottie, args.preview_size, args.preview_fps)?
+            }
+            OutputFormat::Webp => webp(args, &lottie)?,
+            OutputFormat::Mp4 | OutputFormat::Webm => video(args, &lottie, format)?,
+            OutputFormat::SpriteSheet => {
+                let sheet = iconimation::raster::sprite_sheet(
+                    &lottie,
+                    args.sprite_frames,
+                    args.preview_size,
+                )?;
+                // The sheet goes to out_file, its index beside it
+                let index_file = Path::new(&out_file).with_extension("json");
+                let index = serde_json::to_string_pretty(&sheet.index).map_err(Error::JsonError)?;
+                write_output(&index_file.to_string_lossy(), index.as_bytes())?;
+                sheet.png
+            }
+        };
+        write_output(&out_file, &bytes)?;
+    }
+    Ok(())
+}
+
+/// How to write Lottie as the args say, restoring what bodymovin dropped from --template
+fn output_options(
+    args: &Args,
+    templates: &TemplatePool,
+    compact: bool,
+) -> Result<OutputOptions, Error> {
+    Ok(OutputOptions {
+        compact,
+        precision: args.precision,
+        slots: !args.no_slots,
+        optimize: args.optimize,
+        template: match &args.template {
+            Some(template) => Some(templates.get(template)?),
+            None => None,
+        },
+    })
+}
+
+/// Report where `lottie`, as written, breaks the schema of --check-schema and fail if it does
+#[cfg_attr(not(feature = "schema"), allow(unused_variables))]
+fn check_schema(
+    args: &Args,
+    templates: &TemplatePool,
+    icon: &str,
+    lottie: &Lottie,
+) -> Result<(), Error> {
+    let Some(schema_file) = &args.check_schema else {
+        return Ok(());
+    };
+    #[cfg(feature = "schema")]
+    {
+        let schema = serde_json::from_str(&read_file(schema_file)?)
+            .map_err(|e| parse_error(schema_file, e))?;
+        let options = output_options(args, templates, true)?;
+        let json = serde_json::from_str(&to_json(lottie, &options)?).map_err(Error::JsonError)?;
+        let violations = iconimation::schema::schema_violations(&json, &schema)?;
+        if violations.is_empty() {
+            return Ok(());
+        }
+        for violation in violations.iter() {
+            eprintln!("{icon}: {} {}", violation.path, violation.message);
+        }
+        Err(Error::SchemaViolations {
+            icon: icon.to_string(),
+            violations: violations.len(),
+        })
+    }
+    #[cfg(not(feature = "schema"))]
+    Err(Error::FeatureDisabled(
+        format!("--check-schema {schema_file}"),
+        "schema",
+    ))
+}
+
+/// `lottie` as a looping WebP, as --preview-size and --preview-fps say
+#[cfg_attr(not(feature = "webp"), allow(unused_variables))]
+fn webp(args: &Args, lottie: &Lottie) -> Result<Vec<u8>, Error> {
+    #[cfg(feature = "webp")]
+    {
+        iconimation::raster::webp(lottie, args.preview_size, args.preview_fps)
+    }
+    #[cfg(not(feature = "webp"))]
+    Err(Error::FeatureDisabled("webp output".to_string(), "webp"))
+}
+
+/// `lottie` as video, `format` being mp4 or webm, as --preview-size, --preview-fps and
+/// --video-background say
+#[cfg_attr(not(feature = "video"), allow(unused_variables))]
+fn video(args: &Args, lottie: &Lottie, format: OutputFormat) -> Result<Vec<u8>, Error> {
+    #[cfg(feature = "video")]
+    {
+        use iconimation::video::{to_video, Container, VideoOptions};
+        let options = VideoOptions {
+            container: match format {
+                OutputFormat::Webm => Container::WebM,
+                _ => Container::Mp4,
+            },
+            size: args.preview_size,
+            frame_rate: args.preview_fps,
+            background: args.video_background.parse()?,
+        };
+        to_video(lottie, &options)
+    }
+    #[cfg(not(feature = "video"))]
+    Err(Error::FeatureDisabled("video output".to_string(), "video"))
+}
+
+/// A CSS class name for `icon`, such as icon-e855
+fn css_prefix(icon: &str) -> String {
+    let name: String = icon
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("icon-{name}")
+}
+
+fn write_output(out_file: &str, bytes: &[u8]) -> Result<(), Error> {
+    let (mut sink, name) = sink_for(out_file)?;
+    sink.write(&name, bytes)?;
+    sink.finish()?;
+    eprintln!("Wrote {out_file}");
+    Ok(())
+}
+
+/// The animation the semantic rules pick for the glyph, by its post table name or else `icon`
+fn semantic_animation(
+    args: &Args,
+    font: &FontRef,
+    gid: GlyphId,
+    icon: &str,
+) -> Result<Animation, Error> {
+    let post = font.post().ok();
+    let name = post
+        .as_ref()
+        .and_then(|post| post.glyph_name(gid))
+        .unwrap_or(icon);
+    semantic_animation_for(args, name)
+}
+
+/// The animation the semantic rules pick for an icon called `name`, pulse-whole if none do
+fn semantic_animation_for(args: &Args, name: &str) -> Result<Animation, Error> {
+    let mut semantics = Semantics::builtin();
+    if let Some(overrides) = &args.semantics {
+        let overrides = read_file(overrides)?
+            .parse()
+            .map_err(|e| parse_error(overrides, e))?;
+        semantics = semantics.with_overrides(overrides);
+    }
+    Ok(semantics
+        .animation_for(name)?
+        .unwrap_or(Animation::PulseWhole))
+}
+
+/// Where to write an output for `icon`, given a path that may contain "{icon}"
+///
+/// When generating `many` icons and there's no "{icon}", it goes before the extension.
+fn output_path(pattern: &str, icon: &str, many: bool) -> String {
+    if pattern == STDIO {
+        return pattern.to_string();
+    }
+    if pattern.contains("{icon}") {
+        return pattern.replace("{icon}", icon);
+    }
+    if !many {
+        return pattern.to_string();
+    }
+    let path = Path::new(pattern);
+    match (path.file_stem(), path.extension()) {
+        (Some(stem), Some(ext)) => path
+            .with_file_name(format!(
+                "{}-{icon}.{}",
+                stem.to_string_lossy(),
+                ext.to_string_lossy()
+            ))
+            .to_string_lossy()
+            .into_owned(),
+        _ => format!("{pattern}-{icon}"),
+    }
+}
+
+/// --text laid out on a line, shaped if --shape was given
+fn text_line(
+    args: &Args,
+    font: &FontRef,
+    text: &str,
+    location: &DesignLocation,
+) -> Result<Line, Error> {
+    if args.shape {
+        #[cfg(feature = "shaping")]
+        return iconimation::text::shape(font, text, location, args.direction.into());
+        #[cfg(not(feature = "shaping"))]
+        return Err(Error::FeatureDisabled("--shape".to_string(), "shaping"));
+    }
+    layout_by_advance(font, text, location, args.direction.into())
+}
+
+/// Animate one glyph, labelled `icon` in output names, writing anything requested besides the
+/// Lottie; returns where the Lottie should go and the Lottie
+fn generate_one(
+    args: &Args,
+    templates: &TemplatePool,
+    font: &FontRef,
+    location: &DesignLocation,
+    gid: GlyphId,
+    icon: &str,
+    many: bool,
+) -> Result<(String, Lottie), Error> {
+    let out_file = &output_path(&args.out_file, icon, many);
+    let font_drawbox = font_drawbox(font)?;
+    let swap_to = match args.swap_to.as_deref() {
+        Some(swap_to) => Some(gid_for_codepoint(
+            font,
+            iconimation::parse_codepoint(swap_to)?,
+        )?),
+        None => None,
+    };
+
+    // Text draws every glyph as its outline regardless
+    if args.text.is_none() {
+        if let Some(e) = Glyph::new(font, gid, location)?.unusable_svg() {
+            eprintln!("Drawing the outline of {icon}: {e}");
+        }
+    }
 
     if args.debug {
-        let mut pen = DebugPen::new(Rect::new(0.0, 0.0, upem, upem));
-        glyph
-            .draw(skrifa::instance::Size::unscaled(), &mut pen)
-            .unwrap();
-        let debug_out = Path::new(&args.out_file).with_extension("svg");
-        fs::write(debug_out, pen.to_svg()).unwrap();
-        eprintln!("Wrote debug svg {}", args.out_file);
+        let glyph = Glyph::new(font, gid, location)?;
+        let mut pen = DebugPen::new(font_drawbox);
+        glyph.draw(&mut pen)?;
+        let debug_out = Path::new(out_file).with_extension("svg");
+
+        // Show how subpaths would pair up between the two glyphs
+        if let Some(swap_to) = swap_to {
+            let mut to_pen = DebugPen::new(font_drawbox);
+            Glyph::new(font, swap_to, location)?.draw(&mut to_pen)?;
+            let mut from_pen = DebugPen::new(font_drawbox);
+            glyph.draw(&mut from_pen)?;
+            let correspondence_out = debug_out.with_extension("correspondence.svg");
+            write_file(&correspondence_out, from_pen.to_correspondence_svg(to_pen))?;
+            eprintln!("Wrote correspondence svg {correspondence_out:?}");
+        }
+
+        write_file(debug_out, pen.to_svg())?;
+        eprintln!("Wrote debug svg {out_file}");
     }
 
-    let mut lottie = if let Some(template) = args.template {
-        Lottie::load(template).expect("Unable to load custom template")
-    } else {
-        default_template(&font_drawbox)
+    let animation = args
+        .animation
+        .as_ref()
+        .ok_or(Error::MissingArgument("--animation", "animate"))?
+        .to_lib(gid, swap_to, args, || {
+            semantic_animation(args, font, gid, icon)
+        })?;
+    let style = style(args)?;
+    let placement = placement(args)?;
+    let line = match &args.text {
+        Some(text) => Some(text_line(args, font, text, location)?),
+        None => None,
+    };
+    let lottie_for = |template| match &line {
+        Some(line) => lottie_for_text(
+            font, line, location, template, &animation, &style, &placement,
+        ),
+        None => lottie_for_glyph(
+            font, gid, location, template, &animation, &style, &placement,
+        ),
+    };
+    let lottie = animate_into_templates(args, templates, out_file, icon, many, lottie_for)?;
+    Ok((out_file.clone(), lottie))
+}
+
+/// The icon in the SVG file `svg` and its name, the file's stem
+fn read_svg_file(svg: &str) -> Result<(SvgIcon, String), Error> {
+    let data = fs::read(svg).map_err(|source| Error::ReadFile {
+        path: svg.to_string(),
+        source,
+    })?;
+    let svg_icon = read_svg(&data).map_err(|e| parse_error(svg, e))?;
+    let icon = Path::new(svg)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| svg.to_string());
+    Ok((svg_icon, icon))
+}
+
+/// Animate a drawing, from --svg or --path, labelled `icon`; returns where the Lottie should
+/// go and the Lottie
+fn generate_drawing(
+    args: &Args,
+    templates: &TemplatePool,
+    svg_icon: &SvgIcon,
+    icon: &str,
+) -> Result<(String, Lottie), Error> {
+    let out_file = output_path(&args.out_file, icon, false);
+
+    let animation = args
+        .animation
+        .as_ref()
+        .ok_or(Error::MissingArgument("--animation", "animate"))?
+        .to_lib(GlyphId::NOTDEF, None, args, || {
+            semantic_animation_for(args, icon)
+        })?;
+    let style = style(args)?;
+    let placement = placement(args)?;
+    let lottie = animate_into_templates(args, templates, &out_file, icon, false, |template| {
+        lottie_for_svg(svg_icon, icon, template, &animation, &style, &placement)
+    })?;
+    Ok((out_file, lottie))
+}
+
+/// Animate into --template, and beside --compare-template if given, with `lottie_for`, writing
+/// any thumbnail and timing report asked for
+fn animate_into_templates(
+    args: &Args,
+    templates: &TemplatePool,
+    out_file: &str,
+    icon: &str,
+    many: bool,
+    lottie_for: impl Fn(Option<Lottie>) -> Result<Lottie, Error>,
+) -> Result<Lottie, Error> {
+    let template = match &args.template {
+        Some(template) => Some(templates.instantiate(template)?),
+        None => None,
     };
+    let mut lottie = lottie_for(template)?;
+
+    if let Some(compare_template) = &args.compare_template {
+        let other = lottie_for(Some(templates.instantiate(compare_template)?))?;
+        lottie = side_by_side(&lottie, &other)?;
+    }
+
+    if let Some(thumbnail) = &args.thumbnail {
+        let thumbnail = output_path(thumbnail, icon, many);
+        let png =
+            iconimation::raster::thumbnail(&lottie, args.thumbnail_frame, args.thumbnail_size)?;
+        write_output(&thumbnail, &png)?;
+    }
+
+    if let Some(seconds) = args.snapshot {
+        let snapshot_file = output_path(&args.snapshot_file, icon, many);
+        let frame = snapshot(&lottie, seconds)?;
+        let bytes = if snapshot_file.ends_with(".png") {
+            iconimation::raster::png(&frame, args.preview_size, args.preview_size)?
+        } else {
+            frame.to_svg().into_bytes()
+        };
+        write_output(&snapshot_file, &bytes)?;
+    }
 
-    let animation = args.animation.to_lib();
-    lottie
-        .replace_shape(&font_drawbox, &glyph, animation.animator().as_ref())
-        .expect("Failed to replace shape");
+    if args.timing_report {
+        let report = iconimation::report::timing_table(&lottie)?;
+        // Keep stdout for the Lottie when that's where it's going
+        if out_file == STDIO {
+            eprint!("{report}");
+        } else {
+            print!("{report}");
+        }
+    }
 
-    fs::write(
-        &args.out_file,
-        serde_json::to_string_pretty(&lottie).unwrap(),
-    )
-    .unwrap();
-    eprintln!("Wrote {}", args.out_file);
+    Ok(lottie)
 }
@@ -0,0 +1,79 @@
+//! A structural diff of two Lottie files, as json.
+//!
+//! Objects are compared key by key, so key order doesn't matter, arrays item by item and
+//! numbers within a tolerance, so float noise from a round trip doesn't show. Changes are
+//! listed by path, with layer and shape names alongside indices so they can be found.
+
+use serde_json::Value;
+
+/// How long a value may print before it's cut short
+const MAX_VALUE_CHARS: usize = 60;
+
+/// Every change from `before` to `after`, one line each, such as
+/// `~ layers[0] "icon".ks.o.k: 100 -> 50`; empty if they're the same
+pub fn diff(before: &Value, after: &Value, tolerance: f64) -> Vec<String> {
+    let mut changes = Vec::new();
+    walk("", before, after, tolerance, &mut changes);
+    changes
+}
+
+fn walk(path: &str, before: &Value, after: &Value, tolerance: f64, changes: &mut Vec<String>) {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut keys: Vec<_> = before.keys().chain(after.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let path = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match (before.get(key), after.get(key)) {
+                    (Some(before), Some(after)) => walk(&path, before, after, tolerance, changes),
+                    (Some(before), None) => changes.push(format!("- {path}: {}", show(before))),
+                    (None, Some(after)) => changes.push(format!("+ {path}: {}", show(after))),
+                    (None, None) => unreachable!("keys come from one or the other"),
+                }
+            }
+        }
+        (Value::Array(before), Value::Array(after)) => {
+            for i in 0..before.len().max(after.len()) {
+                let item = before.get(i).or(after.get(i)).unwrap();
+                let path = match item["nm"].as_str() {
+                    Some(name) => format!("{path}[{i}] {name:?}"),
+                    None => format!("{path}[{i}]"),
+                };
+                match (before.get(i), after.get(i)) {
+                    (Some(before), Some(after)) => walk(&path, before, after, tolerance, changes),
+                    (Some(before), None) => changes.push(format!("- {path}: {}", show(before))),
+                    (None, Some(after)) => changes.push(format!("+ {path}: {}", show(after))),
+                    (None, None) => unreachable!("i is within one or the other"),
+                }
+            }
+        }
+        (Value::Number(b), Value::Number(a)) => {
+            let (b, a) = (
+                b.as_f64().unwrap_or_default(),
+                a.as_f64().unwrap_or_default(),
+            );
+            if (b - a).abs() > tolerance {
+                changes.push(format!("~ {path}: {b} -> {a}"));
+            }
+        }
+        _ if before != after => {
+            changes.push(format!("~ {path}: {} -> {}", show(before), show(after)));
+        }
+        _ => (),
+    }
+}
+
+/// `value` as compact json, cut short if it's long
+fn show(value: &Value) -> String {
+    let json = value.to_string();
+    if json.chars().count() <= MAX_VALUE_CHARS {
+        return json;
+    }
+    let cut: String = json.chars().take(MAX_VALUE_CHARS).collect();
+    format!("{cut}…")
+}
@@ -1,21 +1,85 @@
 //! Load a lottie file and dump it.
 //!
-//! Useful to determine if that alone trashes a template.
+//! Useful to determine if that alone trashes a template. The diff subcommand compares two
+//! files, such as a template before and after an edit, and stats shows what one is made of.
 use std::{fs, path::Path};
 
 use bodymovin::Bodymovin as Lottie;
+use clap::{Parser, Subcommand};
+use serde_json::Value;
+
+mod diff;
+mod stats;
+
+#[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Lottie files to load and write back out pretty printed, beside them as name-pretty.json
+    files: Vec<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print what changed from one Lottie file to another, by path, ignoring key order and
+    /// exiting with 1 if anything did
+    Diff {
+        before: String,
+        after: String,
+        /// How far apart numbers may be and still count as the same
+        #[arg(long, default_value_t = 1e-3)]
+        tolerance: f64,
+    },
+    /// Print counts of layers, shapes and keyframes, the duration, and how many bytes each layer
+    /// and asset takes, biggest first
+    Stats { files: Vec<String> },
+}
 
 fn main() {
-    for lottie_file in std::env::args().skip(1) {
-        let lottie_file = Path::new(&lottie_file);
-        let lottie = Lottie::load(lottie_file)
-            .unwrap_or_else(|e| panic!("Unable to load {lottie_file:?}: {e}"));
-        let out_file = lottie_file.with_file_name(format!(
-            "{}-pretty.{}",
-            lottie_file.file_stem().unwrap().to_str().unwrap(),
-            lottie_file.extension().unwrap().to_str().unwrap()
-        ));
-        fs::write(&out_file, serde_json::to_string_pretty(&lottie).unwrap()).unwrap();
-        eprintln!("Wrote {out_file:?}");
+    let args = Args::parse();
+    match args.command {
+        Some(Command::Diff {
+            before,
+            after,
+            tolerance,
+        }) => {
+            let changes = diff::diff(&load_json(&before), &load_json(&after), tolerance);
+            for change in changes.iter() {
+                println!("{change}");
+            }
+            if !changes.is_empty() {
+                std::process::exit(1);
+            }
+        }
+        Some(Command::Stats { files }) => {
+            for file in files.iter() {
+                if files.len() > 1 {
+                    println!("{file}");
+                }
+                print!("{}", stats::stats(&load_json(file)));
+            }
+        }
+        None => args.files.iter().for_each(|file| pretty_print(file)),
     }
 }
+
+/// The json of `file`, as is rather than through bodymovin so fields it drops still count
+fn load_json(file: &str) -> Value {
+    let json = fs::read_to_string(file).unwrap_or_else(|e| panic!("Unable to read {file}: {e}"));
+    serde_json::from_str(&json).unwrap_or_else(|e| panic!("Unable to parse {file}: {e}"))
+}
+
+fn pretty_print(lottie_file: &str) {
+    let lottie_file = Path::new(lottie_file);
+    let lottie =
+        Lottie::load(lottie_file).unwrap_or_else(|e| panic!("Unable to load {lottie_file:?}: {e}"));
+    let out_file = lottie_file.with_file_name(format!(
+        "{}-pretty.{}",
+        lottie_file.file_stem().unwrap().to_str().unwrap(),
+        lottie_file.extension().unwrap().to_str().unwrap()
+    ));
+    fs::write(&out_file, serde_json::to_string_pretty(&lottie).unwrap()).unwrap();
+    eprintln!("Wrote {out_file:?}");
+}
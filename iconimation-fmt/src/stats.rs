@@ -0,0 +1,138 @@
+//! What a Lottie file is made of, to see why it's as big as it is.
+//!
+//! Counts layers, shapes, animated properties and keyframes, and how many bytes of compact
+//! json each layer and asset takes.
+
+use std::fmt::{self, Display};
+
+use serde_json::Value;
+
+/// Counts for a whole file, or a part of one
+#[derive(Clone, Debug, Default)]
+pub struct Counts {
+    pub layers: usize,
+    pub shapes: usize,
+    pub animated_properties: usize,
+    pub keyframes: usize,
+    /// Compact json
+    pub bytes: usize,
+}
+
+impl Counts {
+    fn of(value: &Value) -> Counts {
+        let mut counts = Counts {
+            bytes: value.to_string().len(),
+            ..Default::default()
+        };
+        counts.add(value);
+        counts
+    }
+
+    fn add(&mut self, value: &Value) {
+        match value {
+            Value::Object(object) => {
+                // Layers have a numeric type, shapes a string one
+                match object.get("ty") {
+                    Some(Value::Number(..)) => self.layers += 1,
+                    Some(Value::String(..)) => self.shapes += 1,
+                    _ => (),
+                }
+                if object.get("a").and_then(Value::as_i64) == Some(1) {
+                    self.animated_properties += 1;
+                    self.keyframes += object["k"].as_array().map(Vec::len).unwrap_or_default();
+                }
+                object.values().for_each(|value| self.add(value));
+            }
+            Value::Array(values) => values.iter().for_each(|value| self.add(value)),
+            _ => (),
+        }
+    }
+}
+
+/// A part of the file, such as a layer or an asset, and its counts
+#[derive(Clone, Debug)]
+pub struct Part {
+    pub name: String,
+    pub counts: Counts,
+}
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    pub width: f64,
+    pub height: f64,
+    pub frame_rate: f64,
+    /// In seconds
+    pub duration: f64,
+    pub total: Counts,
+    pub layers: Vec<Part>,
+    pub assets: Vec<Part>,
+}
+
+pub fn stats(lottie: &Value) -> Stats {
+    let number = |key: &str| lottie[key].as_f64().unwrap_or_default();
+    let frame_rate = number("fr");
+    let duration = if frame_rate > 0.0 {
+        (number("op") - number("ip")) / frame_rate
+    } else {
+        0.0
+    };
+    let parts = |key: &str, kind: &str| -> Vec<Part> {
+        let empty = Vec::new();
+        lottie[key]
+            .as_array()
+            .unwrap_or(&empty)
+            .iter()
+            .enumerate()
+            .map(|(i, part)| Part {
+                name: match (part["nm"].as_str(), part["id"].as_str()) {
+                    (Some(name), _) | (None, Some(name)) => format!("{kind} {i} {name:?}"),
+                    (None, None) => format!("{kind} {i}"),
+                },
+                counts: Counts::of(part),
+            })
+            .collect()
+    };
+    Stats {
+        width: number("w"),
+        height: number("h"),
+        frame_rate,
+        duration,
+        total: Counts::of(lottie),
+        layers: parts("layers", "layer"),
+        assets: parts("assets", "asset"),
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}x{}, {}s at {} fps",
+            self.width, self.height, self.duration, self.frame_rate
+        )?;
+        writeln!(
+            f,
+            "{:>10} {:>7} {:>7} {:>9} {:>10}",
+            "bytes", "layers", "shapes", "animated", "keyframes"
+        )?;
+        let row = |f: &mut fmt::Formatter<'_>, name: &str, counts: &Counts| {
+            writeln!(
+                f,
+                "{:>10} {:>7} {:>7} {:>9} {:>10}  {name}",
+                counts.bytes,
+                counts.layers,
+                counts.shapes,
+                counts.animated_properties,
+                counts.keyframes
+            )
+        };
+        row(f, "total", &self.total)?;
+        // Biggest first, they're what to look at
+        let mut parts: Vec<_> = self.layers.iter().chain(self.assets.iter()).collect();
+        parts.sort_by_key(|part| std::cmp::Reverse(part.counts.bytes));
+        for part in parts {
+            row(f, &part.name, &part.counts)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+//! Generate animations at compile time and embed them as a `&'static str`.
+//!
+//! For Rust apps that bundle a handful of animated icons and would rather not ship a font.
+//!
+//! ```ignore
+//! const ALARM: &str = iconimation_macros::include_icon_animation!(
+//!     "fonts/MaterialSymbolsOutlined.ttf",
+//!     "alarm",
+//!     "pulse-parts"
+//! );
+//! ```
+
+use std::{fs, path::PathBuf, str::FromStr};
+
+use iconimation::{
+    animate::Animation, glyph_id_for_name, lottie_for_glyph, placement::Placement, style::Style,
+    DesignLocation,
+};
+use proc_macro::{Literal, TokenStream, TokenTree};
+use skrifa::{raw::FontRef, GlyphId, MetadataProvider};
+
+/// `include_icon_animation!(font, icon, animation)` expands to the Lottie JSON, as a string literal.
+///
+/// * `font` is a path relative to the manifest of the crate using the macro
+/// * `icon` is a codepoint ("0xe855"), a single character, or a glyph name ("alarm")
+/// * `animation` is the kebab-case name of an [Animation], such as "pulse-parts"
+#[proc_macro]
+pub fn include_icon_animation(input: TokenStream) -> TokenStream {
+    match expand(input) {
+        Ok(tokens) => tokens,
+        Err(msg) => format!("compile_error!({msg:?})").parse().unwrap(),
+    }
+}
+
+fn expand(input: TokenStream) -> Result<TokenStream, String> {
+    let args = string_args(input)?;
+    let [font_file, icon, animation] = args.as_slice() else {
+        return Err(format!(
+            "Expected (font, icon, animation), got {} argument(s)",
+            args.len()
+        ));
+    };
+
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").map_err(|e| e.to_string())?;
+    let font_file = PathBuf::from(manifest_dir).join(font_file);
+    let font_bytes =
+        fs::read(&font_file).map_err(|e| format!("Unable to read {font_file:?}: {e}"))?;
+    let font = FontRef::new(&font_bytes).map_err(|e| format!("Unable to load font: {e}"))?;
+
+    let gid = resolve_icon(&font, icon)?;
+    let animation = Animation::from_str(animation).map_err(|e| e.to_string())?;
+    let lottie = lottie_for_glyph(
+        &font,
+        gid,
+        &DesignLocation::default(),
+        None,
+        &animation,
+        &Style::default(),
+        &Placement::default(),
+    )
+    .map_err(|e| e.to_string())?;
+    let json = serde_json::to_string(&lottie).map_err(|e| e.to_string())?;
+
+    // include_bytes! so the embedding crate rebuilds when the font changes
+    format!(
+        "{{ const _: &[u8] = include_bytes!({}); {} }}",
+        Literal::string(font_file.to_str().ok_or("Font path must be utf-8")?),
+        Literal::string(&json)
+    )
+    .parse()
+    .map_err(|e| format!("{e:?}"))
+}
+
+/// Comma separated string literals, trailing comma permitted
+fn string_args(input: TokenStream) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for token in input {
+        match token {
+            TokenTree::Literal(lit) => {
+                let lit = lit.to_string();
+                let Some(value) = lit.strip_prefix('"').and_then(|lit| lit.strip_suffix('"'))
+                else {
+                    return Err(format!("Expected a string literal, got {lit}"));
+                };
+                if value.contains('\\') {
+                    return Err(format!("Escapes are not supported: {lit}"));
+                }
+                args.push(value.to_string());
+            }
+            TokenTree::Punct(punct) if punct.as_char() == ',' => (),
+            other => return Err(format!("Unexpected {other}")),
+        }
+    }
+    Ok(args)
+}
+
+/// A codepoint, read as --codepoint reads it, or else a glyph name
+fn resolve_icon(font: &FontRef, icon: &str) -> Result<GlyphId, String> {
+    match iconimation::parse_codepoint(icon) {
+        Ok(codepoint) => font
+            .charmap()
+            .map(codepoint)
+            .ok_or_else(|| format!("No gid for 0x{codepoint:04x}")),
+        Err(_) => glyph_id_for_name(font, icon)
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("No glyph named {icon:?}")),
+    }
+}
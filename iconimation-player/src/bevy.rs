@@ -0,0 +1,134 @@
+//! Load Lottie files as Bevy assets and play them on entities.
+//!
+//! Add [IconimationPlugin], then spawn a [LottiePlayback] with a handle from the asset server.
+//! Each update the playback advances by the frame time and holds the [Frame] to draw; drawing
+//! is left to the app, [crate::polygons] feeds a tessellator such as `bevy_prototype_lyon`.
+//!
+//! ```ignore
+//! commands.spawn(LottiePlayback::new(asset_server.load("home.lottie.json")));
+//! ```
+
+use ::bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use bodymovin::Bodymovin as Lottie;
+use iconimation::{error::Error, evaluate::Frame};
+
+use crate::Player;
+
+/// Registers [LottieAsset], its loader and the system that advances [LottiePlayback]s
+pub struct IconimationPlugin;
+
+impl Plugin for IconimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LottieAsset>()
+            .init_asset_loader::<LottieLoader>()
+            .add_systems(Update, advance_playback);
+    }
+}
+
+/// A Lottie animation, as loaded from a .lottie.json file
+#[derive(Asset, TypePath)]
+pub struct LottieAsset {
+    pub lottie: Lottie,
+}
+
+/// Reads .lottie.json files into [LottieAsset]s
+#[derive(Default)]
+pub struct LottieLoader;
+
+impl AssetLoader for LottieLoader {
+    type Asset = LottieAsset;
+    type Settings = ();
+    type Error = Error;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<LottieAsset, Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader
+                .read_to_end(&mut bytes)
+                .await
+                .map_err(Error::IoError)?;
+            let lottie = serde_json::from_slice(&bytes).map_err(Error::JsonError)?;
+            Ok(LottieAsset { lottie })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        // Plain .json is left to loaders of other json assets
+        &["lottie.json"]
+    }
+}
+
+/// Plays a [LottieAsset] once it has loaded
+#[derive(Component)]
+pub struct LottiePlayback {
+    pub animation: Handle<LottieAsset>,
+    /// Whether to wrap around at the end rather than holding the last frame
+    pub looping: bool,
+    /// Playback rate, 1.0 is normal speed
+    pub speed: f64,
+    player: Option<Player>,
+    frame: Option<Frame>,
+}
+
+impl LottiePlayback {
+    pub fn new(animation: Handle<LottieAsset>) -> Self {
+        LottiePlayback {
+            animation,
+            looping: true,
+            speed: 1.0,
+            player: None,
+            frame: None,
+        }
+    }
+
+    /// The frame to draw, None until the animation has loaded
+    pub fn frame(&self) -> Option<&Frame> {
+        self.frame.as_ref()
+    }
+
+    /// The player, None until the animation has loaded
+    pub fn player(&mut self) -> Option<&mut Player> {
+        self.player.as_mut()
+    }
+}
+
+/// Start playing animations that have loaded, and advance those playing by the frame time
+fn advance_playback(
+    time: Res<Time>,
+    animations: Res<Assets<LottieAsset>>,
+    mut playbacks: Query<&mut LottiePlayback>,
+) {
+    for mut playback in playbacks.iter_mut() {
+        let playback = &mut *playback;
+        if playback.player.is_none() {
+            let Some(animation) = animations.get(&playback.animation) else {
+                continue;
+            };
+            match Player::new(&animation.lottie) {
+                Ok(player) => playback.player = Some(player),
+                Err(e) => {
+                    // Don't retry every update, the asset won't get any better
+                    error!("Unable to play {:?}: {e}", playback.animation);
+                    playback.animation = Handle::default();
+                    continue;
+                }
+            }
+        }
+        let Some(player) = playback.player.as_mut() else {
+            continue;
+        };
+        player.looping = playback.looping;
+        player.speed = playback.speed;
+        playback.frame = Some(player.tick(time.delta_seconds_f64()));
+    }
+}
@@ -0,0 +1,64 @@
+//! Show animations in egui, each frame drawn as an SVG image.
+//!
+//! Needs an svg image loader, such as `egui_extras::install_image_loaders` with its svg feature.
+//!
+//! ```ignore
+//! // In App::update, with the player kept in the app's state
+//! ui.add(LottieImage::new(&mut self.player, "home").size(egui::vec2(96.0, 96.0)));
+//! ```
+
+use ::egui::{load::Bytes, Id, Image, ImageSource, Response, Ui, Vec2, Widget};
+use iconimation::evaluate::Frame;
+
+use crate::Player;
+
+/// The image of `frame`, named `uri` for egui's caches
+///
+/// The uri must end in .svg for svg loaders to pick it up, and egui keeps what it loaded for
+/// a uri until told to forget it, see [LottieImage] for how frames replace one another.
+pub fn image_source(frame: &Frame, uri: String) -> ImageSource<'static> {
+    ImageSource::Bytes {
+        uri: uri.into(),
+        bytes: Bytes::from(frame.to_svg().into_bytes()),
+    }
+}
+
+/// Plays a [Player] in a ui, advancing it by the frame time each time it's shown
+pub struct LottieImage<'a> {
+    player: &'a mut Player,
+    id: Id,
+    size: Option<Vec2>,
+}
+
+impl<'a> LottieImage<'a> {
+    /// `id_source` tells apart animations shown at once, as other egui ids do
+    pub fn new(player: &'a mut Player, id_source: impl std::hash::Hash) -> Self {
+        LottieImage {
+            player,
+            id: Id::new(id_source),
+            size: None,
+        }
+    }
+
+    /// Draw at `size` points, rather than the composition's size
+    pub fn size(mut self, size: Vec2) -> Self {
+        self.size = Some(size);
+        self
+    }
+}
+
+impl Widget for LottieImage<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let frame = self.player.tick(ui.input(|input| input.stable_dt) as f64);
+        let size = self
+            .size
+            .unwrap_or_else(|| Vec2::new(frame.width as f32, frame.height as f32));
+        // Each frame is a new image under the same uri, forget the last so it isn't shown again
+        let uri = format!("bytes://iconimation/{}.svg", self.id.short_debug_format());
+        ui.ctx().forget_image(&uri);
+        if !self.player.is_finished() {
+            ui.ctx().request_repaint();
+        }
+        ui.add(Image::new(image_source(&frame, uri)).fit_to_exact_size(size))
+    }
+}
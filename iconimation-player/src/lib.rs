@@ -0,0 +1,124 @@
+//! Play generated animations from a game or app update loop.
+//!
+//! Engine agnostic: each tick yields an [iconimation::evaluate::Frame], filled paths in
+//! composition units, which is easy to hand to whatever vector support the engine has.
+//!
+//! * egui: `Frame::to_svg` feeds `egui_extras`' svg image loader, see [egui::LottieImage] with
+//!   the egui feature
+//! * Bevy: [polygons] feeds a tessellator such as `bevy_prototype_lyon`; note Bevy is y-up. With
+//!   the bevy feature [bevy::IconimationPlugin] loads Lottie assets and plays them
+//!
+//! ```ignore
+//! // In a Bevy system, or an egui App::update
+//! let frame = player.tick(time.delta_seconds() as f64);
+//! ```
+
+#[cfg(feature = "bevy")]
+pub mod bevy;
+#[cfg(feature = "egui")]
+pub mod egui;
+
+use bodymovin::Bodymovin as Lottie;
+use iconimation::{
+    error::Error,
+    evaluate::{Evaluator, FilledPath, Frame},
+};
+use kurbo::{PathEl, Point};
+
+/// Tracks playback time and samples the animation for it
+pub struct Player {
+    evaluator: Evaluator,
+    /// Seconds since the start of the animation
+    time: f64,
+    /// Whether to wrap around at the end rather than holding the last frame
+    pub looping: bool,
+    /// Playback rate, 1.0 is normal speed
+    pub speed: f64,
+}
+
+impl Player {
+    pub fn new(lottie: &Lottie) -> Result<Self, Error> {
+        let evaluator = Evaluator::new(lottie)?;
+        // Negated so NaN is rejected too
+        let frame_rate = evaluator.frame_rate();
+        if !(frame_rate > 0.0 && frame_rate.is_finite()) {
+            return Err(Error::BadFrameRate(frame_rate));
+        }
+        Ok(Player {
+            evaluator,
+            time: 0.0,
+            looping: true,
+            speed: 1.0,
+        })
+    }
+
+    /// Length of the animation, in seconds
+    pub fn duration(&self) -> f64 {
+        (self.evaluator.out_point() - self.evaluator.in_point()) / self.evaluator.frame_rate()
+    }
+
+    /// Seconds since the start of the animation
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// True once a non-looping player reaches the end
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.time >= self.duration()
+    }
+
+    pub fn seek(&mut self, seconds: f64) {
+        let duration = self.duration();
+        self.time = if duration <= 0.0 {
+            0.0
+        } else if self.looping {
+            seconds.rem_euclid(duration)
+        } else {
+            seconds.clamp(0.0, duration)
+        };
+    }
+
+    /// Advance by `delta_seconds`, the time since the last update, and return the frame to draw
+    pub fn tick(&mut self, delta_seconds: f64) -> Frame {
+        self.seek(self.time + delta_seconds * self.speed);
+        self.frame()
+    }
+
+    /// The frame for the current time
+    pub fn frame(&self) -> Frame {
+        self.evaluator.frame(self.composition_frame(self.time))
+    }
+
+    /// Sample the whole animation at `fps`, for engines that would rather play baked frames
+    pub fn bake(&self, fps: f64) -> Result<Vec<Frame>, Error> {
+        if !(fps > 0.0 && fps.is_finite()) {
+            return Err(Error::BadFrameRate(fps));
+        }
+        let count = (self.duration() * fps).ceil().max(1.0) as usize;
+        Ok((0..count)
+            .map(|i| self.evaluator.frame(self.composition_frame(i as f64 / fps)))
+            .collect())
+    }
+
+    fn composition_frame(&self, seconds: f64) -> f64 {
+        self.evaluator.in_point() + seconds * self.evaluator.frame_rate()
+    }
+}
+
+/// Flatten a filled path to polygons, one per subpath, within `tolerance` composition units
+///
+/// Only the outline: engines painting solid colors take [FilledPath::color], near any gradient or
+/// image, and applying [FilledPath::clips] is up to them.
+pub fn polygons(fill: &FilledPath, tolerance: f64) -> Vec<Vec<Point>> {
+    let mut polygons: Vec<Vec<Point>> = Vec::new();
+    fill.path.flatten(tolerance, |el| match el {
+        PathEl::MoveTo(p) => polygons.push(vec![p]),
+        PathEl::LineTo(p) => {
+            if let Some(polygon) = polygons.last_mut() {
+                polygon.push(p);
+            }
+        }
+        _ => (),
+    });
+    polygons
+}
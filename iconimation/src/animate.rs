@@ -2,21 +2,89 @@
 //!
 //! Typically supports both a whole-icon and parts mode where parts animate offset slightly in time.
 
+use std::str::FromStr;
+
 use bodymovin::properties::{Bezier2d, BezierEase, MultiDimensionalKeyframe, Property};
 use bodymovin::properties::{ControlPoint2d, Value};
-use bodymovin::shapes::{AnyShape, Fill, Group, SubPath, Transform};
-use kurbo::{BezPath, PathEl, Point, Shape, Vec2};
+use bodymovin::shapes::{AnyShape, Group, SubPath, Transform};
+use kurbo::{BezPath, PathEl, Point, Rect, Shape, Vec2};
 use ordered_float::OrderedFloat;
+use serde::Deserialize;
+use serde_json::json;
+use skrifa::{setting::VariationSetting, GlyphId, Tag};
 
-use crate::Error;
+use crate::{
+    annotations::{PartAnimation, PartAnnotations},
+    correspondence::{align_start, match_subpaths},
+    effects::{add_mask, gaussian_blur},
+    shape_pen::animated_subpath,
+    style::FillStyle,
+    Error,
+};
 
 #[derive(Clone, Debug)]
 pub enum Animation {
     None,
     PulseWhole,
-    PulseParts,
+    PulseParts {
+        /// Scale thin parts less so their apparent stroke weight stays close to their siblings'
+        compensate_stroke: bool,
+        parts: PartOptions,
+    },
     TwirlWhole,
-    TwirlParts,
+    /// Swing back and forth from the top, like a ringing bell
+    Shake,
+    TwirlParts {
+        parts: PartOptions,
+    },
+    /// Morph the outline from Light to Bold by keyframing the wght axis
+    WeightMorph,
+    /// Morph Material Symbols from outlined to filled by keyframing the FILL axis
+    FillToggle,
+    /// Morph Material Symbols from rest to hover emphasis by keyframing the GRAD axis
+    ///
+    /// Grade thickens strokes without changing the glyph's footprint so there's no layout shift.
+    GradeHover,
+    /// Morph between two locations in design space, such as wght:100..700,FILL:0..1
+    AxisMorph {
+        ranges: Vec<AxisRange>,
+        /// How many locations to sample, including both ends
+        samples: usize,
+    },
+    /// Animate parts as declared in a companion config rather than as guessed
+    Annotated {
+        annotations: PartAnnotations,
+    },
+    /// Cross-fade between the ends of the ranges, such as wght:400..700
+    ///
+    /// A cheap alternative to [Animation::AxisMorph]: plays everywhere and
+    /// needs no point compatibility between the locations.
+    AxisFade {
+        ranges: Vec<AxisRange>,
+    },
+    /// Cross-fade from one glyph to another, with a slight change in scale
+    Swap {
+        from: GlyphId,
+        to: GlyphId,
+    },
+    /// Fade in from a strong blur, sharpening as it goes
+    BlurIn,
+    /// Uncover the icon by sliding a mask across it
+    Reveal {
+        direction: RevealDirection,
+    },
+    /// Uncover the icon through a circle, a track matte, growing from its center
+    CircleReveal,
+}
+
+/// Which way [Animation::Reveal] uncovers the icon
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RevealDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+    TopToBottom,
+    BottomToTop,
 }
 
 impl Animation {
@@ -24,13 +92,130 @@ impl Animation {
         match self {
             Animation::None => Box::new(Still),
             Animation::PulseWhole => Box::new(Pulse),
-            Animation::PulseParts => Box::new(PulseParts),
+            Animation::PulseParts {
+                compensate_stroke,
+                parts,
+            } => Box::new(PulseParts {
+                compensate_stroke: *compensate_stroke,
+                parts: parts.clone(),
+            }),
             Animation::TwirlWhole => Box::new(Twirl),
-            Animation::TwirlParts => Box::new(TwirlParts),
+            Animation::Shake => Box::new(Shake),
+            Animation::TwirlParts { parts } => Box::new(TwirlParts {
+                parts: parts.clone(),
+            }),
+            Animation::WeightMorph => Box::new(AxisMorph {
+                ranges: vec![AxisRange {
+                    axis: Tag::new(b"wght"),
+                    from: 300.0,
+                    to: 700.0,
+                }],
+                samples: MORPH_SAMPLES,
+            }),
+            Animation::FillToggle => Box::new(AxisMorph {
+                ranges: vec![AxisRange {
+                    axis: Tag::new(b"FILL"),
+                    from: 0.0,
+                    to: 1.0,
+                }],
+                samples: MORPH_SAMPLES,
+            }),
+            Animation::GradeHover => Box::new(AxisMorph {
+                ranges: vec![AxisRange {
+                    axis: Tag::new(b"GRAD"),
+                    from: 0.0,
+                    to: 200.0,
+                }],
+                samples: MORPH_SAMPLES,
+            }),
+            Animation::AxisMorph { ranges, samples } => Box::new(AxisMorph {
+                ranges: ranges.clone(),
+                samples: *samples,
+            }),
+            Animation::Annotated { annotations } => Box::new(Annotated {
+                annotations: annotations.clone(),
+            }),
+            Animation::AxisFade { ranges } => Box::new(CrossFade {
+                from: Sample {
+                    gid: None,
+                    settings: ranges
+                        .iter()
+                        .map(|r| VariationSetting::new(r.axis, r.from))
+                        .collect(),
+                },
+                to: Sample {
+                    gid: None,
+                    settings: ranges
+                        .iter()
+                        .map(|r| VariationSetting::new(r.axis, r.to))
+                        .collect(),
+                },
+                scale: 100.0,
+            }),
+            Animation::Swap { from, to } => Box::new(CrossFade {
+                from: Sample {
+                    gid: Some(*from),
+                    ..Default::default()
+                },
+                to: Sample {
+                    gid: Some(*to),
+                    ..Default::default()
+                },
+                scale: 90.0,
+            }),
+            Animation::BlurIn => Box::new(BlurIn { blur: BLUR_IN }),
+            Animation::Reveal { direction } => Box::new(Reveal {
+                direction: *direction,
+            }),
+            Animation::CircleReveal => Box::new(CircleReveal),
         }
     }
 }
 
+impl FromStr for Animation {
+    type Err = Error;
+
+    /// Parse the kebab-case name of an animation, such as "pulse-parts"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Animation::None),
+            "pulse-whole" => Ok(Animation::PulseWhole),
+            "pulse-parts" => Ok(Animation::PulseParts {
+                compensate_stroke: false,
+                parts: Default::default(),
+            }),
+            "twirl-whole" => Ok(Animation::TwirlWhole),
+            "shake" => Ok(Animation::Shake),
+            "twirl-parts" => Ok(Animation::TwirlParts {
+                parts: Default::default(),
+            }),
+            "weight-morph" => Ok(Animation::WeightMorph),
+            "fill-toggle" => Ok(Animation::FillToggle),
+            "grade-hover" => Ok(Animation::GradeHover),
+            "blur-in" => Ok(Animation::BlurIn),
+            "reveal" => Ok(Animation::Reveal {
+                direction: Default::default(),
+            }),
+            "circle-reveal" => Ok(Animation::CircleReveal),
+            _ => Err(Error::UnknownAnimation(s.to_string())),
+        }
+    }
+}
+
+/// One rendition of the icon, for animators that need several
+#[derive(Clone, Debug, Default)]
+pub struct Sample {
+    /// Draw this glyph, from the same font, rather than the one being animated
+    pub gid: Option<GlyphId>,
+    /// Override the location of the glyph, such as wght=700
+    pub settings: Vec<VariationSetting>,
+}
+
+/// Animates a glyph's shapes
+///
+/// `start` and `end` are when the layer the glyph is placed in plays, in frames of the layer's
+/// own time, which keyframes are in: they needn't start at 0 and the frame rate varies between
+/// templates so animators place keyframes relative to them.
 pub trait Animator {
     fn animate(
         &self,
@@ -38,6 +223,64 @@ pub trait Animator {
         end: f64,
         shapes: Vec<(BezPath, SubPath)>,
     ) -> Result<Vec<AnyShape>, Error>;
+
+    /// Renditions of the icon to draw, such as the glyph at wght=300 then at wght=700
+    ///
+    /// Empty, the default, for animators that move the glyph around rather than change what's drawn.
+    fn samples(&self) -> Vec<Sample> {
+        Vec::new()
+    }
+
+    /// Animate the icon as drawn for each of [Animator::samples], in order
+    ///
+    /// When samples have the same number of subpaths, as when drawing one glyph at several
+    /// locations, shapes correspond: `samples[n][i]` is the same subpath in each sample.
+    /// [verify_contour_correspondence] checks that a font lives up to that.
+    fn animate_samples(
+        &self,
+        start: f64,
+        end: f64,
+        samples: Vec<Vec<(BezPath, SubPath)>>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        let shapes = samples.into_iter().next().unwrap_or_default();
+        self.animate(start, end, shapes)
+    }
+
+    /// Whether shapes should arrive in the order the font draws them
+    ///
+    /// By default they're sorted top to bottom, left to right so parts stagger in reading order.
+    /// Animators that refer to contours by index want them as drawn.
+    fn contour_order(&self) -> bool {
+        false
+    }
+
+    /// Effects, as Lottie json, for the layer the icon is placed in, such as a blur that
+    /// animates along with the shapes
+    ///
+    /// Empty, the default, for animators that only animate shapes.
+    fn layer_effects(&self, _start: f64, _end: f64) -> Result<Vec<serde_json::Value>, Error> {
+        Ok(Vec::new())
+    }
+
+    /// Masks, as Lottie json, for the layer the icon is placed in; `bounds` is the box the
+    /// icon was placed into, in layer units
+    ///
+    /// Empty, the default, for animators that only animate shapes.
+    fn layer_masks(
+        &self,
+        _start: f64,
+        _end: f64,
+        _bounds: Rect,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        Ok(Vec::new())
+    }
+    /// Shapes, in layer units, for a track matte the icon's layer is seen through, such as
+    /// a circle that grows to uncover it; `bounds` is as for [Animator::layer_masks]
+    ///
+    /// Empty, the default, for no matte.
+    fn matte(&self, _start: f64, _end: f64, _bounds: Rect) -> Result<Vec<AnyShape>, Error> {
+        Ok(Vec::new())
+    }
 }
 
 pub struct Still;
@@ -65,11 +308,15 @@ impl Animator for Pulse {
         end: f64,
         shapes: Vec<(BezPath, SubPath)>,
     ) -> Result<Vec<AnyShape>, Error> {
-        Ok(vec![pulse(start, end, 0, shapes)])
+        let pivot = center(&shapes);
+        Ok(vec![pulse(start, end, 0, shapes, PULSE_SCALE, pivot)])
     }
 }
 
-pub struct PulseParts;
+pub struct PulseParts {
+    pub compensate_stroke: bool,
+    pub parts: PartOptions,
+}
 
 impl Animator for PulseParts {
     fn animate(
@@ -78,10 +325,18 @@ impl Animator for PulseParts {
         end: f64,
         shapes: Vec<(BezPath, SubPath)>,
     ) -> Result<Vec<AnyShape>, Error> {
-        Ok(group_icon_parts(shapes)
+        Ok(group_icon_parts_with(shapes, &self.parts)
             .into_iter()
             .enumerate()
-            .map(|(i, s)| pulse(start, end, i, s))
+            .map(|(i, s)| {
+                let scale = if self.compensate_stroke {
+                    compensated_scale(&s, PULSE_SCALE)
+                } else {
+                    PULSE_SCALE
+                };
+                let pivot = center(&s);
+                pulse(start, end, i, s, scale, pivot)
+            })
             .collect())
     }
 }
@@ -95,11 +350,34 @@ impl Animator for Twirl {
         end: f64,
         shapes: Vec<(BezPath, SubPath)>,
     ) -> Result<Vec<AnyShape>, Error> {
-        Ok(vec![twirl(start, end, 0, shapes)])
+        let pivot = center(&shapes);
+        Ok(vec![twirl(start, end, 0, shapes, pivot)])
+    }
+}
+
+pub struct Shake;
+
+impl Animator for Shake {
+    fn animate(
+        &self,
+        start: f64,
+        end: f64,
+        shapes: Vec<(BezPath, SubPath)>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        // swing from the top center, where a bell would hang
+        let pivot = shapes
+            .iter()
+            .map(|(b, _)| b.bounding_box())
+            .reduce(|acc, e| acc.union(e))
+            .map(|b| Point::new(b.center().x, b.min_y()))
+            .unwrap_or_default();
+        Ok(vec![shake(start, end, shapes, pivot)])
     }
 }
 
-pub struct TwirlParts;
+pub struct TwirlParts {
+    pub parts: PartOptions,
+}
 
 impl Animator for TwirlParts {
     fn animate(
@@ -108,15 +386,340 @@ impl Animator for TwirlParts {
         end: f64,
         shapes: Vec<(BezPath, SubPath)>,
     ) -> Result<Vec<AnyShape>, Error> {
-        Ok(group_icon_parts(shapes)
+        Ok(group_icon_parts_with(shapes, &self.parts)
             .into_iter()
             .enumerate()
-            .map(|(i, s)| twirl(start, end, i, s))
+            .map(|(i, s)| {
+                let pivot = center(&s);
+                twirl(start, end, i, s, pivot)
+            })
             .collect())
     }
 }
 
-fn default_ease() -> BezierEase {
+/// Animate the parts declared in [PartAnnotations] rather than guessing at them
+pub struct Annotated {
+    pub annotations: PartAnnotations,
+}
+
+impl Animator for Annotated {
+    fn animate(
+        &self,
+        start: f64,
+        end: f64,
+        shapes: Vec<(BezPath, SubPath)>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        let mut claimed = vec![false; shapes.len()];
+        let mut animated = Vec::with_capacity(self.annotations.parts.len() + 1);
+        for (i, part) in self.annotations.parts.iter().enumerate() {
+            let part_shapes = part
+                .contours
+                .iter()
+                .map(|contour| {
+                    let shape = shapes.get(*contour).ok_or_else(|| {
+                        Error::BadAnnotation(format!(
+                            "contour {contour} doesn't exist, the glyph has {}",
+                            shapes.len()
+                        ))
+                    })?;
+                    claimed[*contour] = true;
+                    Ok(shape.clone())
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            let bbox = part_shapes
+                .iter()
+                .map(|(b, _)| b.bounding_box())
+                .reduce(|acc, e| acc.union(e))
+                .unwrap_or_default();
+            let pivot = part
+                .pivot
+                .map(|[x, y]| {
+                    Point::new(
+                        bbox.min_x() + x * bbox.width(),
+                        bbox.min_y() + y * bbox.height(),
+                    )
+                })
+                .unwrap_or_else(|| bbox.center());
+            animated.push(match part.animation {
+                PartAnimation::None => group_with_transform(i, part_shapes, Transform::default()),
+                PartAnimation::Pulse => pulse(start, end, i, part_shapes, PULSE_SCALE, pivot),
+                PartAnimation::Twirl => twirl(start, end, i, part_shapes, pivot),
+            });
+        }
+
+        let unclaimed: Vec<_> = shapes
+            .into_iter()
+            .zip(claimed)
+            .filter_map(|(shape, claimed)| (!claimed).then_some(shape))
+            .collect();
+        if !unclaimed.is_empty() {
+            animated.push(group_with_transform(
+                animated.len(),
+                unclaimed,
+                Transform::default(),
+            ));
+        }
+        Ok(animated)
+    }
+
+    fn contour_order(&self) -> bool {
+        true
+    }
+}
+
+/// How many locations to sample, including the ends, for preset morphs
+pub const MORPH_SAMPLES: usize = 5;
+
+/// One axis of a morph, such as wght from 100 to 700
+#[derive(Clone, Debug)]
+pub struct AxisRange {
+    pub axis: Tag,
+    pub from: f32,
+    pub to: f32,
+}
+
+/// The axes to morph along, parsed from comma separated tag:from..to, such as "wght:100..700,FILL:0..1"
+#[derive(Clone, Debug, Default)]
+pub struct AxisRanges(pub Vec<AxisRange>);
+
+impl FromStr for AxisRanges {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split(',')
+            .map(str::trim)
+            .filter(|range| !range.is_empty())
+            .map(|range| {
+                let bad = || Error::BadAxisRange(range.to_string());
+                let (tag, values) = range.split_once(':').ok_or_else(bad)?;
+                let (from, to) = values.split_once("..").ok_or_else(bad)?;
+                Ok(AxisRange {
+                    axis: Tag::new_checked(tag.trim().as_bytes()).map_err(|_| bad())?,
+                    from: from.trim().parse().map_err(|_| bad())?,
+                    to: to.trim().parse().map_err(|_| bad())?,
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(AxisRanges)
+    }
+}
+
+/// Morph between two locations in design space by keyframing outlines sampled between them
+///
+/// All axes move together, sample n is at n / (samples - 1) of the way along every range.
+pub struct AxisMorph {
+    pub ranges: Vec<AxisRange>,
+    /// How many locations to sample, including both ends; at least 2
+    pub samples: usize,
+}
+
+impl Animator for AxisMorph {
+    fn animate(
+        &self,
+        start: f64,
+        end: f64,
+        shapes: Vec<(BezPath, SubPath)>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        // A single location has nothing to morph between
+        Still.animate(start, end, shapes)
+    }
+
+    fn samples(&self) -> Vec<Sample> {
+        let samples = self.samples.max(2);
+        (0..samples)
+            .map(|i| {
+                let t = i as f32 / (samples - 1) as f32;
+                Sample {
+                    gid: None,
+                    settings: self
+                        .ranges
+                        .iter()
+                        .map(|r| VariationSetting::new(r.axis, r.from + t * (r.to - r.from)))
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    fn animate_samples(
+        &self,
+        start: f64,
+        end: f64,
+        samples: Vec<Vec<(BezPath, SubPath)>>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        Ok(vec![morph(start, end, samples)?])
+    }
+}
+
+/// Cross-fade from one rendition of the icon to another, a simpler sibling of morphing
+///
+/// Such as one glyph to another, or one glyph at two locations. Both renditions are
+/// placed in the template; no point compatibility is required.
+pub struct CrossFade {
+    pub from: Sample,
+    pub to: Sample,
+    /// Scale, in percent, the outgoing glyph shrinks to and the incoming glyph grows from; 100 for none
+    pub scale: f64,
+}
+
+impl Animator for CrossFade {
+    fn animate(
+        &self,
+        start: f64,
+        end: f64,
+        shapes: Vec<(BezPath, SubPath)>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        // A single glyph has nothing to fade to
+        Still.animate(start, end, shapes)
+    }
+
+    fn samples(&self) -> Vec<Sample> {
+        vec![self.from.clone(), self.to.clone()]
+    }
+
+    fn animate_samples(
+        &self,
+        start: f64,
+        end: f64,
+        samples: Vec<Vec<(BezPath, SubPath)>>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        let mut samples = samples.into_iter();
+        let from = samples.next().unwrap_or_default();
+        let to = samples.next().unwrap_or_default();
+        Ok(vec![
+            fade(start, end, to, (0.0, 100.0), (self.scale, 100.0)),
+            fade(start, end, from, (100.0, 0.0), (100.0, self.scale)),
+        ])
+    }
+}
+
+/// How blurry [Animation::BlurIn] starts
+const BLUR_IN: f64 = 40.0;
+
+/// Fade in while a Gaussian blur layer effect eases from `blur` to none
+///
+/// Players that don't draw effects just fade in.
+pub struct BlurIn {
+    pub blur: f64,
+}
+
+impl Animator for BlurIn {
+    fn animate(
+        &self,
+        start: f64,
+        end: f64,
+        shapes: Vec<(BezPath, SubPath)>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        Ok(vec![fade(start, end, shapes, (0.0, 100.0), (100.0, 100.0))])
+    }
+
+    fn layer_effects(&self, start: f64, end: f64) -> Result<Vec<serde_json::Value>, Error> {
+        Ok(vec![gaussian_blur(self.blur, 0.0, start, end)?])
+    }
+}
+
+/// Reveal the icon with a rectangular mask that grows across it
+pub struct Reveal {
+    pub direction: RevealDirection,
+}
+
+impl Animator for Reveal {
+    fn animate(
+        &self,
+        start: f64,
+        end: f64,
+        shapes: Vec<(BezPath, SubPath)>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        // The mask does the moving
+        Still.animate(start, end, shapes)
+    }
+
+    fn layer_masks(
+        &self,
+        start: f64,
+        end: f64,
+        bounds: Rect,
+    ) -> Result<Vec<serde_json::Value>, Error> {
+        // Start as a sliver along the edge the reveal starts from
+        let sliver = match self.direction {
+            RevealDirection::LeftToRight => Rect::new(bounds.x0, bounds.y0, bounds.x0, bounds.y1),
+            RevealDirection::RightToLeft => Rect::new(bounds.x1, bounds.y0, bounds.x1, bounds.y1),
+            RevealDirection::TopToBottom => Rect::new(bounds.x0, bounds.y0, bounds.x1, bounds.y0),
+            RevealDirection::BottomToTop => Rect::new(bounds.x0, bounds.y1, bounds.x1, bounds.y1),
+        };
+        let (from, to) = (sliver.to_path(0.1), bounds.to_path(0.1));
+        let mask = animated_subpath(&[(start, &from), (end, &to)])?;
+        let path = serde_json::to_value(&mask).map_err(Error::JsonError)?["ks"].take();
+        Ok(vec![add_mask("reveal", path)])
+    }
+}
+
+/// Reveal the icon through a circle, in a matte layer above it, that grows from its center
+pub struct CircleReveal;
+
+impl Animator for CircleReveal {
+    fn animate(
+        &self,
+        start: f64,
+        end: f64,
+        shapes: Vec<(BezPath, SubPath)>,
+    ) -> Result<Vec<AnyShape>, Error> {
+        // The matte does the moving
+        Still.animate(start, end, shapes)
+    }
+
+    fn matte(&self, start: f64, end: f64, bounds: Rect) -> Result<Vec<AnyShape>, Error> {
+        let center = bounds.center();
+        // Big enough to cover the corners
+        let diameter = bounds.width().hypot(bounds.height());
+        let ease = default_ease();
+        let size = serde_json::to_value(vec![
+            MultiDimensionalKeyframe {
+                start_time: start,
+                start_value: Some(vec![0.0, 0.0]),
+                bezier: Some(ease.clone()),
+                ..Default::default()
+            },
+            MultiDimensionalKeyframe {
+                start_time: end,
+                start_value: Some(vec![diameter, diameter]),
+                bezier: Some(ease),
+                ..Default::default()
+            },
+        ])
+        .map_err(Error::JsonError)?;
+        let circle = json!({
+            "ty": "gr",
+            "nm": "matte",
+            "it": [
+                {
+                    "ty": "el",
+                    "p": { "a": 0, "k": [center.x, center.y] },
+                    "s": { "a": 1, "k": size },
+                },
+                {
+                    "ty": "fl",
+                    "c": { "a": 0, "k": [1, 1, 1] },
+                    "o": { "a": 0, "k": 100 },
+                    "r": 1,
+                },
+                {
+                    "ty": "tr",
+                    "a": { "a": 0, "k": [0, 0] },
+                    "p": { "a": 0, "k": [0, 0] },
+                    "s": { "a": 0, "k": [100, 100] },
+                    "r": { "a": 0, "k": 0 },
+                    "o": { "a": 0, "k": 100 },
+                },
+            ],
+        });
+        Ok(vec![
+            AnyShape::deserialize(circle).map_err(Error::JsonError)?
+        ])
+    }
+}
+
+pub(crate) fn default_ease() -> BezierEase {
     // If https://lottiefiles.github.io/lottie-docs/playground/json_editor/ is to be believed
     // the bezier ease is usually required since we rarely want to hold
     BezierEase::_2D(Bezier2d {
@@ -132,7 +735,6 @@ fn default_ease() -> BezierEase {
 /// Meant for simplified (assume the answer is the same for the entire subpath) nonzero fill resolution.
 pub fn a_contained_point(subpath: &BezPath) -> Option<Point> {
     let Some(PathEl::MoveTo(p)) = subpath.elements().first() else {
-        eprintln!("Subpath doesn't start with a move!");
         return None;
     };
 
@@ -145,6 +747,67 @@ pub fn a_contained_point(subpath: &BezPath) -> Option<Point> {
         .find(|p| subpath.contains(*p))
 }
 
+/// How [group_icon_parts_with] splits an icon into parts
+#[derive(Clone, Debug, Default)]
+pub struct PartOptions {
+    /// Parts smaller than this fraction of the icon's bounding box, such as 0.01, are merged
+    /// into the nearest larger part so dots and specks don't stagger on their own; 0 to never merge
+    pub min_part_area: f64,
+}
+
+/// [group_icon_parts], then merge parts that are too small per `options`
+pub fn group_icon_parts_with(
+    shapes: Vec<(BezPath, SubPath)>,
+    options: &PartOptions,
+) -> Vec<Vec<(BezPath, SubPath)>> {
+    let icon_area = shapes
+        .iter()
+        .map(|(b, _)| b.bounding_box())
+        .reduce(|acc, e| acc.union(e))
+        .map(|b| b.area())
+        .unwrap_or_default();
+    merge_small_parts(group_icon_parts(shapes), options.min_part_area * icon_area)
+}
+
+/// Merge parts whose bounding box area is under `min_area` into the part with the closest center
+///
+/// Smallest first so a speck lands on a real part rather than another speck.
+fn merge_small_parts(
+    mut groups: Vec<Vec<(BezPath, SubPath)>>,
+    min_area: f64,
+) -> Vec<Vec<(BezPath, SubPath)>> {
+    let part_area = |group: &[(BezPath, SubPath)]| {
+        group
+            .first()
+            .map(|(b, _)| b.bounding_box().area())
+            .unwrap_or_default()
+    };
+    loop {
+        let Some(small) = (0..groups.len())
+            .filter(|i| part_area(&groups[*i]) < min_area)
+            .min_by_key(|i| OrderedFloat(part_area(&groups[*i])))
+        else {
+            break;
+        };
+        let small_center = center(&groups[small]);
+        let Some(nearest) = (0..groups.len())
+            .filter(|i| *i != small && part_area(&groups[*i]) >= min_area)
+            .min_by_key(|i| OrderedFloat((center(&groups[*i]) - small_center).hypot()))
+        else {
+            // Nothing big enough to merge into, leave the rest as they are
+            break;
+        };
+        let merged = groups.remove(small);
+        let nearest = if nearest > small {
+            nearest - 1
+        } else {
+            nearest
+        };
+        groups[nearest].extend(merged);
+    }
+    groups
+}
+
 /// Piece-wise animation wants to animate "parts" as the eye perceives them; try to so group
 ///
 /// Most importantly, if we have a shape and hole(s) cut out of it they should be together.
@@ -163,9 +826,6 @@ pub fn group_icon_parts(shapes: Vec<(BezPath, SubPath)>) -> Vec<Vec<(BezPath, Su
         .iter()
         .map(|(bez, _)| {
             let Some(contained) = a_contained_point(bez) else {
-                if bez.area() != 0.0 {
-                    eprintln!("THERE IS NO CONTAINED POINT?!");
-                }
                 return false;
             };
             let winding: i32 = shapes.iter().map(|(bez, _)| bez.winding(contained)).sum();
@@ -201,36 +861,14 @@ pub fn group_icon_parts(shapes: Vec<(BezPath, SubPath)>) -> Vec<Vec<(BezPath, Su
                 .position(|group_bbox| group_bbox.intersect(bbox) == bbox)
             {
                 groups[i].push((bez, subpath));
-            } else {
-                eprintln!(
-                    "Uh oh, we have an unfilled shape that didn't land anywhere! {}",
-                    bez.to_svg()
-                );
             }
+            // An unfilled shape outside every filled one cuts nothing out, leave it out
         }
     }
 
     groups
 }
 
-fn nth_group_color(n: usize) -> (u8, u8, u8) {
-    // Taken from https://m2.material.io/design/color/the-color-system.html#tools-for-picking-colors
-    // "2014 Material Design color palettes"
-    const COLORS: &[(u8, u8, u8)] = &[
-        (0xEF, 0x53, 0x50),
-        (0xEC, 0x40, 0x7A),
-        (0xAB, 0x47, 0xBC),
-        (0xE5, 0x39, 0x35),
-        (0xD8, 0x1B, 0x60),
-        (0x8E, 0x24, 0xAA),
-        (0xC6, 0x28, 0x28),
-        (0xAD, 0x14, 0x57),
-        (0x6A, 0x1B, 0x9A),
-    ];
-
-    COLORS[n % COLORS.len()]
-}
-
 fn group_with_transform(
     shape_idx: usize,
     shapes: Vec<(BezPath, SubPath)>,
@@ -245,19 +883,9 @@ fn group_with_transform(
         .items
         .extend(shapes.into_iter().map(|(_, s)| AnyShape::Shape(s)));
 
-    let (r, g, b) = nth_group_color(shape_idx);
-
-    group.items.push(AnyShape::Fill(Fill {
-        opacity: Property {
-            value: Value::Fixed(100.0), // default of 0 is not helpful
-            ..Default::default()
-        },
-        color: Property {
-            value: Value::Fixed(vec![r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0]), // handy for debugging grouping
-            ..Default::default()
-        },
-        ..Default::default()
-    }));
+    group
+        .items
+        .push(AnyShape::Fill(FillStyle::default().fill(shape_idx)));
     group.items.push(AnyShape::Transform(transform));
     AnyShape::Group(group)
 }
@@ -271,18 +899,53 @@ fn center(shapes: &[(BezPath, SubPath)]) -> Point {
         .unwrap_or_default()
 }
 
-fn pulse(start: f64, end: f64, shape_idx: usize, shapes: Vec<(BezPath, SubPath)>) -> AnyShape {
+/// Peak scale, in percent, of a pulse
+const PULSE_SCALE: f64 = 150.0;
+
+/// How thick, relative to its size, a part must be to pulse to the full scale
+///
+/// A disc is 0.5, a stroke 1/10th the width of the part is about 0.1.
+const SOLID_THICKNESS: f64 = 0.25;
+
+/// Reduce `scale` for thin parts, detected by area to perimeter ratio
+///
+/// Scaling a part scales its strokes too so a thin ring pulsing next to a solid dot looks
+/// like it changes weight far more than the dot does. Twice area over perimeter approximates
+/// stroke width, relative to the size of the part it says how stroke-like the part is.
+fn compensated_scale(shapes: &[(BezPath, SubPath)], scale: f64) -> f64 {
+    let area: f64 = shapes.iter().map(|(b, _)| b.area()).sum::<f64>().abs();
+    let perimeter: f64 = shapes.iter().map(|(b, _)| b.perimeter(0.1)).sum();
+    let size = shapes
+        .iter()
+        .map(|(b, _)| b.bounding_box())
+        .reduce(|acc, e| acc.union(e))
+        .map(|b| b.width().max(b.height()))
+        .unwrap_or_default();
+    if perimeter <= 0.0 || size <= 0.0 {
+        return scale;
+    }
+    let thickness = 2.0 * area / perimeter / size;
+    100.0 + (scale - 100.0) * (thickness / SOLID_THICKNESS).clamp(0.0, 1.0)
+}
+
+fn pulse(
+    start: f64,
+    end: f64,
+    shape_idx: usize,
+    shapes: Vec<(BezPath, SubPath)>,
+    scale: f64,
+    pivot: Point,
+) -> AnyShape {
     assert!(end > start);
 
     let i = shape_idx as f64;
     let mut transform = Transform::default();
 
-    // pulse around the center of the shape(s)
+    // pulse around the pivot, usually the center of the shape(s)
     // https://lottiefiles.github.io/lottie-docs/concepts/#transform
     // notes that anchor and position need to match for this
-    let center = center(&shapes);
     transform.anchor_point = Property {
-        value: Value::Fixed(vec![center.x, center.y]),
+        value: Value::Fixed(vec![pivot.x, pivot.y]),
         ..Default::default()
     };
     transform.position = transform.anchor_point.clone();
@@ -292,19 +955,19 @@ fn pulse(start: f64, end: f64, shape_idx: usize, shapes: Vec<(BezPath, SubPath)>
     let ease = default_ease();
     transform.scale.value = Value::Animated(vec![
         MultiDimensionalKeyframe {
-            start_time: 0.2 * (end - start) * i,
+            start_time: start + 0.2 * (end - start) * i,
             start_value: Some(vec![100.0, 100.0]),
             bezier: Some(ease.clone()),
             ..Default::default()
         },
         MultiDimensionalKeyframe {
-            start_time: 0.2 * (end - start) * (i + 1.0),
-            start_value: Some(vec![150.0, 150.0]),
+            start_time: start + 0.2 * (end - start) * (i + 1.0),
+            start_value: Some(vec![scale, scale]),
             bezier: Some(ease.clone()),
             ..Default::default()
         },
         MultiDimensionalKeyframe {
-            start_time: 0.2 * (end - start) * (i + 2.0),
+            start_time: start + 0.2 * (end - start) * (i + 2.0),
             start_value: Some(vec![100.0, 100.0]),
             bezier: Some(ease),
             ..Default::default()
@@ -313,18 +976,23 @@ fn pulse(start: f64, end: f64, shape_idx: usize, shapes: Vec<(BezPath, SubPath)>
     group_with_transform(shape_idx, shapes, transform)
 }
 
-fn twirl(start: f64, end: f64, shape_idx: usize, shapes: Vec<(BezPath, SubPath)>) -> AnyShape {
+fn twirl(
+    start: f64,
+    end: f64,
+    shape_idx: usize,
+    shapes: Vec<(BezPath, SubPath)>,
+    pivot: Point,
+) -> AnyShape {
     assert!(end > start);
 
     let i = shape_idx as f64;
     let mut transform = Transform::default();
 
-    // spin around the center of the shape(s)
+    // spin around the pivot, usually the center of the shape(s)
     // https://lottiefiles.github.io/lottie-docs/concepts/#transform
     // notes that anchor and position need to match for this
-    let center = center(&shapes);
     transform.anchor_point = Property {
-        value: Value::Fixed(vec![center.x, center.y]),
+        value: Value::Fixed(vec![pivot.x, pivot.y]),
         ..Default::default()
     };
     transform.position = transform.anchor_point.clone();
@@ -333,13 +1001,13 @@ fn twirl(start: f64, end: f64, shape_idx: usize, shapes: Vec<(BezPath, SubPath)>
     let ease = default_ease();
     transform.rotation.value = Value::Animated(vec![
         MultiDimensionalKeyframe {
-            start_time: 0.2 * (end - start) * i,
+            start_time: start + 0.2 * (end - start) * i,
             start_value: Some(vec![0.0]),
             bezier: Some(ease.clone()),
             ..Default::default()
         },
         MultiDimensionalKeyframe {
-            start_time: 0.2 * (end - start) * (i + 2.0),
+            start_time: start + 0.2 * (end - start) * (i + 2.0),
             start_value: Some(vec![360.0]),
             bezier: Some(ease),
             ..Default::default()
@@ -347,3 +1015,196 @@ fn twirl(start: f64, end: f64, shape_idx: usize, shapes: Vec<(BezPath, SubPath)>
     ]);
     group_with_transform(shape_idx, shapes, transform)
 }
+
+fn shake(start: f64, end: f64, shapes: Vec<(BezPath, SubPath)>, pivot: Point) -> AnyShape {
+    assert!(end > start);
+
+    let mut transform = Transform::default();
+    transform.anchor_point = Property {
+        value: Value::Fixed(vec![pivot.x, pivot.y]),
+        ..Default::default()
+    };
+    transform.position = transform.anchor_point.clone();
+
+    // a decaying swing over the first half, then rest
+    const SWING: &[f64] = &[0.0, 15.0, -13.0, 10.0, -7.0, 4.0, 0.0];
+    let step = 0.5 * (end - start) / (SWING.len() - 1) as f64;
+    let ease = default_ease();
+    transform.rotation.animated = 1;
+    transform.rotation.value = Value::Animated(
+        SWING
+            .iter()
+            .enumerate()
+            .map(|(i, angle)| MultiDimensionalKeyframe {
+                start_time: start + step * i as f64,
+                start_value: Some(vec![*angle]),
+                bezier: Some(ease.clone()),
+                ..Default::default()
+            })
+            .collect(),
+    );
+    group_with_transform(0, shapes, transform)
+}
+
+/// The segment types of `path`, such as MLLCZ
+fn verbs(path: &BezPath) -> Vec<u8> {
+    path.elements()
+        .iter()
+        .map(|el| match el {
+            PathEl::MoveTo(..) => b'M',
+            PathEl::LineTo(..) => b'L',
+            PathEl::QuadTo(..) => b'Q',
+            PathEl::CurveTo(..) => b'C',
+            PathEl::ClosePath => b'Z',
+        })
+        .collect()
+}
+
+/// Put the subpaths of each sample in the order of the sample before, matched with
+/// [match_subpaths], and start closed subpaths where they line up with their counterpart's start
+///
+/// skrifa draws a glyph's contours in the same order at every location of a well formed
+/// variable font, in which case nothing moves. Where the order or starts differ, say between
+/// masters that were drawn separately, indices would otherwise pair the wrong contours and
+/// twist them around the outline. Samples that don't match up completely are left as drawn
+/// for [verify_contour_correspondence] to report.
+fn correspond(samples: &mut [Vec<(BezPath, SubPath)>]) {
+    for n in 1..samples.len() {
+        let (before, rest) = samples.split_at_mut(n);
+        let (previous, sample) = (&before[n - 1], &mut rest[0]);
+        let from: Vec<_> = previous.iter().map(|(path, _)| path.clone()).collect();
+        let to: Vec<_> = sample.iter().map(|(path, _)| path.clone()).collect();
+        let correspondence = match_subpaths(&from, &to);
+        if from.len() != to.len() || !correspondence.unmatched_from.is_empty() {
+            continue;
+        }
+        let mut order = vec![0; from.len()];
+        for (i, j) in correspondence.pairs {
+            order[i] = j;
+        }
+        *sample = order
+            .iter()
+            .enumerate()
+            .map(|(i, j)| {
+                let (path, subpath) = sample[*j].clone();
+                // Only a rotation that keeps the segments compatible helps
+                let aligned = align_start(&from[i], &path);
+                let path = if verbs(&aligned) == verbs(&from[i]) {
+                    aligned
+                } else {
+                    path
+                };
+                (path, subpath)
+            })
+            .collect();
+    }
+}
+
+/// Check that contour `i` of every sample is the same contour, drawn the same way
+///
+/// skrifa draws a glyph's contours in the same order with the same segments at every location
+/// of a well formed variable font, and [crate::Template::replace_shape] applies the first
+/// sample's order to all samples, so indices are stable. A font that varies the number or
+/// structure of contours would otherwise scramble which shape morphs into which.
+pub fn verify_contour_correspondence(samples: &[Vec<(BezPath, SubPath)>]) -> Result<(), Error> {
+    let Some(first) = samples.first() else {
+        return Ok(());
+    };
+    for (n, sample) in samples.iter().enumerate().skip(1) {
+        if sample.len() != first.len() {
+            return Err(Error::IncompatibleOutlines);
+        }
+        for (i, ((expected, _), (actual, _))) in first.iter().zip(sample).enumerate() {
+            let (expected, actual) = (verbs(expected), verbs(actual));
+            if expected != actual {
+                return Err(Error::ContourMismatch {
+                    sample: n,
+                    contour: i,
+                    reason: format!(
+                        "{} segments rather than {}",
+                        String::from_utf8_lossy(&actual),
+                        String::from_utf8_lossy(&expected)
+                    ),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Keyframe each subpath through its shape in each sample, spaced evenly from start to end
+fn morph(
+    start: f64,
+    end: f64,
+    mut samples: Vec<Vec<(BezPath, SubPath)>>,
+) -> Result<AnyShape, Error> {
+    assert!(end > start);
+    assert!(samples.len() > 1, "Need at least two samples to morph");
+    correspond(&mut samples);
+    verify_contour_correspondence(&samples)?;
+
+    let step = (end - start) / (samples.len() - 1) as f64;
+    let shapes = (0..samples[0].len())
+        .map(|i| {
+            let keyframes: Vec<_> = samples
+                .iter()
+                .enumerate()
+                .map(|(n, sample)| (start + step * n as f64, &sample[i].0))
+                .collect();
+            Ok((samples[0][i].0.clone(), animated_subpath(&keyframes)?))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    Ok(group_with_transform(0, shapes, Transform::default()))
+}
+
+/// Animate opacity and scale, each from .0 to .1, around the center of the shape(s)
+fn fade(
+    start: f64,
+    end: f64,
+    shapes: Vec<(BezPath, SubPath)>,
+    opacity: (f64, f64),
+    scale: (f64, f64),
+) -> AnyShape {
+    assert!(end > start);
+
+    let mut transform = Transform::default();
+    let center = center(&shapes);
+    transform.anchor_point = Property {
+        value: Value::Fixed(vec![center.x, center.y]),
+        ..Default::default()
+    };
+    transform.position = transform.anchor_point.clone();
+
+    let ease = default_ease();
+    transform.opacity.animated = 1;
+    transform.opacity.value = Value::Animated(vec![
+        MultiDimensionalKeyframe {
+            start_time: start,
+            start_value: Some(vec![opacity.0]),
+            bezier: Some(ease.clone()),
+            ..Default::default()
+        },
+        MultiDimensionalKeyframe {
+            start_time: end,
+            start_value: Some(vec![opacity.1]),
+            bezier: Some(ease.clone()),
+            ..Default::default()
+        },
+    ]);
+    transform.scale.animated = 1;
+    transform.scale.value = Value::Animated(vec![
+        MultiDimensionalKeyframe {
+            start_time: start,
+            start_value: Some(vec![scale.0, scale.0]),
+            bezier: Some(ease.clone()),
+            ..Default::default()
+        },
+        MultiDimensionalKeyframe {
+            start_time: end,
+            start_value: Some(vec![scale.1, scale.1]),
+            bezier: Some(ease),
+            ..Default::default()
+        },
+    ]);
+    group_with_transform(0, shapes, transform)
+}
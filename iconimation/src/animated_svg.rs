@@ -0,0 +1,247 @@
+//! Animated SVG, for places that can't embed a Lottie player.
+//!
+//! The Lottie is sampled with [Evaluator] every frame and played back with SMIL. When every
+//! sample draws the same paths, each path's `d`, fill and opacity animate from sample to sample;
+//! when they don't, such as when layers come and go, the samples are shown one after another.
+//! Strokes are painted as their outlines. Gradients, images, masks and track mattes aren't
+//! animated yet and are refused with [Error::NotAnimatableSvg], as is whatever the evaluator
+//! refuses.
+//!
+//! See <https://www.w3.org/TR/SVG11/animate.html>.
+
+use std::mem::discriminant;
+
+use bodymovin::Bodymovin as Lottie;
+use kurbo::{BezPath, PathEl, Point};
+
+use crate::{
+    error::Error,
+    evaluate::{Evaluator, FilledPath, Frame},
+    output::OutputOptions,
+};
+
+/// An SVG document that plays `lottie` on a loop
+///
+/// `options.precision` rounds coordinates, `options.compact` leaves out line breaks and
+/// indentation.
+pub fn to_animated_svg(lottie: &Lottie, options: &OutputOptions) -> Result<String, Error> {
+    let evaluator = Evaluator::new(lottie)?;
+    let (start, end) = (evaluator.in_point(), evaluator.out_point());
+    let frame_rate = evaluator.frame_rate();
+    let frames: Vec<_> = if end > start && frame_rate > 0.0 {
+        // Layers end before their out point, sample the frames they're drawn in
+        (0..(end - start).ceil() as usize)
+            .map(|i| evaluator.frame(start + i as f64))
+            .collect()
+    } else {
+        vec![evaluator.frame(start)]
+    };
+    for fill in frames.iter().flat_map(|frame| frame.fills.iter()) {
+        if fill.shader.is_some() {
+            return Err(Error::NotAnimatableSvg("gradients or images"));
+        }
+        if !fill.clips.is_empty() {
+            return Err(Error::NotAnimatableSvg("masks or track mattes"));
+        }
+    }
+    let timing = Timing {
+        dur: format!("{}s", round((end - start) / frame_rate, 3)),
+        key_times: key_times(frames.len()),
+    };
+
+    let mut svg = SvgWriter {
+        out: String::new(),
+        precision: options.precision,
+        compact: options.compact,
+    };
+    svg.line(
+        0,
+        &format!(
+            r#"<svg viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">"#,
+            evaluator.width(),
+            evaluator.height()
+        ),
+    );
+    if frames.len() == 1 || !same_paths(&frames) {
+        for (i, frame) in frames.iter().enumerate() {
+            svg.flipbook_page(frame, i, frames.len(), &timing);
+        }
+    } else {
+        for fill in 0..frames[0].fills.len() {
+            let samples: Vec<_> = frames.iter().map(|frame| &frame.fills[fill]).collect();
+            svg.animated_path(&samples, &timing);
+        }
+    }
+    svg.line(0, "</svg>");
+    Ok(svg.out)
+}
+
+struct Timing {
+    dur: String,
+    /// For one value per sample and a last holding the last sample until the loop restarts
+    key_times: String,
+}
+
+/// Whether every frame draws the same number of paths, each with the same commands, so they
+/// can be interpolated
+fn same_paths(frames: &[Frame]) -> bool {
+    let commands =
+        |path: &BezPath| -> Vec<_> { path.elements().iter().map(discriminant).collect() };
+    let first = &frames[0];
+    frames.iter().all(|frame| {
+        frame.fills.len() == first.fills.len()
+            && frame
+                .fills
+                .iter()
+                .zip(first.fills.iter())
+                .all(|(fill, first)| {
+                    fill.even_odd == first.even_odd && commands(&fill.path) == commands(&first.path)
+                })
+    })
+}
+
+/// Evenly spaced, from 0 to 1, for `samples` values and a last to hold
+fn key_times(samples: usize) -> String {
+    (0..=samples)
+        .map(|i| round(i as f64 / samples as f64, 4).to_string())
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+pub(crate) fn round(value: f64, decimals: u32) -> f64 {
+    let scale = 10f64.powi(decimals as i32);
+    (value * scale).round() / scale
+}
+
+struct SvgWriter {
+    out: String,
+    precision: Option<u32>,
+    compact: bool,
+}
+
+impl SvgWriter {
+    fn line(&mut self, depth: usize, text: &str) {
+        if !self.compact {
+            self.out.push_str(&"  ".repeat(depth));
+        }
+        self.out.push_str(text);
+        if !self.compact {
+            self.out.push('\n');
+        }
+    }
+
+    /// One path whose shape, fill and opacity animate from sample to sample, one per frame
+    fn animated_path(&mut self, samples: &[&FilledPath], timing: &Timing) {
+        let first = samples[0];
+        let ds: Vec<_> = samples.iter().map(|fill| self.d(&fill.path)).collect();
+        let fills: Vec<_> = samples.iter().map(|fill| rgb(fill.color)).collect();
+        let opacities: Vec<_> = samples.iter().map(|fill| opacity(fill.color)).collect();
+        self.line(
+            1,
+            &format!("<path d=\"{}\"{}>", ds[0], paint_attributes(first)),
+        );
+        for (attribute, values) in [("d", ds), ("fill", fills), ("fill-opacity", opacities)] {
+            if values.iter().all(|value| *value == values[0]) {
+                continue;
+            }
+            let last = values.last().unwrap().clone();
+            let values = values.into_iter().chain([last]).collect::<Vec<_>>();
+            self.line(
+                2,
+                &format!(
+                    r#"<animate attributeName="{attribute}" dur="{}" repeatCount="indefinite" keyTimes="{}" values="{}" />"#,
+                    timing.dur,
+                    timing.key_times,
+                    values.join(";")
+                ),
+            );
+        }
+        self.line(1, "</path>");
+    }
+
+    /// Frame `page` of `pages`, visible only while it's the current sample
+    fn flipbook_page(&mut self, frame: &Frame, page: usize, pages: usize, timing: &Timing) {
+        if pages == 1 {
+            self.line(1, "<g>");
+        } else {
+            let (shown, hidden) = (page as f64 / pages as f64, (page + 1) as f64 / pages as f64);
+            let mut key_times = vec![0.0];
+            let mut values = vec![if page == 0 { "visible" } else { "hidden" }];
+            if page > 0 {
+                key_times.push(shown);
+                values.push("visible");
+            }
+            if page + 1 < pages {
+                key_times.push(hidden);
+                values.push("hidden");
+            }
+            self.line(1, r#"<g visibility="hidden">"#);
+            self.line(
+                2,
+                &format!(
+                    r#"<animate attributeName="visibility" dur="{}" repeatCount="indefinite" calcMode="discrete" keyTimes="{}" values="{}" />"#,
+                    timing.dur,
+                    key_times
+                        .iter()
+                        .map(|t| round(*t, 4).to_string())
+                        .collect::<Vec<_>>()
+                        .join(";"),
+                    values.join(";")
+                ),
+            );
+        }
+        for fill in frame.fills.iter() {
+            let d = self.d(&fill.path);
+            self.line(2, &format!("<path d=\"{d}\"{} />", paint_attributes(fill)));
+        }
+        self.line(1, "</g>");
+    }
+
+    fn d(&self, path: &BezPath) -> String {
+        path_data(path, self.precision)
+    }
+}
+
+/// `path` as path data, rounded to `precision` decimals if given
+pub(crate) fn path_data(path: &BezPath, precision: Option<u32>) -> String {
+    let Some(precision) = precision else {
+        return path.to_svg();
+    };
+    let round_point = |p: Point| Point::new(round(p.x, precision), round(p.y, precision));
+    let rounded: BezPath = path
+        .elements()
+        .iter()
+        .map(|el| match *el {
+            PathEl::MoveTo(p) => PathEl::MoveTo(round_point(p)),
+            PathEl::LineTo(p) => PathEl::LineTo(round_point(p)),
+            PathEl::QuadTo(c, p) => PathEl::QuadTo(round_point(c), round_point(p)),
+            PathEl::CurveTo(c0, c1, p) => {
+                PathEl::CurveTo(round_point(c0), round_point(c1), round_point(p))
+            }
+            PathEl::ClosePath => PathEl::ClosePath,
+        })
+        .collect();
+    rounded.to_svg()
+}
+
+fn paint_attributes(fill: &FilledPath) -> String {
+    format!(
+        " fill=\"{}\" fill-opacity=\"{}\"{}",
+        rgb(fill.color),
+        opacity(fill.color),
+        if fill.even_odd {
+            " fill-rule=\"evenodd\""
+        } else {
+            ""
+        }
+    )
+}
+
+pub(crate) fn rgb(color: [f64; 4]) -> String {
+    let [r, g, b, _] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round());
+    format!("rgb({r}, {g}, {b})")
+}
+
+fn opacity(color: [f64; 4]) -> String {
+    round(color[3].clamp(0.0, 1.0), 3).to_string()
+}
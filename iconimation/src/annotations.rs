@@ -0,0 +1,72 @@
+//! Explicit parts for icons where automatic grouping gets it wrong.
+//!
+//! A companion json file per icon lists which contours make up each part and how it moves:
+//!
+//! ```json
+//! {
+//!   "parts": [
+//!     { "contours": [0, 1], "animation": "pulse" },
+//!     { "contours": [2], "animation": "twirl", "pivot": [0.5, 1.0] }
+//!   ]
+//! }
+//! ```
+//!
+//! Contours are numbered in the order the font draws them. Contours no part claims hold still.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::error::Error;
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct PartAnnotations {
+    /// Parts animate in this order, staggered like the automatic parts animations
+    pub parts: Vec<PartAnnotation>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PartAnnotation {
+    /// Indices of the contours that make up the part, in font drawing order
+    pub contours: Vec<usize>,
+    #[serde(default)]
+    pub animation: PartAnimation,
+    /// What the part pulses or twirls around, relative to its bounds: [0, 0] is top left,
+    /// [1, 1] bottom right and [0.5, 0.5], the center, the default
+    pub pivot: Option<[f64; 2]>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PartAnimation {
+    #[default]
+    None,
+    Pulse,
+    Twirl,
+}
+
+impl PartAnnotations {
+    /// Contours claimed by more than one part are an error, they can't move two ways at once
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut seen = Vec::new();
+        for contour in self.parts.iter().flat_map(|p| p.contours.iter()) {
+            if seen.contains(contour) {
+                return Err(Error::BadAnnotation(format!(
+                    "contour {contour} is in more than one part"
+                )));
+            }
+            seen.push(*contour);
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PartAnnotations {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let annotations: PartAnnotations = serde_json::from_str(s).map_err(Error::JsonError)?;
+        annotations.validate()?;
+        Ok(annotations)
+    }
+}
@@ -0,0 +1,369 @@
+//! Bitmap glyphs, such as emoji in CBDT or sbix fonts, that have no outlines to animate.
+//!
+//! The glyph's PNG is embedded as an image asset, placed in a precomp at the size the glyph's
+//! shapes would have been, and the precomp is moved as the shapes would have been. That only
+//! works for animations that move, scale, rotate or fade the glyph as a whole; anything that
+//! changes what's drawn, such as a morph, a reveal or animating parts, is an error.
+//!
+//! Placeholders in the template's own precomps aren't filled, only those in its layers.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bodymovin::{layers::AnyLayer, shapes::AnyShape, sources::Asset, Bodymovin as Lottie};
+use kurbo::{Affine, Rect};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use skrifa::{
+    outline::OutlinePen,
+    raw::{
+        tables::bitmap::{BitmapContent, BitmapDataFormat, BitmapMetrics},
+        FontRef, TableProvider,
+    },
+    GlyphId, Tag,
+};
+
+use crate::{
+    animate::{Animation, Animator},
+    error::Error,
+    find_placeholders, font_units_to_lottie_units, item_path, layer_name, layer_time_range,
+    placeholder_box, placeholder_layer_to_group,
+    placement::Placement,
+    searched_layers,
+    shape_pen::SubPathPen,
+};
+
+/// The PNG image a font has for a glyph
+#[derive(Clone, Debug)]
+pub struct Bitmap {
+    pub png: Vec<u8>,
+    /// In pixels
+    pub width: u32,
+    /// In pixels
+    pub height: u32,
+    /// Where the image goes, in font units
+    pub bounds: Rect,
+}
+
+/// The largest PNG the sbix or CBDT table has for `gid`, None if neither has one
+pub fn bitmap_glyph(font: &FontRef, gid: GlyphId) -> Result<Option<Bitmap>, Error> {
+    let upem = font.head().map_err(Error::ReadError)?.units_per_em() as f64;
+    if let Some(bitmap) = sbix_glyph(font, gid, upem)? {
+        return Ok(Some(bitmap));
+    }
+    cbdt_glyph(font, gid, upem)
+}
+
+fn sbix_glyph(font: &FontRef, gid: GlyphId, upem: f64) -> Result<Option<Bitmap>, Error> {
+    let Ok(sbix) = font.sbix() else {
+        return Ok(None);
+    };
+    let mut largest = None;
+    for strike in sbix.strikes().iter() {
+        let strike = strike.map_err(Error::ReadError)?;
+        let Some(data) = strike.glyph_data(gid).map_err(Error::ReadError)? else {
+            continue;
+        };
+        if data.graphic_type() != Tag::new(b"png ") {
+            continue;
+        }
+        let ppem = strike.ppem();
+        if largest
+            .as_ref()
+            .is_some_and(|(largest, _)| *largest >= ppem)
+        {
+            continue;
+        }
+        let Some((width, height)) = png_size(data.data()) else {
+            continue;
+        };
+        // The origin offset is from the glyph origin to the bottom left of the image
+        let scale = upem / ppem as f64;
+        let (x0, y0) = (
+            data.origin_offset_x() as f64 * scale,
+            data.origin_offset_y() as f64 * scale,
+        );
+        let bounds = Rect::new(
+            x0,
+            y0,
+            x0 + width as f64 * scale,
+            y0 + height as f64 * scale,
+        );
+        let bitmap = Bitmap {
+            png: data.data().to_vec(),
+            width,
+            height,
+            bounds,
+        };
+        largest = Some((ppem, bitmap));
+    }
+    Ok(largest.map(|(_, bitmap)| bitmap))
+}
+
+fn cbdt_glyph(font: &FontRef, gid: GlyphId, upem: f64) -> Result<Option<Bitmap>, Error> {
+    let (Ok(cblc), Ok(cbdt)) = (font.cblc(), font.cbdt()) else {
+        return Ok(None);
+    };
+    let mut largest = None;
+    for size in cblc.bitmap_sizes() {
+        let ppem = size.ppem_y();
+        if largest
+            .as_ref()
+            .is_some_and(|(largest, _)| *largest >= ppem)
+        {
+            continue;
+        }
+        // Strikes that don't have the glyph fail to locate it
+        let Ok(location) = size.location(cblc.offset_data(), gid) else {
+            continue;
+        };
+        let data = cbdt.data(&location).map_err(Error::ReadError)?;
+        let BitmapContent::Data(BitmapDataFormat::Png, png) = data.content else {
+            continue;
+        };
+        let Some((width, height)) = png_size(png) else {
+            continue;
+        };
+        let (bearing_x, bearing_y) = match &data.metrics {
+            BitmapMetrics::Small(metrics) => (metrics.bearing_x(), metrics.bearing_y()),
+            BitmapMetrics::Big(metrics) => (metrics.hori_bearing_x(), metrics.hori_bearing_y()),
+        };
+        // Bearings are from the glyph origin to the top left of the image
+        let scale = upem / ppem as f64;
+        let (x0, y1) = (bearing_x as f64 * scale, bearing_y as f64 * scale);
+        let bounds = Rect::new(
+            x0,
+            y1 - height as f64 * scale,
+            x0 + width as f64 * scale,
+            y1,
+        );
+        let bitmap = Bitmap {
+            png: png.to_vec(),
+            width,
+            height,
+            bounds,
+        };
+        largest = Some((ppem, bitmap));
+    }
+    Ok(largest.map(|(_, bitmap)| bitmap))
+}
+
+/// The width and height in the IHDR chunk of `png`, None if it isn't a PNG
+fn png_size(png: &[u8]) -> Option<(u32, u32)> {
+    const SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+    if png.len() < 24 || !png.starts_with(SIGNATURE) || &png[12..16] != b"IHDR" {
+        return None;
+    }
+    let read = |at: usize| u32::from_be_bytes(png[at..at + 4].try_into().unwrap());
+    Some((read(16), read(20)))
+}
+
+/// Place `bitmap` in the placeholders of `lottie`, `drawbox` fitted to each as glyphs are, and
+/// animate it as `animator` would its shapes
+pub(crate) fn replace_with_bitmap(
+    lottie: &mut Lottie,
+    bitmap: &Bitmap,
+    drawbox: &Rect,
+    label: &str,
+    animator: &dyn Animator,
+    placement: &Placement,
+) -> Result<(), Error> {
+    if !animator.samples().is_empty() {
+        return Err(Error::BitmapAnimation(
+            "the animation draws the glyph more than once".to_string(),
+        ));
+    }
+    let image = format!("data:image/png;base64,{}", STANDARD.encode(&bitmap.png));
+    let mut assets = vec![json!({
+        "id": "bitmap",
+        "w": bitmap.width,
+        "h": bitmap.height,
+        "u": "",
+        "p": image,
+        "e": 1,
+    })];
+    let mut next_index = lottie
+        .layers
+        .iter()
+        .filter_map(|l| serde_json::to_value(l).ok()?["ind"].as_i64())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    // Image pixels run Y-down from the top left of the bitmap's bounds
+    let pixels_to_font = Affine::new([
+        bitmap.bounds.width() / bitmap.width as f64,
+        0.0,
+        0.0,
+        -bitmap.bounds.height() / bitmap.height as f64,
+        bitmap.bounds.x0,
+        bitmap.bounds.y1,
+    ]);
+
+    let mut inserts = Vec::new();
+    for (layer_idx, layer) in lottie.layers.iter_mut().enumerate() {
+        let AnyLayer::Shape(layer) = layer else {
+            continue;
+        };
+        if let Some(name) =
+            layer_name(layer)?.filter(|n| placement.is_placeholder(Some(n.as_str())))
+        {
+            placeholder_layer_to_group(layer, name);
+        }
+        let timing = serde_json::to_value(&*layer).map_err(Error::JsonError)?;
+        let Some((start, end)) = layer_time_range(layer)? else {
+            continue;
+        };
+        let mut placeholders = Vec::new();
+        find_placeholders(&mut layer.mixin.shapes, placement, &mut placeholders)?;
+        for (placeholder, name) in placeholders {
+            let mut found = None;
+            for (i, item) in placeholder.items.iter().enumerate() {
+                if let Some(lottie_box) = placeholder_box(item)
+                    .map_err(|e| e.within(&item_path(placeholder.name.as_deref(), i)))?
+                {
+                    found = Some((i, lottie_box));
+                    break;
+                }
+            }
+            let Some((i, lottie_box)) = found else {
+                continue;
+            };
+            // The box is drawn as the bitmap, not as a shape
+            placeholder.items.remove(i);
+            let lottie_box = name.padding.unwrap_or(placement.padding).inset(lottie_box);
+            let font_to_lottie = font_units_to_lottie_units(
+                drawbox,
+                &lottie_box,
+                name.fit.unwrap_or(placement.fit),
+                name.align.unwrap_or_default(),
+            )?;
+            let [sx, _, _, sy, x, y] = (font_to_lottie * pixels_to_font).as_coeffs();
+
+            // Animate the box the bitmap fills as if it were the glyph
+            let bounds = font_to_lottie.transform_rect_bbox(bitmap.bounds);
+            let mut pen = SubPathPen::default();
+            pen.move_to(bounds.x0 as f32, bounds.y0 as f32);
+            pen.line_to(bounds.x1 as f32, bounds.y0 as f32);
+            pen.line_to(bounds.x1 as f32, bounds.y1 as f32);
+            pen.line_to(bounds.x0 as f32, bounds.y1 as f32);
+            pen.close();
+            let directed = name.animation.as_ref().map(Animation::animator);
+            let animator = directed.as_deref().unwrap_or(animator);
+            let shapes = animator.animate(start, end, pen.into_shapes())?;
+            if !animator.matte(start, end, bounds)?.is_empty()
+                || !animator.layer_masks(start, end, bounds)?.is_empty()
+            {
+                return Err(Error::BitmapAnimation(
+                    "the animation masks the glyph".to_string(),
+                ));
+            }
+            let transform = whole_transform(&shapes)?;
+            let effects: Vec<_> = animator
+                .layer_effects(start, end)?
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut effect)| {
+                    effect["ix"] = json!(i + 1);
+                    effect
+                })
+                .collect();
+
+            let precomp = format!("bitmap-{}", assets.len() - 1);
+            assets.push(json!({
+                "id": precomp,
+                "layers": [{
+                    "ddd": 0,
+                    "ind": 1,
+                    "ty": 2,
+                    "nm": label,
+                    "refId": "bitmap",
+                    "sr": 1,
+                    "ks": {
+                        "o": fixed(json!(100)),
+                        "r": fixed(json!(0)),
+                        "p": fixed(json!([x, y, 0])),
+                        "a": fixed(json!([0, 0, 0])),
+                        "s": fixed(json!([sx * 100.0, sy * 100.0, 100])),
+                    },
+                    "ao": 0,
+                    "ip": start,
+                    "op": end,
+                    "st": 0,
+                    "bm": 0,
+                }],
+            }));
+            let mut precomp_layer = json!({
+                "ddd": 0,
+                "ind": next_index,
+                "ty": 0,
+                "nm": label,
+                "refId": precomp,
+                "ks": transform,
+                "ao": 0,
+                "w": lottie.width,
+                "h": lottie.height,
+                "bm": 0,
+                "ef": effects,
+            });
+            // Play and move with the placeholder's layer
+            for key in ["ip", "op", "st", "sr", "parent"] {
+                if !timing[key].is_null() {
+                    precomp_layer[key] = timing[key].clone();
+                }
+            }
+            next_index += 1;
+            inserts.push((layer_idx, precomp_layer));
+        }
+    }
+    if inserts.is_empty() {
+        return Err(Error::NoPlaceholder {
+            marker: placement.marker.clone(),
+            searched_layers: searched_layers(lottie),
+        });
+    }
+
+    for asset in assets {
+        lottie
+            .assets
+            .push(Asset::deserialize(asset).map_err(Error::JsonError)?);
+    }
+    // Earlier layers draw on top, put the bitmap above the placeholder's layer
+    for (layer_idx, layer) in inserts.into_iter().rev() {
+        let layer = AnyLayer::deserialize(layer).map_err(Error::JsonError)?;
+        lottie.layers.insert(layer_idx, layer);
+    }
+    Ok(())
+}
+
+/// The transform, as Lottie json, that moves `shapes` as a whole
+///
+/// Animators that move the whole glyph make one group ending in a transform, those that don't
+/// animate make bare shapes. Anything else animates parts or what's drawn.
+fn whole_transform(shapes: &[AnyShape]) -> Result<Value, Error> {
+    let unsupported =
+        || Error::BitmapAnimation("the animation changes what's drawn, not just where".to_string());
+    if shapes.iter().all(|s| matches!(s, AnyShape::Shape(..))) {
+        return Ok(json!({
+            "o": fixed(json!(100)),
+            "r": fixed(json!(0)),
+            "p": fixed(json!([0, 0, 0])),
+            "a": fixed(json!([0, 0, 0])),
+            "s": fixed(json!([100, 100, 100])),
+        }));
+    }
+    let [AnyShape::Group(group)] = shapes else {
+        return Err(unsupported());
+    };
+    let mut transform = None;
+    for item in group.items.iter() {
+        match item {
+            AnyShape::Shape(..) | AnyShape::Fill(..) => (),
+            AnyShape::Transform(t) if transform.is_none() => transform = Some(t),
+            _ => return Err(unsupported()),
+        }
+    }
+    let transform = transform.ok_or_else(unsupported)?;
+    serde_json::to_value(transform).map_err(Error::JsonError)
+}
+
+fn fixed(value: Value) -> Value {
+    json!({ "a": 0, "k": value })
+}
@@ -0,0 +1,41 @@
+//! Templates shipped with iconimation, for icons on a badge, a card and so on without
+//! authoring Lottie by hand.
+//!
+//! Wherever a template path is taken, `builtin:circle-badge` names the circle badge.
+
+use serde_json::Value;
+
+use crate::{error::Error, pool::SharedTemplate};
+
+/// Prefixes the name of a builtin template where a path is expected
+pub const BUILTIN: &str = "builtin:";
+
+const TEMPLATES: &[(&str, &str)] = &[
+    ("plain", include_str!("../resources/templates/plain.json")),
+    (
+        "circle-badge",
+        include_str!("../resources/templates/circle-badge.json"),
+    ),
+    (
+        "rounded-card",
+        include_str!("../resources/templates/rounded-card.json"),
+    ),
+    (
+        "notification-dot",
+        include_str!("../resources/templates/notification-dot.json"),
+    ),
+];
+
+/// The names of the builtin templates, such as "circle-badge"
+pub fn builtin_names() -> impl Iterator<Item = &'static str> {
+    TEMPLATES.iter().map(|(name, _)| *name)
+}
+
+/// The builtin template `name`, without the [BUILTIN] prefix
+pub fn builtin_template(name: &str) -> Result<SharedTemplate, Error> {
+    let Some((_, json)) = TEMPLATES.iter().find(|(n, _)| *n == name) else {
+        return Err(Error::UnknownTemplate(name.to_string()));
+    };
+    let json: Value = serde_json::from_str(json).map_err(Error::JsonError)?;
+    Ok(SharedTemplate::from_json(json))
+}
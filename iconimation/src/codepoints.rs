@@ -0,0 +1,46 @@
+//! Icon names, such as "home", for the codepoints of their glyphs, such as 0xE88A, as
+//! Material Symbols `.codepoints` files have them.
+//!
+//! Each line is a name and a hex codepoint separated by a space. The files ship alongside the
+//! fonts, such as `MaterialSymbolsOutlined[FILL,GRAD,opsz,wght].codepoints`.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::error::Error;
+
+#[derive(Clone, Debug, Default)]
+pub struct IconNames {
+    codepoints: HashMap<String, u32>,
+}
+
+impl FromStr for IconNames {
+    type Err = Error;
+
+    /// Parse the contents of a `.codepoints` file, skipping blank lines
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut codepoints = HashMap::new();
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let bad = || Error::BadCodepointsLine(line.to_string());
+            let (name, codepoint) = line.split_once(' ').ok_or_else(bad)?;
+            let codepoint = u32::from_str_radix(codepoint.trim(), 16).map_err(|_| bad())?;
+            codepoints.insert(name.to_string(), codepoint);
+        }
+        Ok(IconNames { codepoints })
+    }
+}
+
+impl IconNames {
+    /// The codepoint of the icon named `name`, such as "home"
+    pub fn codepoint(&self, name: &str) -> Option<u32> {
+        self.codepoints.get(name).copied()
+    }
+
+    /// Where the `.codepoints` file for `font_file` is, if it ships alongside it
+    pub fn beside(font_file: &Path) -> PathBuf {
+        font_file.with_extension("codepoints")
+    }
+}
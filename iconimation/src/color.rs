@@ -0,0 +1,567 @@
+//! Color glyphs: COLR layers, each an outline glyph filled with a CPAL color or, in COLR v1,
+//! a gradient.
+//!
+//! The layers are drawn and animated together, as one icon, then the shapes of each layer are
+//! grouped with a fill of their own.
+//!
+//! A glyph can also shift from one palette to another, such as light to dark theme, as it
+//! animates; only solid colors shift.
+//!
+//! COLR v1 support is the subset color icon fonts, such as Noto Color Emoji, use: glyphs
+//! filled with solid colors or linear and radial gradients, under transforms. Composite modes
+//! are ignored and sweep gradients are approximated by their first color.
+//!
+//! Fonts without COLR may have OT-SVG glyphs instead, read with the `svg` feature into layers
+//! of their own paths, see [crate::svg].
+
+use std::collections::BTreeMap;
+
+use bodymovin::{
+    properties::{MultiDimensionalKeyframe, Value},
+    shapes::{AnyShape, Fill, Group, SubPath},
+};
+use kurbo::{Affine, BezPath, Point, Vec2};
+use serde::Deserialize;
+use serde_json::json;
+use skrifa::{
+    color::{Brush, ColorGlyphFormat, ColorPainter, ColorStop, CompositeMode, Transform},
+    instance::Location,
+    raw::{tables::cpal::Cpal, types::BoundingBox, FontRef, TableProvider},
+    GlyphId, MetadataProvider,
+};
+
+use crate::{animate::default_ease, error::Error, style::Rgb, Glyph};
+
+/// Layers whose palette index is this use the foreground color, the fill from [crate::style::Style]
+const FOREGROUND: u16 = 0xFFFF;
+
+#[derive(Clone, Debug)]
+pub struct ColorLayer {
+    pub gid: GlyphId,
+    /// Applied to the layer glyph's outline, in font units
+    pub transform: Affine,
+    pub paint: Paint,
+    /// Drawn, in font units, in place of the outline of `gid`, as OT-SVG glyphs are
+    pub path: Option<BezPath>,
+}
+
+/// How a layer is filled, in font units
+#[derive(Clone, Debug)]
+pub enum Paint {
+    /// The foreground color, whatever the fill from [crate::style::Style] is
+    Foreground {
+        alpha: f64,
+    },
+    Solid {
+        color: Rgb,
+        alpha: f64,
+    },
+    Linear {
+        start: Point,
+        end: Point,
+        stops: Vec<GradientStop>,
+    },
+    /// Lottie has no focal circle so COLR's start circle is dropped
+    Radial {
+        center: Point,
+        radius: f64,
+        stops: Vec<GradientStop>,
+    },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct GradientStop {
+    /// 0 to 1
+    pub offset: f64,
+    pub color: Rgb,
+    pub alpha: f64,
+}
+
+/// The COLR layers of `gid`, bottom to top, colored from CPAL `palette`
+///
+/// COLR v1 glyphs are painted at `location`. Empty if `gid` isn't a color glyph.
+pub fn color_layers(
+    font: &FontRef,
+    gid: GlyphId,
+    palette: u16,
+    location: &Location,
+) -> Result<Vec<ColorLayer>, Error> {
+    let Ok(colr) = font.colr() else {
+        #[cfg(feature = "svg")]
+        return crate::svg::svg_layers(font, gid);
+        #[cfg(not(feature = "svg"))]
+        return Ok(Vec::new());
+    };
+    if let Some(glyph) = font
+        .color_glyphs()
+        .get_with_format(gid, ColorGlyphFormat::ColrV1)
+    {
+        let mut painter = LayerPainter {
+            palette: Palette::new(font, palette)?,
+            transforms: vec![Affine::IDENTITY],
+            clips: Vec::new(),
+            layers: Vec::new(),
+            error: None,
+        };
+        glyph
+            .paint(location, &mut painter)
+            .map_err(|e| Error::BadColorGlyph(format!("unable to paint {gid}: {e}")))?;
+        if let Some(e) = painter.error {
+            return Err(e);
+        }
+        return Ok(painter.layers);
+    }
+
+    let Some(base_glyphs) = colr
+        .base_glyph_records()
+        .transpose()
+        .map_err(Error::ReadError)?
+    else {
+        return Ok(Vec::new());
+    };
+    let Some(base) = base_glyphs.iter().find(|b| b.glyph_id() == gid) else {
+        return Ok(Vec::new());
+    };
+    let layers = colr
+        .layer_records()
+        .transpose()
+        .map_err(Error::ReadError)?
+        .unwrap_or_default();
+    let first = base.first_layer_index() as usize;
+    let Some(layers) = layers.get(first..first + base.num_layers() as usize) else {
+        return Err(Error::BadColorGlyph(format!(
+            "{gid} refers to layers past the end of COLR"
+        )));
+    };
+
+    let palette = Palette::new(font, palette)?;
+    layers
+        .iter()
+        .map(|layer| {
+            Ok(ColorLayer {
+                gid: layer.glyph_id(),
+                transform: Affine::IDENTITY,
+                paint: palette.solid(layer.palette_index(), 1.0)?,
+                path: None,
+            })
+        })
+        .collect()
+}
+
+/// One CPAL palette
+struct Palette<'a> {
+    cpal: Cpal<'a>,
+    first_record: usize,
+}
+
+impl<'a> Palette<'a> {
+    fn new(font: &FontRef<'a>, palette: u16) -> Result<Self, Error> {
+        let cpal = font.cpal().map_err(Error::ReadError)?;
+        if palette >= cpal.num_palettes() {
+            return Err(Error::BadColorGlyph(format!(
+                "there is no palette {palette}, the font has {}",
+                cpal.num_palettes()
+            )));
+        }
+        let first_record = cpal
+            .color_record_indices()
+            .get(palette as usize)
+            .map(|i| i.get() as usize)
+            .unwrap_or_default();
+        Ok(Palette { cpal, first_record })
+    }
+
+    /// The color and alpha of an entry, None for the foreground color
+    fn color(&self, entry: u16) -> Result<Option<(Rgb, f64)>, Error> {
+        if entry == FOREGROUND {
+            return Ok(None);
+        }
+        if entry >= self.cpal.num_palette_entries() {
+            return Err(Error::BadColorGlyph(format!(
+                "palette entry {entry} is out of range, palettes have {}",
+                self.cpal.num_palette_entries()
+            )));
+        }
+        let records = self
+            .cpal
+            .color_records_array()
+            .transpose()
+            .map_err(Error::ReadError)?
+            .unwrap_or_default();
+        let record = records
+            .get(self.first_record + entry as usize)
+            .ok_or_else(|| Error::BadColorGlyph(format!("no color record for entry {entry}")))?;
+        Ok(Some((
+            Rgb([
+                record.red() as f64 / 255.0,
+                record.green() as f64 / 255.0,
+                record.blue() as f64 / 255.0,
+            ]),
+            record.alpha() as f64 / 255.0,
+        )))
+    }
+
+    fn solid(&self, entry: u16, alpha: f64) -> Result<Paint, Error> {
+        Ok(match self.color(entry)? {
+            Some((color, a)) => Paint::Solid {
+                color,
+                alpha: a * alpha,
+            },
+            None => Paint::Foreground { alpha },
+        })
+    }
+
+    fn stops(&self, stops: &[ColorStop]) -> Result<Vec<GradientStop>, Error> {
+        stops
+            .iter()
+            .map(|stop| {
+                // There's no telling what the foreground will be, black is as good as any
+                let (color, alpha) = self
+                    .color(stop.palette_index)?
+                    .unwrap_or((Rgb([0.0, 0.0, 0.0]), 1.0));
+                Ok(GradientStop {
+                    offset: stop.offset as f64,
+                    color,
+                    alpha: alpha * stop.alpha as f64,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Flattens a COLR v1 paint graph into layers: each glyph clip filled with a brush
+struct LayerPainter<'a> {
+    palette: Palette<'a>,
+    /// Cumulative, the last is current
+    transforms: Vec<Affine>,
+    /// Glyph clips and the transform they were pushed under; None for clip boxes
+    clips: Vec<Option<(GlyphId, Affine)>>,
+    layers: Vec<ColorLayer>,
+    /// ColorPainter can't fail so the first error is kept for after painting
+    error: Option<Error>,
+}
+
+impl LayerPainter<'_> {
+    fn current(&self) -> Affine {
+        self.transforms.last().copied().unwrap_or_default()
+    }
+
+    fn add_layer(
+        &mut self,
+        gid: GlyphId,
+        transform: Affine,
+        brush: Brush,
+        brush_transform: Affine,
+    ) {
+        match self.paint(brush, brush_transform) {
+            Ok(paint) => self.layers.push(ColorLayer {
+                gid,
+                transform,
+                paint,
+                path: None,
+            }),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+    }
+
+    fn paint(&self, brush: Brush, transform: Affine) -> Result<Paint, Error> {
+        let point = |x: f32, y: f32| transform * Point::new(x as f64, y as f64);
+        Ok(match brush {
+            Brush::Solid {
+                palette_index,
+                alpha,
+            } => self.palette.solid(palette_index, alpha as f64)?,
+            Brush::LinearGradient {
+                p0,
+                p1,
+                color_stops,
+                ..
+            } => Paint::Linear {
+                start: point(p0.x, p0.y),
+                end: point(p1.x, p1.y),
+                stops: self.palette.stops(color_stops)?,
+            },
+            Brush::RadialGradient {
+                c1,
+                r1,
+                color_stops,
+                ..
+            } => Paint::Radial {
+                center: point(c1.x, c1.y),
+                radius: r1 as f64 * transform.determinant().abs().sqrt(),
+                stops: self.palette.stops(color_stops)?,
+            },
+            Brush::SweepGradient { color_stops, .. } => {
+                match self.palette.stops(color_stops)?.first() {
+                    Some(stop) => Paint::Solid {
+                        color: stop.color,
+                        alpha: stop.alpha,
+                    },
+                    None => Paint::Foreground { alpha: 0.0 },
+                }
+            }
+        })
+    }
+}
+
+fn affine(transform: Transform) -> Affine {
+    Affine::new([
+        transform.xx as f64,
+        transform.yx as f64,
+        transform.xy as f64,
+        transform.yy as f64,
+        transform.dx as f64,
+        transform.dy as f64,
+    ])
+}
+
+impl ColorPainter for LayerPainter<'_> {
+    fn push_transform(&mut self, transform: Transform) {
+        let transform = self.current() * affine(transform);
+        self.transforms.push(transform);
+    }
+
+    fn pop_transform(&mut self) {
+        self.transforms.pop();
+    }
+
+    fn push_clip_glyph(&mut self, glyph_id: GlyphId) {
+        let current = self.current();
+        self.clips.push(Some((glyph_id, current)));
+    }
+
+    fn push_clip_box(&mut self, _clip_box: BoundingBox<f32>) {
+        self.clips.push(None);
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    fn fill(&mut self, brush: Brush<'_>) {
+        // Fills outside any glyph, such as backgrounds, have no outline to draw
+        let Some((gid, transform)) = self.clips.iter().rev().find_map(|clip| *clip) else {
+            return;
+        };
+        let current = self.current();
+        self.add_layer(gid, transform, brush, current);
+    }
+
+    fn fill_glyph(
+        &mut self,
+        glyph_id: GlyphId,
+        brush_transform: Option<Transform>,
+        brush: Brush<'_>,
+    ) {
+        let current = self.current();
+        let brush_transform = current * brush_transform.map(affine).unwrap_or_default();
+        self.add_layer(glyph_id, current, brush, brush_transform);
+    }
+
+    fn push_layer(&mut self, _composite_mode: CompositeMode) {}
+}
+
+/// Split each group of animated shapes into a group per color layer, each with its layer's fill
+///
+/// `drawn` are the shapes as they were drawn, before animation, and `layer_of` the index into
+/// the glyph's color layers of each. Animated shapes are matched to what was drawn or, failing
+/// that, such as for morphs, taken to be in drawing order. Gradients are placed by
+/// `font_units_to_lottie_units`. If the glyph changes palette, solid colors shift to the new
+/// palette's from `start` to `end`.
+pub(crate) fn split_by_layer(
+    shapes: &mut [AnyShape],
+    drawn: &[SubPath],
+    layer_of: &[usize],
+    glyph: &Glyph,
+    font_units_to_lottie_units: Affine,
+    start: f64,
+    end: f64,
+) -> Result<(), Error> {
+    let drawn = drawn
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(Error::JsonError)?;
+    let splitter = LayerSplitter {
+        drawn,
+        layer_of,
+        layers: glyph.color_layers(),
+        shifted_layers: glyph.shifted_color_layers(),
+        font_units_to_lottie_units,
+        start,
+        end,
+    };
+    for shape in shapes.iter_mut() {
+        splitter.split_group(shape)?;
+    }
+    Ok(())
+}
+
+struct LayerSplitter<'a> {
+    drawn: Vec<serde_json::Value>,
+    layer_of: &'a [usize],
+    layers: &'a [ColorLayer],
+    /// The layers in the palette to shift to, empty to stay put
+    shifted_layers: &'a [ColorLayer],
+    font_units_to_lottie_units: Affine,
+    start: f64,
+    end: f64,
+}
+
+impl LayerSplitter<'_> {
+    fn split_group(&self, shape: &mut AnyShape) -> Result<(), Error> {
+        let AnyShape::Group(group) = shape else {
+            return Ok(());
+        };
+        for item in group.items.iter_mut() {
+            self.split_group(item)?;
+        }
+        let Some(foreground) = group
+            .items
+            .iter()
+            .find(|item| matches!(item, AnyShape::Fill(..) | AnyShape::GradientFill(..)))
+            .cloned()
+        else {
+            return Ok(());
+        };
+
+        let mut by_layer: BTreeMap<usize, Vec<AnyShape>> = BTreeMap::new();
+        let mut rest = Vec::new();
+        let mut nth_shape = 0;
+        for item in group.items.drain(..) {
+            match item {
+                AnyShape::Shape(subpath) => {
+                    let value = serde_json::to_value(&subpath).map_err(Error::JsonError)?;
+                    let layer = self
+                        .drawn
+                        .iter()
+                        .position(|d| *d == value)
+                        .and_then(|i| self.layer_of.get(i))
+                        .or_else(|| self.layer_of.get(nth_shape))
+                        .copied()
+                        .unwrap_or_default();
+                    nth_shape += 1;
+                    by_layer
+                        .entry(layer)
+                        .or_default()
+                        .push(AnyShape::Shape(subpath));
+                }
+                AnyShape::Fill(..) | AnyShape::GradientFill(..) => (),
+                other => rest.push(other),
+            }
+        }
+
+        let mut items = Vec::with_capacity(by_layer.len() + rest.len());
+        for (layer, mut shapes) in by_layer {
+            shapes.push(self.layer_fill(&foreground, layer)?);
+            items.push(AnyShape::Group(Group {
+                name: Some(format!("color layer {layer}")),
+                items: shapes,
+                ..Default::default()
+            }));
+        }
+        items.extend(rest);
+        group.items = items;
+        Ok(())
+    }
+
+    /// The fill of `layer`, given the `foreground` fill, solid or gradient, from the style
+    fn layer_fill(&self, foreground: &AnyShape, layer_idx: usize) -> Result<AnyShape, Error> {
+        let layer = &self.layers[layer_idx];
+        let (mut fill, opacity) = match foreground {
+            AnyShape::Fill(fill) => (
+                fill.clone(),
+                match fill.opacity.value {
+                    Value::Fixed(opacity) => opacity,
+                    _ => 100.0,
+                },
+            ),
+            _ => (
+                Fill::default(),
+                serde_json::to_value(foreground).map_err(Error::JsonError)?["o"]["k"]
+                    .as_f64()
+                    .unwrap_or(100.0),
+            ),
+        };
+        let (start, end, stops, gradient_type) = match &layer.paint {
+            Paint::Foreground { alpha } => {
+                let AnyShape::Fill(..) = foreground else {
+                    // A gradient's opacity is left as the style has it
+                    return Ok(foreground.clone());
+                };
+                fill.opacity.value = Value::Fixed(opacity * alpha);
+                return Ok(AnyShape::Fill(fill));
+            }
+            Paint::Solid {
+                color: Rgb([r, g, b]),
+                alpha,
+            } => {
+                fill.color.value = Value::Fixed(vec![*r, *g, *b]);
+                fill.opacity.value = Value::Fixed(opacity * alpha);
+                if let Some(ColorLayer {
+                    paint: Paint::Solid { color: to, .. },
+                    ..
+                }) = self.shifted_layers.get(layer_idx)
+                {
+                    fill.color.animated = 1;
+                    fill.color.value = Value::Animated(vec![
+                        MultiDimensionalKeyframe {
+                            start_time: self.start,
+                            start_value: Some(vec![*r, *g, *b]),
+                            bezier: Some(default_ease()),
+                            ..Default::default()
+                        },
+                        MultiDimensionalKeyframe {
+                            start_time: self.end,
+                            start_value: Some(to.0.to_vec()),
+                            bezier: Some(default_ease()),
+                            ..Default::default()
+                        },
+                    ]);
+                }
+                return Ok(AnyShape::Fill(fill));
+            }
+            Paint::Linear { start, end, stops } => (*start, *end, stops, 1),
+            Paint::Radial {
+                center,
+                radius,
+                stops,
+            } => (*center, *center + Vec2::new(*radius, 0.0), stops, 2),
+        };
+        let (start, end) = (
+            self.font_units_to_lottie_units * start,
+            self.font_units_to_lottie_units * end,
+        );
+        gradient_fill(start, end, stops, gradient_type, opacity)
+    }
+}
+
+/// A Lottie gradient fill, type 1 linear or 2 radial, in Lottie units
+pub(crate) fn gradient_fill(
+    start: Point,
+    end: Point,
+    stops: &[GradientStop],
+    gradient_type: u8,
+    opacity: f64,
+) -> Result<AnyShape, Error> {
+    // Colors as offset, r, g, b then alphas as offset, alpha
+    let colors = stops
+        .iter()
+        .flat_map(|s| [s.offset, s.color.0[0], s.color.0[1], s.color.0[2]]);
+    let alphas = stops.iter().flat_map(|s| [s.offset, s.alpha]);
+    let gradient = json!({
+        "ty": "gf",
+        "o": { "a": 0, "k": opacity },
+        "r": 1,
+        "s": { "a": 0, "k": [start.x, start.y] },
+        "e": { "a": 0, "k": [end.x, end.y] },
+        "t": gradient_type,
+        "g": {
+            "p": stops.len(),
+            "k": { "a": 0, "k": colors.chain(alphas).collect::<Vec<_>>() },
+        },
+    });
+    AnyShape::deserialize(gradient).map_err(Error::JsonError)
+}
@@ -0,0 +1,93 @@
+//! Combine animations side by side for review, such as one icon in an old and a new template.
+
+use bodymovin::Bodymovin as Lottie;
+use serde_json::{json, Value};
+
+use crate::error::Error;
+
+/// A Lottie playing `left` and `right` next to each other, each in its own precomp
+///
+/// Plays at the frame rate of `left`; `right` is stretched so both play at their own speed.
+/// Asset ids are prefixed so the two can't collide.
+pub fn side_by_side(left: &Lottie, right: &Lottie) -> Result<Lottie, Error> {
+    let left = serde_json::to_value(left).map_err(Error::JsonError)?;
+    let right = serde_json::to_value(right).map_err(Error::JsonError)?;
+    let number = |lottie: &Value, key: &str| lottie[key].as_f64().unwrap_or_default();
+
+    let frame_rate = number(&left, "fr");
+    let mut assets = Vec::new();
+    let mut layers = Vec::new();
+    let mut x = 0.0;
+    let mut end: f64 = 0.0;
+    for (prefix, lottie) in [("left", &left), ("right", &right)] {
+        let stretch = match number(lottie, "fr") {
+            fr if fr > 0.0 && frame_rate > 0.0 => frame_rate / fr,
+            _ => 1.0,
+        };
+        let (width, height) = (number(lottie, "w"), number(lottie, "h"));
+        let (in_point, out_point) = (
+            number(lottie, "ip") * stretch,
+            number(lottie, "op") * stretch,
+        );
+        end = end.max(out_point);
+
+        for asset in lottie["assets"].as_array().into_iter().flatten() {
+            let mut asset = asset.clone();
+            if let Some(id) = asset["id"].as_str() {
+                asset["id"] = format!("{prefix}_{id}").into();
+            }
+            prefix_ref_ids(&mut asset, prefix);
+            assets.push(asset);
+        }
+        let mut precomp_layers = lottie["layers"].clone();
+        prefix_ref_ids(&mut precomp_layers, prefix);
+        assets.push(json!({ "id": prefix, "layers": precomp_layers }));
+
+        layers.push(json!({
+            "ty": 0,
+            "nm": prefix,
+            "refId": prefix,
+            "w": width,
+            "h": height,
+            "ip": in_point,
+            "op": out_point,
+            "st": 0,
+            "sr": stretch,
+            "ks": {
+                "a": { "a": 0, "k": [0, 0] },
+                "p": { "a": 0, "k": [x, 0] },
+                "s": { "a": 0, "k": [100, 100] },
+                "r": { "a": 0, "k": 0 },
+                "o": { "a": 0, "k": 100 },
+            },
+        }));
+        x += width;
+    }
+
+    let combined = json!({
+        "v": left["v"].as_str().unwrap_or("5.7.0"),
+        "fr": frame_rate,
+        "ip": 0,
+        "op": end,
+        "w": x,
+        "h": number(&left, "h").max(number(&right, "h")),
+        "nm": "side by side",
+        "assets": assets,
+        "layers": layers,
+    });
+    serde_json::from_value(combined).map_err(Error::JsonError)
+}
+
+/// Point precomp layers at the prefixed asset ids
+fn prefix_ref_ids(value: &mut Value, prefix: &str) {
+    match value {
+        Value::Array(items) => items.iter_mut().for_each(|v| prefix_ref_ids(v, prefix)),
+        Value::Object(fields) => {
+            if let Some(Value::String(ref_id)) = fields.get_mut("refId") {
+                *ref_id = format!("{prefix}_{ref_id}");
+            }
+            fields.values_mut().for_each(|v| prefix_ref_ids(v, prefix));
+        }
+        _ => (),
+    }
+}
@@ -0,0 +1,232 @@
+//! Which animations and Lottie features each player can handle.
+//!
+//! Front-ends can query the matrix to grey out combinations that won't play rather than
+//! generating and finding out afterwards.
+
+use std::str::FromStr;
+
+use serde::Serialize;
+use skrifa::GlyphId;
+
+use crate::{animate::Animation, error::Error};
+
+/// A player, or family of players, that generated Lottie is destined for
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Profile {
+    /// Telegram animated stickers, rlottie plus Telegram's own restrictions
+    Tgs,
+    Rlottie,
+    LottieWeb,
+    /// The dotLottie players, built on ThorVG
+    DotLottie,
+}
+
+impl Profile {
+    pub const ALL: [Profile; 4] = [
+        Profile::Tgs,
+        Profile::Rlottie,
+        Profile::LottieWeb,
+        Profile::DotLottie,
+    ];
+
+    pub fn supports(&self, feature: Feature) -> Support {
+        use Feature::*;
+        use Profile::*;
+        match (self, feature) {
+            (_, TransformKeyframes | OpacityKeyframes | PathMorph | Precomps | TimeStretch) => {
+                Support::Full
+            }
+            (Tgs | Rlottie, Expressions) => Support::Unsupported("expressions are not evaluated"),
+            (DotLottie, Expressions) => Support::Partial("only a subset of expressions evaluate"),
+            (LottieWeb, Expressions) => Support::Full,
+            (Tgs | Rlottie, LayerEffects) => Support::Unsupported("layer effects are ignored"),
+            (LottieWeb, LayerEffects) => Support::Partial("only the svg renderer draws effects"),
+            (DotLottie, LayerEffects) => Support::Partial("only some effects are drawn"),
+            (Tgs, Images) => Support::Unsupported("stickers may not embed images"),
+            (_, Images) => Support::Full,
+            (_, Masks | Mattes) => Support::Full,
+        }
+    }
+}
+
+impl FromStr for Profile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "tgs" => Ok(Profile::Tgs),
+            "rlottie" => Ok(Profile::Rlottie),
+            "lottie-web" => Ok(Profile::LottieWeb),
+            "dotlottie" | "dot-lottie" => Ok(Profile::DotLottie),
+            _ => Err(Error::UnknownProfile(s.to_string())),
+        }
+    }
+}
+
+/// A Lottie capability that output may rely on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Feature {
+    /// Keyframed position, scale or rotation
+    TransformKeyframes,
+    OpacityKeyframes,
+    /// Keyframed path vertices
+    PathMorph,
+    Precomps,
+    /// Layers with a stretch (sr) other than 1
+    TimeStretch,
+    Expressions,
+    LayerEffects,
+    Images,
+    /// Layer masks, keyframed or not
+    Masks,
+    /// Track mattes, a layer seen through the one above it
+    Mattes,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 10] = [
+        Feature::TransformKeyframes,
+        Feature::OpacityKeyframes,
+        Feature::PathMorph,
+        Feature::Precomps,
+        Feature::TimeStretch,
+        Feature::Expressions,
+        Feature::LayerEffects,
+        Feature::Images,
+        Feature::Masks,
+        Feature::Mattes,
+    ];
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case", tag = "support", content = "reason")]
+pub enum Support {
+    Full,
+    /// Plays, but may not look as intended
+    Partial(&'static str),
+    Unsupported(&'static str),
+}
+
+impl Support {
+    /// The weaker of the two
+    fn and(self, other: Support) -> Support {
+        match (self, other) {
+            (Support::Unsupported(..), _) => self,
+            (_, Support::Unsupported(..)) => other,
+            (Support::Partial(..), _) => self,
+            _ => other,
+        }
+    }
+}
+
+impl Animation {
+    /// The Lottie features output for this animation relies on, ignoring the template
+    pub fn features(&self) -> Vec<Feature> {
+        match self {
+            Animation::None => Vec::new(),
+            Animation::PulseWhole
+            | Animation::PulseParts { .. }
+            | Animation::TwirlWhole
+            | Animation::Shake
+            | Animation::TwirlParts { .. }
+            | Animation::Annotated { .. } => vec![Feature::TransformKeyframes],
+            Animation::WeightMorph
+            | Animation::FillToggle
+            | Animation::GradeHover
+            | Animation::AxisMorph { .. } => vec![Feature::PathMorph],
+            Animation::AxisFade { .. } | Animation::Swap { .. } => {
+                vec![Feature::TransformKeyframes, Feature::OpacityKeyframes]
+            }
+            Animation::Reveal { .. } => vec![Feature::Masks],
+            Animation::CircleReveal => vec![Feature::Mattes],
+            Animation::BlurIn => vec![
+                Feature::TransformKeyframes,
+                Feature::OpacityKeyframes,
+                Feature::LayerEffects,
+            ],
+        }
+    }
+
+    /// How well `profile` plays this animation: the weakest support of any feature it needs
+    pub fn support(&self, profile: Profile) -> Support {
+        self.features()
+            .into_iter()
+            .fold(Support::Full, |acc, f| acc.and(profile.supports(f)))
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MatrixEntry {
+    /// The kebab-case name of an animation, as the cli takes it
+    pub animation: &'static str,
+    pub profile: Profile,
+    #[serde(flatten)]
+    pub support: Support,
+}
+
+/// Every animation under every profile
+pub fn support_matrix() -> Vec<MatrixEntry> {
+    let animations = [
+        ("none", Animation::None),
+        ("pulse-whole", Animation::PulseWhole),
+        (
+            "pulse-parts",
+            Animation::PulseParts {
+                compensate_stroke: false,
+                parts: Default::default(),
+            },
+        ),
+        ("twirl-whole", Animation::TwirlWhole),
+        (
+            "twirl-parts",
+            Animation::TwirlParts {
+                parts: Default::default(),
+            },
+        ),
+        ("shake", Animation::Shake),
+        ("weight-morph", Animation::WeightMorph),
+        ("fill-toggle", Animation::FillToggle),
+        ("grade-hover", Animation::GradeHover),
+        (
+            "axis-morph",
+            Animation::AxisMorph {
+                ranges: Vec::new(),
+                samples: 0,
+            },
+        ),
+        ("axis-fade", Animation::AxisFade { ranges: Vec::new() }),
+        (
+            "annotated",
+            Animation::Annotated {
+                annotations: Default::default(),
+            },
+        ),
+        (
+            "swap",
+            Animation::Swap {
+                from: GlyphId::NOTDEF,
+                to: GlyphId::NOTDEF,
+            },
+        ),
+        ("blur-in", Animation::BlurIn),
+        (
+            "reveal",
+            Animation::Reveal {
+                direction: Default::default(),
+            },
+        ),
+        ("circle-reveal", Animation::CircleReveal),
+    ];
+    animations
+        .iter()
+        .flat_map(|(name, animation)| {
+            Profile::ALL.into_iter().map(|profile| MatrixEntry {
+                animation: name,
+                profile,
+                support: animation.support(profile),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,166 @@
+//! Pair up subpaths of two glyphs so one can morph into the other.
+//!
+//! Subpaths are matched by centroid, area and winding. Matched closed subpaths can then have
+//! their start point rotated to line up so the morph doesn't twist around the outline. Morphs
+//! put each sample in the order of the one before this way, [crate::debug_pen::DebugPen] draws
+//! what was matched with what.
+
+use kurbo::{BezPath, ParamCurve, PathEl, PathSeg, Point, Rect, Shape, Vec2};
+
+/// What's needed of a subpath to match it
+#[derive(Clone, Copy, Debug)]
+pub struct Summary {
+    pub centroid: Point,
+    pub area: f64,
+}
+
+impl Summary {
+    pub fn new(path: &BezPath) -> Self {
+        Summary {
+            centroid: centroid(path),
+            area: path.area(),
+        }
+    }
+
+    /// Counter-clockwise in y-up (font) space, clockwise in y-down (Lottie) space
+    fn positive(&self) -> bool {
+        self.area > 0.0
+    }
+}
+
+/// How the subpaths of `from` correspond to those of `to`
+#[derive(Clone, Debug, Default)]
+pub struct Correspondence {
+    /// (index in from, index in to), best match first
+    pub pairs: Vec<(usize, usize)>,
+    /// Subpaths of from with no counterpart in to, they'll have to fade or shrink away
+    pub unmatched_from: Vec<usize>,
+    /// Subpaths of to with no counterpart in from, they'll have to fade or grow in
+    pub unmatched_to: Vec<usize>,
+}
+
+/// Greedily pair the cheapest remaining subpaths, never pairing opposite windings
+///
+/// Centroid distance is relative to the size of the glyphs so the cost is scale independent.
+pub fn match_subpaths(from: &[BezPath], to: &[BezPath]) -> Correspondence {
+    let from_summary: Vec<_> = from.iter().map(Summary::new).collect();
+    let to_summary: Vec<_> = to.iter().map(Summary::new).collect();
+    let extent = from
+        .iter()
+        .chain(to.iter())
+        .map(|p| p.bounding_box())
+        .reduce(|acc, b| acc.union(b))
+        .map(|b: Rect| b.width().max(b.height()))
+        .unwrap_or(1.0)
+        .max(f64::EPSILON);
+
+    let mut candidates = Vec::new();
+    for (i, a) in from_summary.iter().enumerate() {
+        for (j, b) in to_summary.iter().enumerate() {
+            if a.positive() != b.positive() {
+                continue;
+            }
+            let distance = (a.centroid - b.centroid).hypot() / extent;
+            let (small, large) = if a.area.abs() < b.area.abs() {
+                (a.area.abs(), b.area.abs())
+            } else {
+                (b.area.abs(), a.area.abs())
+            };
+            let area = if large > 0.0 {
+                1.0 - small / large
+            } else {
+                0.0
+            };
+            candidates.push((distance + area, i, j));
+        }
+    }
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut used_from = vec![false; from.len()];
+    let mut used_to = vec![false; to.len()];
+    let mut correspondence = Correspondence::default();
+    for (_, i, j) in candidates {
+        if used_from[i] || used_to[j] {
+            continue;
+        }
+        used_from[i] = true;
+        used_to[j] = true;
+        correspondence.pairs.push((i, j));
+    }
+    correspondence.unmatched_from = (0..from.len()).filter(|i| !used_from[*i]).collect();
+    correspondence.unmatched_to = (0..to.len()).filter(|j| !used_to[*j]).collect();
+    correspondence
+}
+
+/// Rotate the start of closed subpath `to` to the vertex that best lines up with the start of `from`
+///
+/// Starts are compared relative to each subpath's centroid so a subpath that moves
+/// still lines up. Open subpaths are returned unchanged.
+pub fn align_start(from: &BezPath, to: &BezPath) -> BezPath {
+    let (Some(PathEl::MoveTo(from_start)), Some(PathEl::ClosePath)) =
+        (from.elements().first(), to.elements().last())
+    else {
+        return to.clone();
+    };
+    let segments: Vec<PathSeg> = to.segments().collect();
+    if segments.is_empty() {
+        return to.clone();
+    }
+    let from_offset = *from_start - centroid(from);
+    let to_centroid = centroid(to);
+    let best = (0..segments.len())
+        .min_by(|a, b| {
+            let cost = |i: usize| (segments[i].start() - to_centroid - from_offset).hypot2();
+            cost(*a).total_cmp(&cost(*b))
+        })
+        .unwrap_or_default();
+
+    let mut aligned = BezPath::new();
+    aligned.move_to(segments[best].start());
+    for seg in segments[best..].iter().chain(segments[..best].iter()) {
+        match *seg {
+            PathSeg::Line(line) => aligned.line_to(line.p1),
+            PathSeg::Quad(quad) => aligned.quad_to(quad.p1, quad.p2),
+            PathSeg::Cubic(cubic) => aligned.curve_to(cubic.p1, cubic.p2, cubic.p3),
+        }
+    }
+    aligned.close_path();
+    aligned
+}
+
+/// Area weighted centroid of the flattened subpath, falling back to the bbox center
+pub fn centroid(path: &BezPath) -> Point {
+    let (area, sum) = area_and_moment(path);
+    if area.abs() < f64::EPSILON {
+        return path.bounding_box().center();
+    }
+    (sum / (3.0 * area)).to_point()
+}
+
+/// Area weighted centroid of several subpaths, such as a glyph's, holes taking away from it
+/// as their winding opposes the rest; None if they enclose nothing
+pub fn area_centroid(paths: &[BezPath]) -> Option<Point> {
+    let (area, sum) = paths
+        .iter()
+        .map(area_and_moment)
+        .fold((0.0, Vec2::ZERO), |(area, sum), (a, s)| (area + a, sum + s));
+    (area.abs() >= f64::EPSILON).then(|| (sum / (3.0 * area)).to_point())
+}
+
+/// Twice the signed area of the flattened subpath and the sum its centroid is found from
+fn area_and_moment(path: &BezPath) -> (f64, Vec2) {
+    let mut points: Vec<Point> = Vec::new();
+    path.flatten(0.25, |el| match el {
+        PathEl::MoveTo(p) | PathEl::LineTo(p) => points.push(p),
+        _ => (),
+    });
+    let mut area = 0.0;
+    let mut sum = Vec2::ZERO;
+    for (i, p0) in points.iter().enumerate() {
+        let p1 = points[(i + 1) % points.len()];
+        let cross = p0.x * p1.y - p1.x * p0.y;
+        area += cross;
+        sum += (p0.to_vec2() + p1.to_vec2()) * cross;
+    }
+    (area, sum)
+}
@@ -0,0 +1,339 @@
+//! CSS keyframes for animations that only move, scale, rotate or fade groups.
+//!
+//! Such animations play as a static SVG whose groups a stylesheet animates, no player needed.
+//! Every layer and group is a `<g>`; those that animate get a class and `@keyframes` sampling
+//! their transform and opacity every frame. Paths are painted with the nearest fill above them,
+//! as Lottie paints them, and strokes are left out.
+//!
+//! Animations that change shapes or colors, use effects, mattes or precomps, or show layers
+//! for only part of the time are refused with [Error::NotTransformOnly];
+//! [crate::animated_svg] plays those.
+
+use bodymovin::Bodymovin as Lottie;
+use kurbo::{Affine, BezPath};
+use serde_json::Value;
+
+use crate::{
+    animated_svg::{path_data, rgb, round},
+    error::Error,
+    evaluate::{
+        ellipse_path, numbers_at, parent_transform, property_at, rect_path, shape_path,
+        transform_at,
+    },
+    names::is_animated,
+    output::OutputOptions,
+};
+
+/// A static SVG and the stylesheet that animates it
+#[derive(Clone, Debug)]
+pub struct CssAnimation {
+    pub svg: String,
+    pub css: String,
+}
+
+/// `lottie` as an SVG animated by CSS, its classes and keyframes named `prefix`-1, `prefix`-2
+/// and so on so several can share a page
+///
+/// The SVG has the first frame's transforms as attributes so it looks right without the
+/// stylesheet. `options.precision` rounds coordinates, `options.compact` leaves out line
+/// breaks and indentation.
+pub fn to_css_animation(
+    lottie: &Lottie,
+    prefix: &str,
+    options: &OutputOptions,
+) -> Result<CssAnimation, Error> {
+    let json = serde_json::to_value(lottie).map_err(Error::JsonError)?;
+    let (start, end) = (lottie.in_point, lottie.out_point);
+    // Layers end before their out point, sample the frames they're drawn in
+    let times: Vec<_> = if end > start && lottie.frame_rate > 0.0 {
+        (0..(end - start).ceil() as usize)
+            .map(|i| start + i as f64)
+            .collect()
+    } else {
+        vec![start]
+    };
+    let mut writer = CssWriter {
+        svg: String::new(),
+        css: String::new(),
+        prefix: prefix.to_string(),
+        classes: 0,
+        times,
+        dur: round((end - start) / lottie.frame_rate, 3),
+        precision: options.precision,
+        compact: options.compact,
+    };
+
+    writer.svg_line(
+        0,
+        &format!(
+            r#"<svg viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">"#,
+            lottie.width, lottie.height
+        ),
+    );
+    let empty = Vec::new();
+    let layers = json["layers"].as_array().unwrap_or(&empty);
+    // Layers are listed top first, paint bottom first
+    for layer in layers.iter().rev() {
+        writer.layer(layers, layer, (start, end))?;
+    }
+    writer.svg_line(0, "</svg>");
+    Ok(CssAnimation {
+        svg: writer.svg,
+        css: writer.css,
+    })
+}
+
+fn name(value: &Value) -> &str {
+    value["nm"].as_str().unwrap_or("unnamed")
+}
+
+/// How a path is painted, from a Lottie fill
+#[derive(Clone, Copy, PartialEq)]
+struct Fill {
+    color: [f64; 4],
+    even_odd: bool,
+}
+
+impl Fill {
+    fn from_item(item: &Value, t: f64) -> Option<Fill> {
+        let color = numbers_at(&item["c"], t);
+        let opacity = numbers_at(&item["o"], t).first().copied().unwrap_or(100.0);
+        if color.len() < 3 {
+            return None;
+        }
+        Some(Fill {
+            color: [
+                color[0],
+                color[1],
+                color[2],
+                opacity / 100.0 * color.get(3).copied().unwrap_or(1.0),
+            ],
+            even_odd: item["r"].as_i64() == Some(2),
+        })
+    }
+}
+
+/// What a group draws, top first
+enum Element<'a> {
+    Path(BezPath, Fill),
+    Group(&'a Value, Option<Fill>),
+}
+
+struct CssWriter {
+    svg: String,
+    css: String,
+    prefix: String,
+    classes: usize,
+    /// The frames to sample transforms at
+    times: Vec<f64>,
+    /// In seconds
+    dur: f64,
+    precision: Option<u32>,
+    compact: bool,
+}
+
+impl CssWriter {
+    fn svg_line(&mut self, depth: usize, text: &str) {
+        push_line(&mut self.svg, self.compact, depth, text);
+    }
+
+    fn css_line(&mut self, depth: usize, text: &str) {
+        push_line(&mut self.css, self.compact, depth, text);
+    }
+
+    fn layer(
+        &mut self,
+        layers: &[Value],
+        layer: &Value,
+        (start, end): (f64, f64),
+    ) -> Result<(), Error> {
+        if layer["hd"].as_bool() == Some(true) {
+            return Ok(());
+        }
+        let refuse = |why: &str| {
+            Err(Error::NotTransformOnly(format!(
+                "layer {:?} {why}",
+                name(layer)
+            )))
+        };
+        if layer["ty"].as_i64() != Some(4) {
+            return refuse("isn't a shape layer");
+        }
+        if !layer["tt"].is_null() || layer["td"].as_i64() == Some(1) {
+            return refuse("is part of a matte");
+        }
+        let shown = |key: &str| layer[key].as_f64();
+        if shown("ip").is_some_and(|ip| ip > start) || shown("op").is_some_and(|op| op < end) {
+            return refuse("isn't shown throughout");
+        }
+        for (key, value) in layer.as_object().into_iter().flatten() {
+            if !matches!(key.as_str(), "ks" | "shapes") && is_animated(value) {
+                return refuse(&format!("animates {key:?}"));
+            }
+        }
+
+        self.open_group(1, |t| {
+            let (local, opacity) = transform_at(&layer["ks"], t);
+            (parent_transform(layers, layer, t, 0) * local, opacity)
+        });
+        if let Some(items) = layer["shapes"].as_array() {
+            self.items(items, 2, None)?;
+        }
+        self.svg_line(1, "</g>");
+        Ok(())
+    }
+
+    /// Draw `items`, bottom first, each path with the nearest fill above it in `items` or else
+    /// `inherited`
+    fn items(
+        &mut self,
+        items: &[Value],
+        depth: usize,
+        inherited: Option<Fill>,
+    ) -> Result<(), Error> {
+        let t = self.times[0];
+        let mut elements: Vec<Element> = Vec::new();
+        for (i, item) in items.iter().enumerate() {
+            if item["hd"].as_bool() == Some(true) {
+                continue;
+            }
+            let ty = item["ty"].as_str().unwrap_or_default();
+            if ty != "gr" && ty != "tr" && is_animated(item) {
+                return Err(Error::NotTransformOnly(format!(
+                    "{:?} animates its {ty}",
+                    name(item)
+                )));
+            }
+            let fill = items[i + 1..]
+                .iter()
+                .filter(|item| {
+                    item["ty"].as_str() == Some("fl") && item["hd"].as_bool() != Some(true)
+                })
+                .find_map(|item| Fill::from_item(item, t))
+                .or(inherited);
+            let path = match ty {
+                "sh" => property_at(&item["ks"], t).and_then(|v| shape_path(&v)),
+                "rc" => rect_path(item, t),
+                "el" => ellipse_path(item, t),
+                "gr" => {
+                    elements.push(Element::Group(item, fill));
+                    None
+                }
+                _ => None,
+            };
+            let (Some(path), Some(fill)) = (path, fill) else {
+                continue;
+            };
+            // Paths painted alike go together, so counters cut their outlines
+            match elements.last_mut() {
+                Some(Element::Path(painted, painted_with)) if *painted_with == fill => {
+                    painted.extend(path);
+                }
+                _ => elements.push(Element::Path(path, fill)),
+            }
+        }
+
+        for element in elements.iter().rev() {
+            match element {
+                Element::Path(path, fill) => {
+                    let a = fill.color[3];
+                    let d = path_data(path, self.precision);
+                    self.svg_line(
+                        depth,
+                        &format!(
+                            "<path d=\"{d}\" fill=\"{}\" fill-opacity=\"{}\"{} />",
+                            rgb(fill.color),
+                            round(a.clamp(0.0, 1.0), 3),
+                            if fill.even_odd {
+                                " fill-rule=\"evenodd\""
+                            } else {
+                                ""
+                            }
+                        ),
+                    );
+                }
+                Element::Group(group, fill) => {
+                    let empty = Vec::new();
+                    let items = group["it"].as_array().unwrap_or(&empty);
+                    let transform = items.iter().find(|item| item["ty"].as_str() == Some("tr"));
+                    self.open_group(depth, |t| {
+                        transform
+                            .map(|tr| transform_at(tr, t))
+                            .unwrap_or((Affine::IDENTITY, 1.0))
+                    });
+                    self.items(items, depth + 1, *fill)?;
+                    self.svg_line(depth, "</g>");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Open a `<g>` with the transform and opacity `transform_at` gives at the first frame,
+    /// and keyframes for them if they change
+    fn open_group(&mut self, depth: usize, transform_at: impl Fn(f64) -> (Affine, f64)) {
+        let samples: Vec<_> = self.times.iter().map(|t| transform_at(*t)).collect();
+        let (first, first_opacity) = samples[0];
+        let mut attributes = String::new();
+        if first != Affine::IDENTITY {
+            attributes.push_str(&format!(" transform=\"matrix({})\"", matrix(first, " ")));
+        }
+        if first_opacity != 1.0 {
+            attributes.push_str(&format!(" opacity=\"{}\"", round(first_opacity, 3)));
+        }
+        if samples.iter().all(|sample| *sample == samples[0]) {
+            self.svg_line(depth, &format!("<g{attributes}>"));
+            return;
+        }
+
+        self.classes += 1;
+        let class = format!("{}-{}", self.prefix, self.classes);
+        self.svg_line(depth, &format!("<g class=\"{class}\"{attributes}>"));
+        let fades = samples.iter().any(|(_, opacity)| *opacity != first_opacity);
+        self.css_line(0, &format!(".{class} {{"));
+        self.css_line(
+            1,
+            &format!("animation: {class} {}s linear infinite;", self.dur),
+        );
+        // Transform in the SVG's units, as the transform attribute does
+        self.css_line(1, "transform-box: view-box;");
+        self.css_line(1, "transform-origin: 0 0;");
+        self.css_line(0, "}");
+        self.css_line(0, &format!("@keyframes {class} {{"));
+        // The last sample holds until the loop restarts
+        let last = *samples.last().unwrap();
+        for (i, (transform, opacity)) in samples.iter().chain([&last]).enumerate() {
+            let percent = round(i as f64 / self.times.len() as f64 * 100.0, 3);
+            let opacity = if fades {
+                format!(" opacity: {};", round(*opacity, 3))
+            } else {
+                String::new()
+            };
+            self.css_line(
+                1,
+                &format!(
+                    "{percent}% {{ transform: matrix({});{opacity} }}",
+                    matrix(*transform, ", ")
+                ),
+            );
+        }
+        self.css_line(0, "}");
+    }
+}
+
+fn push_line(out: &mut String, compact: bool, depth: usize, text: &str) {
+    if !compact {
+        out.push_str(&"  ".repeat(depth));
+    }
+    out.push_str(text);
+    if !compact {
+        out.push('\n');
+    }
+}
+
+fn matrix(transform: Affine, separator: &str) -> String {
+    transform
+        .as_coeffs()
+        .map(|c| round(c, 4).to_string())
+        .join(separator)
+}
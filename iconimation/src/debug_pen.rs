@@ -7,6 +7,7 @@ use write_fonts::pens::write_to_pen;
 
 use crate::{
     animate::{a_contained_point, group_icon_parts},
+    correspondence::{align_start, centroid, match_subpaths},
     shape_pen::SubPathPen,
 };
 
@@ -143,7 +144,8 @@ impl DebugPen {
         return self.paths.last_mut().unwrap();
     }
 
-    pub fn to_svg(self) -> String {
+    /// Our paths, flipped so they draw the right way up
+    fn flipped_paths(self) -> Vec<BezPath> {
         // It's nice to draw the right way up
         let transform = Affine::IDENTITY
             // Move center-y to be at y=0
@@ -153,14 +155,73 @@ impl DebugPen {
             // Go back again
             .then_translate((0.0, self.glyph_block.center().y).into());
 
-        let paths: Vec<_> = self
-            .paths
+        self.paths
             .into_iter()
             .map(|mut p| {
                 p.apply_affine(transform);
                 p
             })
-            .collect();
+            .collect()
+    }
+
+    /// Overlay our glyph (blue) and `other` (red) joining corresponding subpaths
+    ///
+    /// Matched subpaths are joined centroid to centroid and their aligned start points
+    /// joined by a dashed line; unmatched subpaths are drawn with a dashed outline.
+    pub fn to_correspondence_svg(self, other: DebugPen) -> String {
+        let viewbox = self.glyph_block.union(other.glyph_block);
+        let from = self.flipped_paths();
+        let to = other.flipped_paths();
+        let correspondence = match_subpaths(&from, &to);
+
+        let mut svg = format!(
+            r#"<svg viewBox="{} {} {} {}" xmlns="http://www.w3.org/2000/svg">"#,
+            viewbox.min_x(),
+            viewbox.min_y(),
+            viewbox.width(),
+            viewbox.height()
+        );
+        svg.push('\n');
+        for (paths, color, unmatched) in [
+            (&from, "blue", &correspondence.unmatched_from),
+            (&to, "red", &correspondence.unmatched_to),
+        ] {
+            for (i, path) in paths.iter().enumerate() {
+                let dashed = if unmatched.contains(&i) {
+                    " stroke-dasharray=\"8\""
+                } else {
+                    ""
+                };
+                svg.push_str(&format!(
+                    "  <path d=\"{}\" fill=\"{color}\" fill-opacity=\"0.2\" stroke=\"{color}\"{dashed} />\n",
+                    path.to_svg()
+                ));
+            }
+        }
+        for (n, (i, j)) in correspondence.pairs.iter().enumerate() {
+            let (a, b) = (centroid(&from[*i]), centroid(&to[*j]));
+            svg.push_str(&format!(
+                "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" />\n",
+                a.x, a.y, b.x, b.y
+            ));
+            svg.push_str(&format!("  <text x=\"{}\" y=\"{}\">{n}</text>\n", a.x, a.y));
+            let aligned = align_start(&from[*i], &to[*j]);
+            if let (Some(PathEl::MoveTo(a)), Some(PathEl::MoveTo(b))) =
+                (from[*i].elements().first(), aligned.elements().first())
+            {
+                svg.push_str(&format!(
+                    "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"black\" stroke-dasharray=\"4\" />\n",
+                    a.x, a.y, b.x, b.y
+                ));
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    pub fn to_svg(self) -> String {
+        let glyph_block = self.glyph_block;
+        let paths = self.flipped_paths();
 
         let shapes = paths
             .iter()
@@ -174,10 +235,10 @@ impl DebugPen {
 
         // We need one glyph block for the annotated svg plus one per group, vertically
         let viewbox = Rect::new(
-            self.glyph_block.min_x(),
-            self.glyph_block.min_y(),
-            self.glyph_block.max_x(),
-            self.glyph_block.min_y() + self.glyph_block.height() * (1 + groups.len()) as f64,
+            glyph_block.min_x(),
+            glyph_block.min_y(),
+            glyph_block.max_x(),
+            glyph_block.min_y() + glyph_block.height() * (1 + groups.len()) as f64,
         );
 
         let mut svg = format!(
@@ -197,7 +258,7 @@ impl DebugPen {
 
         for (i, group) in groups.iter().enumerate() {
             // group i draws into glyph block i+1
-            let y_offset = self.glyph_block.min_y() + (i as f64 + 1.0) * self.glyph_block.height();
+            let y_offset = glyph_block.min_y() + (i as f64 + 1.0) * glyph_block.height();
             let paths: Vec<_> = group.iter().map(|(bez, _)| bez.clone()).collect();
             draw_annotated(&mut svg, y_offset, paths);
         }
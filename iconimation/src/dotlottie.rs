@@ -0,0 +1,145 @@
+//! dotLottie bundles: a zip of one or more animations plus a manifest listing them.
+//!
+//! See <https://dotlottie.io/structure/>.
+
+use std::{
+    io::{Seek, Write},
+    path::Path,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    error::Error,
+    sink::{Sink, ZipSink},
+};
+
+/// Plays once when the pointer enters
+pub const INTRO_MARKER: &str = "intro";
+/// Plays repeatedly once clicked
+pub const LOOP_MARKER: &str = "loop";
+
+/// Writes each animation, Lottie json named for its id, into a .lottie
+///
+/// The manifest is written, and the bundle usable, once [Sink::finish] is called.
+pub struct DotLottieSink<W: Write + Seek> {
+    zip: ZipSink<W>,
+    ids: Vec<String>,
+    interactive: bool,
+}
+
+impl<W: Write + Seek> DotLottieSink<W> {
+    pub fn new(writer: W) -> Self {
+        DotLottieSink {
+            zip: ZipSink::new(writer),
+            ids: Vec::new(),
+            interactive: false,
+        }
+    }
+
+    /// Give each animation "intro" and "loop" markers, unless its template already has them,
+    /// and a state machine that plays intro on hover and loop on click
+    pub fn with_interactivity(mut self) -> Self {
+        self.interactive = true;
+        self
+    }
+
+    /// Finish, if that hasn't happened already, and return the underlying writer
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        self.finish()?;
+        self.zip.into_inner()
+    }
+}
+
+impl<W: Write + Seek> Sink for DotLottieSink<W> {
+    /// `name` is the animation id; an extension, as in "e855.json", is dropped
+    fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), Error> {
+        let id = Path::new(name)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| name.to_string());
+        if self.interactive {
+            let mut lottie: Value = serde_json::from_slice(bytes).map_err(Error::JsonError)?;
+            add_markers(&mut lottie);
+            self.zip.write(
+                &format!("animations/{id}.json"),
+                &serde_json::to_vec(&lottie).map_err(Error::JsonError)?,
+            )?;
+            self.zip.write(
+                &format!("states/{id}.json"),
+                &serde_json::to_vec(&state_machine(&id)).map_err(Error::JsonError)?,
+            )?;
+        } else {
+            self.zip.write(&format!("animations/{id}.json"), bytes)?;
+        }
+        self.ids.push(id);
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if !self.ids.is_empty() {
+            let mut manifest = json!({
+                "version": "1",
+                "generator": concat!("iconimation ", env!("CARGO_PKG_VERSION")),
+                "activeAnimationId": self.ids[0],
+                "animations": self.ids.iter().map(|id| json!({ "id": id })).collect::<Vec<_>>(),
+            });
+            if self.interactive {
+                manifest["states"] = json!(self.ids);
+            }
+            self.zip.write(
+                "manifest.json",
+                serde_json::to_string(&manifest)
+                    .map_err(Error::JsonError)?
+                    .as_bytes(),
+            )?;
+            // Only ever write the manifest once
+            self.ids.clear();
+        }
+        self.zip.finish()
+    }
+}
+
+/// Mark the first half, where generated animations move, as intro and the whole as loop
+fn add_markers(lottie: &mut Value) {
+    let (Some(ip), Some(op)) = (lottie["ip"].as_f64(), lottie["op"].as_f64()) else {
+        return;
+    };
+    if !lottie["markers"].is_array() {
+        lottie["markers"] = json!([]);
+    }
+    let markers = lottie["markers"].as_array_mut().unwrap();
+    for (name, start, duration) in [
+        (INTRO_MARKER, ip, (op - ip) / 2.0),
+        (LOOP_MARKER, ip, op - ip),
+    ] {
+        if !markers.iter().any(|m| m["cm"] == name) {
+            markers.push(json!({ "cm": name, "tm": start, "dr": duration }));
+        }
+    }
+}
+
+/// Idle until hovered, play the intro once per hover and loop once clicked, until clicked again
+fn state_machine(id: &str) -> Value {
+    json!({
+        "descriptor": { "id": id, "initial": 0 },
+        "states": [
+            { "type": "PlaybackState", "name": "idle", "animationId": id, "frame": 0, "autoplay": false, "loop": false },
+            { "type": "PlaybackState", "name": INTRO_MARKER, "animationId": id, "segment": INTRO_MARKER, "autoplay": true, "loop": false },
+            { "type": "PlaybackState", "name": LOOP_MARKER, "animationId": id, "segment": LOOP_MARKER, "autoplay": true, "loop": true },
+        ],
+        "transitions": [
+            { "type": "Transition", "fromState": 0, "toState": 1, "onPointerEnter": {} },
+            { "type": "Transition", "fromState": 1, "toState": 0, "onPointerExit": {} },
+            { "type": "Transition", "fromState": 0, "toState": 2, "onPointerDown": {} },
+            { "type": "Transition", "fromState": 1, "toState": 2, "onPointerDown": {} },
+            { "type": "Transition", "fromState": 2, "toState": 0, "onPointerDown": {} },
+        ],
+        "listeners": [
+            { "type": "PointerEnter" },
+            { "type": "PointerExit" },
+            { "type": "PointerDown" },
+        ],
+        "context_variables": [],
+    })
+}
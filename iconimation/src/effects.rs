@@ -0,0 +1,175 @@
+//! Layer effects, such as drop shadows and blurs, masks and track mattes for the layer the
+//! glyph is placed in.
+//!
+//! Effects, masks and mattes are layer properties, such as "ef", "masksProperties" and "tt" in
+//! Lottie json, that the bodymovin types don't expose as fields so they're edited as json. If a
+//! property doesn't survive the trip back into bodymovin that's an error rather than output
+//! silently missing the effect.
+//!
+//! See <https://lottiefiles.github.io/lottie-docs/effects/>.
+
+use bodymovin::{
+    layers::Shape as ShapeLayer, properties::MultiDimensionalKeyframe, shapes::AnyShape,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{animate::default_ease, error::Error, style::ShadowStyle};
+
+/// Append `effect` to the effects of `layer`
+pub(crate) fn add_layer_effect(layer: &mut ShapeLayer, effect: Value) -> Result<(), Error> {
+    edit_layer(layer, "ef", |effects| {
+        if !effects.is_array() {
+            *effects = json!([]);
+        }
+        let effects = effects.as_array_mut().unwrap();
+        let mut effect = effect;
+        // Effect indices are 1-based
+        effect["ix"] = json!(effects.len() + 1);
+        effects.push(effect);
+    })
+}
+
+/// Append `masks` to the masks of `layer`
+pub(crate) fn add_layer_masks(layer: &mut ShapeLayer, masks: Vec<Value>) -> Result<(), Error> {
+    if masks.is_empty() {
+        return Ok(());
+    }
+    edit_layer(layer, "masksProperties", |existing| {
+        if !existing.is_array() {
+            *existing = json!([]);
+        }
+        existing.as_array_mut().unwrap().extend(masks);
+    })?;
+    edit_layer(layer, "hasMask", |has_mask| *has_mask = json!(true))
+}
+
+/// A copy of `layer`, to be placed directly above it, drawing `shapes` as a matte
+/// that `layer` is seen through where the matte is opaque
+///
+/// The matte gets index `index` and shares the layer's transform, timing and parent so
+/// shapes in layer units line up.
+pub(crate) fn alpha_matte(
+    layer: &mut ShapeLayer,
+    shapes: Vec<AnyShape>,
+    index: i64,
+) -> Result<ShapeLayer, Error> {
+    let mut matte = serde_json::to_value(&*layer).map_err(Error::JsonError)?;
+    let matte = matte.as_object_mut().unwrap();
+    for key in ["tt", "td", "masksProperties", "hasMask", "ef"] {
+        matte.remove(key);
+    }
+    matte.insert("nm".to_string(), json!("matte"));
+    matte.insert("ind".to_string(), json!(index));
+    matte.insert(
+        "shapes".to_string(),
+        serde_json::to_value(shapes).map_err(Error::JsonError)?,
+    );
+    let mut matte =
+        ShapeLayer::deserialize(Value::Object(matte.clone())).map_err(Error::JsonError)?;
+    edit_layer(&mut matte, "td", |td| *td = json!(1))?;
+    // 1 is alpha matte, seen through where the layer above is opaque
+    edit_layer(layer, "tt", |tt| *tt = json!(1))?;
+    Ok(matte)
+}
+
+/// Edit the json property `key` of `layer`, null if it isn't set
+pub(crate) fn edit_layer(
+    layer: &mut ShapeLayer,
+    key: &str,
+    edit: impl FnOnce(&mut Value),
+) -> Result<(), Error> {
+    let mut json = serde_json::to_value(&*layer).map_err(Error::JsonError)?;
+    edit(&mut json[key]);
+    let expected = json[key].clone();
+    let edited = ShapeLayer::deserialize(json).map_err(Error::JsonError)?;
+    if serde_json::to_value(&edited).map_err(Error::JsonError)?[key] != expected {
+        return Err(Error::UnsupportedPropertyValue {
+            path: key.to_string(),
+            reason: "bodymovin can't represent the layer property as set".to_string(),
+        });
+    }
+    *layer = edited;
+    Ok(())
+}
+
+/// An After Effects drop shadow, which Lottie players that draw effects understand
+pub fn drop_shadow(shadow: &ShadowStyle) -> Value {
+    let [r, g, b] = shadow.color.0;
+    // Direction is clockwise from straight up, in degrees
+    let direction = shadow.offset.x.atan2(-shadow.offset.y).to_degrees();
+    effect(
+        25,
+        "Drop Shadow",
+        &[
+            (2, "Shadow Color", fixed(json!([r, g, b, 1]))),
+            // 0 to 255
+            (0, "Opacity", fixed(json!(shadow.opacity * 2.55))),
+            (0, "Direction", fixed(json!(direction))),
+            (0, "Distance", fixed(json!(shadow.offset.length()))),
+            (0, "Softness", fixed(json!(shadow.softness))),
+        ],
+    )
+}
+
+/// A Gaussian blur whose blurriness eases from `from` to `to` between `start` and `end`
+pub fn gaussian_blur(from: f64, to: f64, start: f64, end: f64) -> Result<Value, Error> {
+    let keyframe = |start_time, blurriness| MultiDimensionalKeyframe {
+        start_time,
+        start_value: Some(vec![blurriness]),
+        bezier: Some(default_ease()),
+        ..Default::default()
+    };
+    let keyframes = serde_json::to_value(vec![keyframe(start, from), keyframe(end, to)])
+        .map_err(Error::JsonError)?;
+    Ok(effect(
+        29,
+        "Gaussian Blur",
+        &[
+            (0, "Blurriness", json!({ "a": 1, "k": keyframes })),
+            // 1 blurs horizontally and vertically
+            (7, "Blur Dimensions", fixed(json!(1))),
+            (4, "Repeat Edge Pixels", fixed(json!(0))),
+        ],
+    ))
+}
+
+/// A mask that adds `path`, a shape property, fixed or keyframed, to what's shown
+pub fn add_mask(name: &str, path: Value) -> Value {
+    json!({
+        "nm": name,
+        "mode": "a",
+        "inv": false,
+        "pt": path,
+        "o": fixed(json!(100)),
+        "x": fixed(json!(0)),
+    })
+}
+
+fn fixed(value: Value) -> Value {
+    json!({ "a": 0, "k": value })
+}
+
+/// An effect of type `ty` with values, each of which has a type, 0 for a slider, 2 for a
+/// color and so on, a name and a property, fixed or keyframed
+fn effect(ty: u8, name: &str, values: &[(u8, &str, Value)]) -> Value {
+    let values: Vec<_> = values
+        .iter()
+        .enumerate()
+        .map(|(i, (ty, name, value))| {
+            json!({
+                "ty": ty,
+                "nm": name,
+                "ix": i + 1,
+                "v": value,
+            })
+        })
+        .collect();
+    json!({
+        "ty": ty,
+        "nm": name,
+        "np": values.len() + 3,
+        "en": 1,
+        "ef": values,
+    })
+}
@@ -5,6 +5,179 @@ use thiserror::Error;
 pub enum Error {
     #[error("Unable to draw: {0}")]
     DrawError(skrifa::outline::DrawError),
-    #[error("No shapes updated")]
-    NoShapesUpdated,
+    #[error(
+        "No placeholder named {marker:?} in the layers {}; name a group or shape layer {marker:?}",
+        .searched_layers.join(", ")
+    )]
+    NoPlaceholder {
+        marker: String,
+        /// The names of the layers looked in, those of precomps too
+        searched_layers: Vec<String>,
+    },
+    #[error("Unsupported template: {0}")]
+    UnsupportedTemplate(String),
+    #[error("Unsupported template value at {path}: {reason}")]
+    UnsupportedPropertyValue { path: String, reason: String },
+    #[error("Unable to read font: {0}")]
+    ReadError(skrifa::raw::ReadError),
+    #[error("No outline for gid {0}")]
+    NoOutline(skrifa::GlyphId),
+    #[error("Unrecognized animation {0:?}")]
+    UnknownAnimation(String),
+    #[error("Unrecognized profile {0:?}, expected tgs, rlottie, lottie-web or dotlottie")]
+    UnknownProfile(String),
+    #[error("Not a valid Telegram sticker: {}", .0.join("; "))]
+    NotTgs(Vec<String>),
+    #[error(
+        "Expected a codepoint such as 0xE87C, U+E87C, 59516 or the character itself, got {0:?}"
+    )]
+    BadCodepoint(String),
+    #[error(
+        "Unrecognized builtin template {0:?}, expected plain, circle-badge, rounded-card or notification-dot"
+    )]
+    UnknownTemplate(String),
+    #[error("Unrecognized fit {0:?}, expected contain, cover, stretch or none")]
+    UnknownFit(String),
+    #[error("Expected padding such as 16 or 10%, got {0:?}")]
+    BadPadding(String),
+    #[error("Unrecognized alignment {0:?}, expected center, top, bottom-left and so on")]
+    UnknownAlign(String),
+    #[error(
+        "Expected a placeholder directive such as fit=contain, align=top, pad=8 or anim=shake, got {0:?}"
+    )]
+    BadDirective(String),
+    #[error("Expected slot=codepoint, such as 2=0xE87D, got {0:?}")]
+    BadSlot(String),
+    #[error("The font has no glyph for U+{0:04X}, list-glyphs shows those it has")]
+    NoGlyphForCodepoint(u32),
+    #[error("Nothing to lay out, the text is empty")]
+    EmptyText,
+    #[error("Expected a line such as \"home e88a\" in a .codepoints file, got {0:?}")]
+    BadCodepointsLine(String),
+    #[error("Unable to fetch font: {0}")]
+    FetchError(String),
+    #[error("Unable to read web font: {0}")]
+    BadWebFont(String),
+    #[error("Unable to read SVG: {0}")]
+    BadSvg(String),
+    #[error("Unable to animate a bitmap glyph, {0}")]
+    BitmapAnimation(String),
+    #[error("Expected SVG path data such as \"M4 4 L20 4 L12 20 Z\", got {0:?}")]
+    BadPath(String),
+    #[error("Only animations that transform groups can be CSS, {0}; try an animated SVG")]
+    NotTransformOnly(String),
+    #[error("Animated SVGs can't paint {0} yet; try a gif or webp")]
+    NotAnimatableSvg(&'static str),
+    #[error("Not drawn from a font, there are no other glyphs to draw")]
+    NotFromFont,
+    #[error("No single glyph for the sequence {0}")]
+    NoGlyphForSequence(String),
+    #[cfg(feature = "shaping")]
+    #[error("Unable to load the font for shaping")]
+    ShapingFontError,
+    #[error("Expected tag=value, such as wght=700, got {0:?}")]
+    BadDesignLocation(String),
+    #[error("The {0} axis is set more than once")]
+    DuplicateAxis(skrifa::Tag),
+    #[error("Expected tag:from..to, such as wght:100..700, got {0:?}")]
+    BadAxisRange(String),
+    #[error("Bad part annotation: {0}")]
+    BadAnnotation(String),
+    #[error("Expected a color such as #1A73E8, got {0:?}")]
+    BadColor(String),
+    #[error("Expected dash and gap lengths such as 4,2, got {0:?}")]
+    BadDash(String),
+    #[error("Expected a shadow offset such as 2,3, got {0:?}")]
+    BadShadow(String),
+    #[error("Bad color glyph: {0}")]
+    BadColorGlyph(String),
+    #[error("The font has no {0} axis")]
+    NoSuchAxis(skrifa::Tag),
+    #[error("Outlines aren't point compatible across the sampled locations")]
+    IncompatibleOutlines,
+    #[error("Contour {contour} of sample {sample} doesn't match the first sample: {reason}")]
+    ContourMismatch {
+        sample: usize,
+        contour: usize,
+        reason: String,
+    },
+    #[error("Unable to convert to or from json: {0}")]
+    JsonError(serde_json::Error),
+    #[error("IO error: {0}")]
+    IoError(std::io::Error),
+    #[error("Unable to write zip: {0}")]
+    ZipError(zip::result::ZipError),
+    #[error("Unable to rasterize: {0}")]
+    RasterError(String),
+    #[error("Unable to compile the schema: {0}")]
+    BadSchema(String),
+    #[error("Golden frames differ: {0}")]
+    GoldenMismatch(String),
+    #[error("Unable to draw {what} in {layer}; see the README for what previews draw")]
+    Undrawable { what: String, layer: String },
+    #[error("Expected a positive frame rate, got {0}")]
+    BadFrameRate(f64),
+    #[error("Unable to encode video: {0}")]
+    VideoError(String),
+    #[error("No glyph named {0:?}")]
+    NoGlyphNamed(String),
+    #[error("{0} is required to {1}")]
+    MissingArgument(&'static str, &'static str),
+    #[error("{0} needs the {1} feature")]
+    FeatureDisabled(String, &'static str),
+    #[error("Unable to read {path}: {source}")]
+    ReadFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Unable to write {path}: {source}")]
+    WriteFile {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("Unable to parse {path}: {reason}")]
+    ParseFile { path: String, reason: String },
+    #[error("Unable to load template {path}: {source}")]
+    TemplateLoad {
+        path: String,
+        source: TemplateLoadError,
+    },
+    #[error("{problems} of {placeholders} placeholders have problems")]
+    PlaceholderProblems {
+        problems: usize,
+        placeholders: usize,
+    },
+    #[error("{failed} of {jobs} jobs failed")]
+    JobsFailed { failed: usize, jobs: usize },
+    #[error("{icon}: {violations} schema violations")]
+    SchemaViolations { icon: String, violations: usize },
+    #[error("{0} icons can't all be written to stdout; give an --out-file with {{icon}} in it, or use --format lottie")]
+    ManyToStdout(usize),
+    #[error("Bad url {0}")]
+    BadUrl(String),
+    #[cfg(feature = "object_store")]
+    #[error("Object store error: {0}")]
+    ObjectStoreError(object_store::Error),
+}
+
+/// Why a template file didn't load
+#[derive(Debug, Error)]
+pub enum TemplateLoadError {
+    #[error("{0}")]
+    Io(std::io::Error),
+    #[error("not valid json, {0}")]
+    Json(serde_json::Error),
+}
+
+impl Error {
+    /// `self`, with the path of an [Error::UnsupportedPropertyValue] made relative to `parent`
+    pub(crate) fn within(self, parent: &str) -> Self {
+        match self {
+            Error::UnsupportedPropertyValue { path, reason } => Error::UnsupportedPropertyValue {
+                path: format!("{parent}.{path}"),
+                reason,
+            },
+            e => e,
+        }
+    }
 }
@@ -0,0 +1,1508 @@
+//! Evaluate a Lottie at a point in time, producing filled paths.
+//!
+//! Works on json rather than the bodymovin structs so anything we emit, including the parts
+//! bodymovin doesn't model, can be sampled. Covers what we generate and what typical templates
+//! use: shape, solid, image and precomp layers, parenting, masks, track mattes, groups, paths,
+//! rects, ellipses, stars, fills, strokes, gradients, repeaters and transforms. Anything else,
+//! such as effects, text or trim paths, is refused by [Evaluator::new] rather than drawn wrong.
+
+use std::{
+    collections::HashMap,
+    f64::consts::{FRAC_PI_2, TAU},
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bodymovin::Bodymovin as Lottie;
+use kurbo::{
+    stroke, Affine, BezPath, Cap, Ellipse, Join, Point, Rect, RoundedRect, Shape, Stroke,
+    StrokeOpts, Vec2,
+};
+use serde_json::Value;
+
+use crate::{error::Error, placement::Placement};
+
+/// A path and what to paint it with, in composition units
+#[derive(Clone, Debug, Default)]
+pub struct FilledPath {
+    pub path: BezPath,
+    /// Red, green, blue, alpha in 0..1; for gradients and images, a color near the shader's,
+    /// for outputs that only paint solid colors
+    pub color: [f64; 4],
+    pub even_odd: bool,
+    /// Paint with a gradient or image rather than `color`
+    pub shader: Option<Shader>,
+    /// Paint only where every clip shows, from masks and track mattes
+    pub clips: Vec<Clip>,
+}
+
+/// Paint that varies across a path
+#[derive(Clone, Debug)]
+pub enum Shader {
+    /// From `start` to `end`, which `transform` takes to composition units
+    LinearGradient {
+        start: Point,
+        end: Point,
+        stops: Vec<ColorStop>,
+        transform: Affine,
+    },
+    /// Out from `center` to `radius`, starting at `focus`
+    RadialGradient {
+        center: Point,
+        focus: Point,
+        radius: f64,
+        stops: Vec<ColorStop>,
+        transform: Affine,
+    },
+    /// A `width` by `height` png, which `transform` takes from pixels to composition units
+    Image {
+        png: Arc<Vec<u8>>,
+        width: f64,
+        height: f64,
+        transform: Affine,
+        opacity: f64,
+    },
+}
+
+/// A color along a gradient, from 0 to 1, its opacity folded into alpha
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    pub offset: f64,
+    pub color: [f64; 4],
+}
+
+/// Where to paint, by what other fills cover
+#[derive(Clone, Debug, Default)]
+pub struct Clip {
+    pub fills: Vec<FilledPath>,
+    /// By luminance, for luma mattes, rather than by alpha
+    pub luma: bool,
+    /// Show where `fills` don't, for subtracted masks and inverted mattes
+    pub inverted: bool,
+}
+
+impl FilledPath {
+    /// Moved by `affine`, which applies to its shader and clips too, and faded by `opacity`
+    fn transformed(&self, affine: Affine, opacity: f64) -> FilledPath {
+        let [r, g, b, a] = self.color;
+        let fade = |stops: &[ColorStop]| -> Vec<ColorStop> {
+            stops
+                .iter()
+                .map(|stop| {
+                    let [r, g, b, a] = stop.color;
+                    ColorStop {
+                        offset: stop.offset,
+                        color: [r, g, b, a * opacity],
+                    }
+                })
+                .collect()
+        };
+        let shader = self.shader.as_ref().map(|shader| match shader {
+            Shader::LinearGradient {
+                start,
+                end,
+                stops,
+                transform,
+            } => Shader::LinearGradient {
+                start: *start,
+                end: *end,
+                stops: fade(stops),
+                transform: affine * *transform,
+            },
+            Shader::RadialGradient {
+                center,
+                focus,
+                radius,
+                stops,
+                transform,
+            } => Shader::RadialGradient {
+                center: *center,
+                focus: *focus,
+                radius: *radius,
+                stops: fade(stops),
+                transform: affine * *transform,
+            },
+            Shader::Image {
+                png,
+                width,
+                height,
+                transform,
+                opacity: image_opacity,
+            } => Shader::Image {
+                png: png.clone(),
+                width: *width,
+                height: *height,
+                transform: affine * *transform,
+                opacity: image_opacity * opacity,
+            },
+        });
+        FilledPath {
+            path: affine * self.path.clone(),
+            color: [r, g, b, a * opacity],
+            even_odd: self.even_odd,
+            shader,
+            clips: self
+                .clips
+                .iter()
+                .map(|clip| Clip {
+                    fills: clip
+                        .fills
+                        .iter()
+                        .map(|fill| fill.transformed(affine, 1.0))
+                        .collect(),
+                    ..*clip
+                })
+                .collect(),
+        }
+    }
+}
+
+/// What to paint for one frame, back to front
+#[derive(Clone, Debug, Default)]
+pub struct Frame {
+    pub width: f64,
+    pub height: f64,
+    pub fills: Vec<FilledPath>,
+}
+
+impl Frame {
+    pub fn to_svg(&self) -> String {
+        let mut svg = format!(
+            r#"<svg viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">"#,
+            self.width, self.height
+        );
+        svg.push('\n');
+        let mut writer = SvgFills {
+            svg,
+            width: self.width,
+            height: self.height,
+            ids: 0,
+        };
+        for fill in self.fills.iter() {
+            writer.fill(fill, 1);
+        }
+        let mut svg = writer.svg;
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+/// Writes filled paths as svg, defining gradients and masks next to what uses them
+struct SvgFills {
+    svg: String,
+    width: f64,
+    height: f64,
+    /// How many ids have been handed out
+    ids: usize,
+}
+
+impl SvgFills {
+    fn line(&mut self, depth: usize, text: &str) {
+        self.svg.push_str(&"  ".repeat(depth));
+        self.svg.push_str(text);
+        self.svg.push('\n');
+    }
+
+    fn id(&mut self, prefix: &str) -> String {
+        self.ids += 1;
+        format!("{prefix}{}", self.ids)
+    }
+
+    fn fill(&mut self, fill: &FilledPath, depth: usize) {
+        // Each clip wraps the fill in a masked group
+        let masks: Vec<_> = fill
+            .clips
+            .iter()
+            .map(|clip| self.mask(clip, depth))
+            .collect();
+        for (i, mask) in masks.iter().enumerate() {
+            self.line(depth + i, &format!("<g mask=\"url(#{mask})\">"));
+        }
+        let depth = depth + masks.len();
+        let fill_rule = if fill.even_odd {
+            " fill-rule=\"evenodd\""
+        } else {
+            ""
+        };
+        match &fill.shader {
+            None => {
+                let [r, g, b, a] = fill.color.map(|c| c.clamp(0.0, 1.0));
+                self.line(
+                    depth,
+                    &format!(
+                        "<path d=\"{}\" fill=\"rgb({}, {}, {})\" fill-opacity=\"{a}\"{fill_rule} />",
+                        fill.path.to_svg(),
+                        (r * 255.0).round(),
+                        (g * 255.0).round(),
+                        (b * 255.0).round(),
+                    ),
+                );
+            }
+            Some(Shader::Image {
+                png,
+                width,
+                height,
+                transform,
+                opacity,
+            }) => {
+                // The path is the image's bounds
+                self.line(
+                    depth,
+                    &format!(
+                        "<image href=\"data:image/png;base64,{}\" width=\"{width}\" height=\"{height}\" transform=\"{}\" opacity=\"{}\" />",
+                        STANDARD.encode(png.as_slice()),
+                        svg_matrix(*transform),
+                        opacity.clamp(0.0, 1.0)
+                    ),
+                );
+            }
+            Some(gradient) => {
+                let id = self.gradient(gradient, depth);
+                self.line(
+                    depth,
+                    &format!(
+                        "<path d=\"{}\" fill=\"url(#{id})\"{fill_rule} />",
+                        fill.path.to_svg()
+                    ),
+                );
+            }
+        }
+        for i in (0..masks.len()).rev() {
+            self.line(depth - masks.len() + i, "</g>");
+        }
+    }
+
+    /// Define `gradient`, returning its id
+    fn gradient(&mut self, gradient: &Shader, depth: usize) -> String {
+        let id = self.id("gradient");
+        let (element, attributes, stops, transform) = match gradient {
+            Shader::LinearGradient {
+                start,
+                end,
+                stops,
+                transform,
+            } => (
+                "linearGradient",
+                format!(
+                    "x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"",
+                    start.x, start.y, end.x, end.y
+                ),
+                stops,
+                transform,
+            ),
+            Shader::RadialGradient {
+                center,
+                focus,
+                radius,
+                stops,
+                transform,
+            } => (
+                "radialGradient",
+                format!(
+                    "cx=\"{}\" cy=\"{}\" r=\"{radius}\" fx=\"{}\" fy=\"{}\"",
+                    center.x, center.y, focus.x, focus.y
+                ),
+                stops,
+                transform,
+            ),
+            Shader::Image { .. } => return id,
+        };
+        self.line(
+            depth,
+            &format!(
+                "<{element} id=\"{id}\" gradientUnits=\"userSpaceOnUse\" {attributes} gradientTransform=\"{}\">",
+                svg_matrix(*transform)
+            ),
+        );
+        for stop in stops.iter() {
+            let [r, g, b, a] = stop.color.map(|c| c.clamp(0.0, 1.0));
+            self.line(
+                depth + 1,
+                &format!(
+                    "<stop offset=\"{}\" stop-color=\"rgb({}, {}, {})\" stop-opacity=\"{a}\" />",
+                    stop.offset,
+                    (r * 255.0).round(),
+                    (g * 255.0).round(),
+                    (b * 255.0).round(),
+                ),
+            );
+        }
+        self.line(depth, &format!("</{element}>"));
+        id
+    }
+
+    /// Define a mask for `clip`, returning its id
+    fn mask(&mut self, clip: &Clip, depth: usize) -> String {
+        let id = self.id("mask");
+        let (width, height) = (self.width, self.height);
+        let luminance = clip.luma || clip.inverted;
+        self.line(
+            depth,
+            &format!(
+                "<mask id=\"{id}\" maskUnits=\"userSpaceOnUse\" x=\"0\" y=\"0\" width=\"{width}\" height=\"{height}\" style=\"mask-type:{}\">",
+                if luminance { "luminance" } else { "alpha" }
+            ),
+        );
+        if clip.inverted {
+            // White where nothing covers; what covers is recolored so it masks by how much it
+            // covers, or by its luminance, taken from white
+            let filter = self.id("invert");
+            let matrix = if clip.luma {
+                "-1 0 0 0 1 0 -1 0 0 1 0 0 -1 0 1 0 0 0 1 0"
+            } else {
+                "0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 0 1 0"
+            };
+            self.line(
+                depth + 1,
+                &format!(
+                    "<filter id=\"{filter}\" filterUnits=\"userSpaceOnUse\" x=\"0\" y=\"0\" width=\"{width}\" height=\"{height}\" color-interpolation-filters=\"sRGB\">"
+                ),
+            );
+            self.line(
+                depth + 2,
+                &format!("<feColorMatrix type=\"matrix\" values=\"{matrix}\" />"),
+            );
+            self.line(depth + 1, "</filter>");
+            self.line(
+                depth + 1,
+                &format!("<rect width=\"{width}\" height=\"{height}\" fill=\"white\" />"),
+            );
+            self.line(depth + 1, &format!("<g filter=\"url(#{filter})\">"));
+            for fill in clip.fills.iter() {
+                self.fill(fill, depth + 2);
+            }
+            self.line(depth + 1, "</g>");
+        } else {
+            for fill in clip.fills.iter() {
+                self.fill(fill, depth + 1);
+            }
+        }
+        self.line(depth, "</mask>");
+        id
+    }
+}
+
+fn svg_matrix(transform: Affine) -> String {
+    let [a, b, c, d, e, f] = transform.as_coeffs();
+    format!("matrix({a} {b} {c} {d} {e} {f})")
+}
+
+/// `lottie` `seconds` after its in point, such as for a still for documentation
+///
+/// Fails with [Error::Undrawable] rather than leave out what the evaluator can't draw.
+pub fn snapshot(lottie: &Lottie, seconds: f64) -> Result<Frame, Error> {
+    let evaluator = Evaluator::new(lottie)?;
+    Ok(evaluator.frame(evaluator.in_point() + seconds * evaluator.frame_rate()))
+}
+
+/// The shapes `lottie`, as json, draws `seconds` after its in point, only those within the
+/// placeholders of `placement` if given, such as to check what a template was filled with
+///
+/// Placeholders keep the transforms and paints of the groups and layers around them.
+pub fn extract_shapes(
+    mut lottie: Value,
+    placement: Option<&Placement>,
+    seconds: f64,
+) -> Result<Frame, Error> {
+    if let Some(placement) = placement {
+        only_placeholders(&mut lottie, placement);
+    }
+    let evaluator = Evaluator::from_json(lottie)?;
+    Ok(evaluator.frame(evaluator.in_point() + seconds * evaluator.frame_rate()))
+}
+
+/// Hide every shape, and layer, that isn't within a placeholder
+fn only_placeholders(lottie: &mut Value, placement: &Placement) {
+    let Value::Object(fields) = lottie else {
+        return;
+    };
+    let mut layer_lists = Vec::new();
+    for (key, value) in fields.iter_mut() {
+        match (key.as_str(), value) {
+            ("layers", Value::Array(layers)) => layer_lists.push(layers),
+            ("assets", Value::Array(assets)) => layer_lists.extend(
+                assets
+                    .iter_mut()
+                    .filter_map(|asset| asset.get_mut("layers"))
+                    .filter_map(Value::as_array_mut),
+            ),
+            _ => (),
+        }
+    }
+    for layers in layer_lists {
+        for layer in layers.iter_mut() {
+            let shown = match layer["ty"].as_i64() {
+                // Precomps show what's left of their layers, nulls draw nothing anyway
+                Some(0) | Some(3) => true,
+                Some(4) => {
+                    placement.is_placeholder(layer["nm"].as_str())
+                        || layer
+                            .get_mut("shapes")
+                            .and_then(Value::as_array_mut)
+                            .is_some_and(|items| only_placeholder_items(items, placement))
+                }
+                _ => false,
+            };
+            if !shown {
+                layer["hd"] = Value::Bool(true);
+            }
+        }
+    }
+}
+
+/// Drop shapes outside placeholders from `items`, returning whether any are within one
+fn only_placeholder_items(items: &mut Vec<Value>, placement: &Placement) -> bool {
+    let mut found = false;
+    items.retain_mut(|item| match item["ty"].as_str() {
+        Some("gr") => {
+            let keep = placement.is_placeholder(item["nm"].as_str())
+                || item
+                    .get_mut("it")
+                    .and_then(Value::as_array_mut)
+                    .is_some_and(|items| only_placeholder_items(items, placement));
+            found |= keep;
+            keep
+        }
+        Some("sh" | "rc" | "el" | "sr") => false,
+        // Transforms and paints apply to placeholders within
+        _ => true,
+    });
+    found
+}
+
+/// Samples a Lottie at arbitrary frames
+pub struct Evaluator {
+    json: Value,
+    /// Embedded pngs, by asset id
+    images: HashMap<String, Arc<Vec<u8>>>,
+}
+
+impl Evaluator {
+    /// Fails with [Error::Undrawable] for content that would be drawn wrong, see the module docs
+    pub fn new(lottie: &Lottie) -> Result<Self, Error> {
+        Self::from_json(serde_json::to_value(lottie).map_err(Error::JsonError)?)
+    }
+
+    pub fn from_json(json: Value) -> Result<Self, Error> {
+        let images = embedded_images(&json);
+        check_drawable(&json, &images)?;
+        Ok(Evaluator { json, images })
+    }
+
+    pub fn in_point(&self) -> f64 {
+        number(&self.json["ip"]).unwrap_or_default()
+    }
+
+    pub fn out_point(&self) -> f64 {
+        number(&self.json["op"]).unwrap_or_default()
+    }
+
+    pub fn frame_rate(&self) -> f64 {
+        number(&self.json["fr"]).unwrap_or(60.0)
+    }
+
+    pub fn width(&self) -> f64 {
+        number(&self.json["w"]).unwrap_or_default()
+    }
+
+    pub fn height(&self) -> f64 {
+        number(&self.json["h"]).unwrap_or_default()
+    }
+
+    /// Evaluate at `frame`, in composition frames (not seconds)
+    pub fn frame(&self, frame: f64) -> Frame {
+        let mut fills = Vec::new();
+        if let Some(layers) = self.json["layers"].as_array() {
+            self.render_layers(layers, frame, Affine::IDENTITY, 1.0, 0, &mut fills);
+        }
+        Frame {
+            width: self.width(),
+            height: self.height(),
+            fills,
+        }
+    }
+
+    fn asset(&self, id: &str) -> Option<&Value> {
+        self.json["assets"]
+            .as_array()?
+            .iter()
+            .find(|asset| asset["id"].as_str() == Some(id))
+    }
+
+    fn render_layers(
+        &self,
+        layers: &[Value],
+        t: f64,
+        transform: Affine,
+        opacity: f64,
+        depth: usize,
+        fills: &mut Vec<FilledPath>,
+    ) {
+        // A precomp that (indirectly) contains itself
+        if depth > 16 {
+            return;
+        }
+        // Layers are listed top first, paint bottom first
+        for (i, layer) in layers.iter().enumerate().rev() {
+            // Mattes are only drawn through the layers they matte
+            if layer["td"].as_i64() == Some(1) {
+                continue;
+            }
+            let start = fills.len();
+            self.render_layer(layers, layer, t, transform, opacity, depth, fills);
+            let mode = layer["tt"].as_i64().unwrap_or_default();
+            if !(1..=4).contains(&mode) || fills.len() == start {
+                continue;
+            }
+            // The matte is named by index, or in older files is the layer above
+            let source = match layer["tp"].as_i64() {
+                Some(index) => layers.iter().find(|l| l["ind"].as_i64() == Some(index)),
+                None => i.checked_sub(1).map(|above| &layers[above]),
+            };
+            let mut matte = Vec::new();
+            if let Some(source) = source {
+                // Opacity above the layers applies to the matted layer, not again to its matte
+                self.render_layer(layers, source, t, transform, 1.0, depth, &mut matte);
+            }
+            let clip = Clip {
+                fills: matte,
+                luma: mode >= 3,
+                inverted: mode % 2 == 0,
+            };
+            for fill in fills[start..].iter_mut() {
+                fill.clips.push(clip.clone());
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_layer(
+        &self,
+        layers: &[Value],
+        layer: &Value,
+        t: f64,
+        transform: Affine,
+        opacity: f64,
+        depth: usize,
+        fills: &mut Vec<FilledPath>,
+    ) {
+        if layer["hd"].as_bool() == Some(true) {
+            return;
+        }
+        let in_point = number(&layer["ip"]).unwrap_or(f64::MIN);
+        let out_point = number(&layer["op"]).unwrap_or(f64::MAX);
+        if t < in_point || t >= out_point {
+            return;
+        }
+        let (local, local_opacity) = transform_at(&layer["ks"], t);
+        let layer_transform = transform * parent_transform(layers, layer, t, 0) * local;
+        let opacity = opacity * local_opacity;
+        let first = fills.len();
+
+        match layer["ty"].as_i64() {
+            // precomp
+            Some(0) => {
+                let Some(inner) = layer["refId"]
+                    .as_str()
+                    .and_then(|ref_id| self.asset(ref_id))
+                    .and_then(|asset| asset["layers"].as_array())
+                else {
+                    return;
+                };
+                let start = number(&layer["st"]).unwrap_or_default();
+                let stretch = number(&layer["sr"]).unwrap_or(1.0);
+                self.render_layers(
+                    inner,
+                    (t - start) / stretch,
+                    layer_transform,
+                    opacity,
+                    depth + 1,
+                    fills,
+                );
+            }
+            // solid
+            Some(1) => {
+                let size = (
+                    number(&layer["sw"]).unwrap_or_default(),
+                    number(&layer["sh"]).unwrap_or_default(),
+                );
+                let Some([r, g, b]) = layer["sc"].as_str().and_then(hex_color) else {
+                    return;
+                };
+                fills.push(FilledPath {
+                    path: layer_transform * Rect::from_origin_size(Point::ZERO, size).to_path(0.1),
+                    color: [r, g, b, opacity],
+                    ..Default::default()
+                });
+            }
+            // image
+            Some(2) => {
+                let Some((asset, png)) = layer["refId"].as_str().and_then(|ref_id| {
+                    Some((self.asset(ref_id)?, self.images.get(ref_id)?.clone()))
+                }) else {
+                    return;
+                };
+                let (width, height) = (
+                    number(&asset["w"]).unwrap_or_default(),
+                    number(&asset["h"]).unwrap_or_default(),
+                );
+                fills.push(FilledPath {
+                    path: layer_transform * Rect::new(0.0, 0.0, width, height).to_path(0.1),
+                    color: [0.5, 0.5, 0.5, opacity],
+                    shader: Some(Shader::Image {
+                        png,
+                        width,
+                        height,
+                        transform: layer_transform,
+                        opacity,
+                    }),
+                    ..Default::default()
+                });
+            }
+            // shape
+            Some(4) => {
+                if let Some(items) = layer["shapes"].as_array() {
+                    let painted = render_items(items, t, opacity).painted;
+                    fills.extend(
+                        painted
+                            .iter()
+                            .rev()
+                            .map(|fill| fill.transformed(layer_transform, 1.0)),
+                    );
+                }
+            }
+            _ => (),
+        }
+
+        let clips = mask_clips(&layer["masksProperties"], t, layer_transform);
+        if !clips.is_empty() {
+            for fill in fills[first..].iter_mut() {
+                fill.clips.extend(clips.iter().cloned());
+            }
+        }
+    }
+}
+
+/// Embedded pngs, by asset id; images that aren't embedded pngs can't be drawn
+fn embedded_images(json: &Value) -> HashMap<String, Arc<Vec<u8>>> {
+    json["assets"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|asset| {
+            let data = asset["p"]
+                .as_str()?
+                .strip_prefix("data:image/png;base64,")?;
+            let png = STANDARD.decode(data).ok()?;
+            Some((asset["id"].as_str()?.to_string(), Arc::new(png)))
+        })
+        .collect()
+}
+
+/// Refuse what [Evaluator::frame] would draw wrong, or not at all, rather than draw it
+fn check_drawable(json: &Value, images: &HashMap<String, Arc<Vec<u8>>>) -> Result<(), Error> {
+    let layer_lists = [&json["layers"]].into_iter().chain(
+        json["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|asset| &asset["layers"]),
+    );
+    for layers in layer_lists {
+        for (i, layer) in layers.as_array().into_iter().flatten().enumerate() {
+            if layer["hd"].as_bool() == Some(true) {
+                continue;
+            }
+            check_layer(layer, images).map_err(|what| Error::Undrawable {
+                what,
+                layer: match layer["nm"].as_str() {
+                    Some(name) => format!("layer {name:?}"),
+                    None => format!("layer {i}"),
+                },
+            })?;
+        }
+    }
+    Ok(())
+}
+
+fn check_layer(layer: &Value, images: &HashMap<String, Arc<Vec<u8>>>) -> Result<(), String> {
+    match layer["ty"].as_i64() {
+        Some(0 | 1 | 3 | 4) => (),
+        Some(2) => {
+            if !layer["refId"]
+                .as_str()
+                .is_some_and(|ref_id| images.contains_key(ref_id))
+            {
+                return Err("an image that isn't an embedded png".to_string());
+            }
+        }
+        Some(5) => return Err("text".to_string()),
+        Some(ty) => return Err(format!("a type {ty} layer")),
+        None => return Err("a layer without a type".to_string()),
+    }
+    if layer["ddd"].as_i64() == Some(1) {
+        return Err("3D".to_string());
+    }
+    if !layer["tm"].is_null() {
+        return Err("time remapping".to_string());
+    }
+    if layer["bm"].as_i64().unwrap_or_default() != 0 {
+        return Err("a blend mode".to_string());
+    }
+    if layer["ef"]
+        .as_array()
+        .is_some_and(|effects| effects.iter().any(|e| e["en"].as_i64() != Some(0)))
+    {
+        return Err("effects".to_string());
+    }
+    if layer["sy"]
+        .as_array()
+        .is_some_and(|styles| !styles.is_empty())
+    {
+        return Err("layer styles".to_string());
+    }
+    match layer["tt"].as_i64() {
+        None | Some(0..=4) => (),
+        Some(mode) => return Err(format!("track matte mode {mode}")),
+    }
+    let mut subtracted = false;
+    for mask in layer["masksProperties"].as_array().into_iter().flatten() {
+        match mask["mode"].as_str().unwrap_or("a") {
+            "n" => continue,
+            "a" if subtracted => {
+                return Err("a mask added after one subtracted or intersected".to_string())
+            }
+            "a" => (),
+            "s" | "i" => subtracted = true,
+            mode => return Err(format!("mask mode {mode:?}")),
+        }
+        if mask["x"]["a"].as_i64() == Some(1) || numbers(&mask["x"]["k"]).iter().any(|x| *x != 0.0)
+        {
+            return Err("mask expansion".to_string());
+        }
+    }
+    check_items(&layer["shapes"])?;
+    if has_expression(layer) {
+        return Err("expressions".to_string());
+    }
+    Ok(())
+}
+
+fn check_items(items: &Value) -> Result<(), String> {
+    for item in items.as_array().into_iter().flatten() {
+        if item["hd"].as_bool() == Some(true) {
+            continue;
+        }
+        let what = match item["ty"].as_str().unwrap_or_default() {
+            "gr" => {
+                check_items(&item["it"])?;
+                continue;
+            }
+            "sh" | "rc" | "el" | "fl" | "gf" | "st" | "gs" | "tr" | "rp" => continue,
+            "sr" => {
+                let rounded = |key: &str| {
+                    item[key]["a"].as_i64() == Some(1)
+                        || numbers(&item[key]["k"]).iter().any(|r| *r != 0.0)
+                };
+                if !rounded("os") && !rounded("is") {
+                    continue;
+                }
+                "rounded stars and polygons"
+            }
+            "tm" => "trim paths",
+            "mm" => "merge paths",
+            "rd" => "round corners",
+            "op" => "offset paths",
+            "pb" => "pucker and bloat",
+            "tw" => "twist",
+            "zz" => "zig zag",
+            other => return Err(format!("{other:?} shapes")),
+        };
+        return Err(what.to_string());
+    }
+    Ok(())
+}
+
+/// Whether any property within `value` is driven by an expression
+fn has_expression(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => {
+            (fields.contains_key("k")
+                && fields
+                    .get("x")
+                    .and_then(Value::as_str)
+                    .is_some_and(|x| !x.is_empty()))
+                || fields.values().any(has_expression)
+        }
+        Value::Array(values) => values.iter().any(has_expression),
+        _ => false,
+    }
+}
+
+/// Clips for a layer's masks: one showing what's added, then one for each mask subtracted or
+/// intersected, which [check_layer] requires come after those added
+fn mask_clips(masks: &Value, t: f64, transform: Affine) -> Vec<Clip> {
+    let mut added = Clip::default();
+    let mut any_added = false;
+    let mut clips = Vec::new();
+    for mask in masks.as_array().into_iter().flatten() {
+        let mode = mask["mode"].as_str().unwrap_or("a");
+        if mode == "n" {
+            continue;
+        }
+        let path = property_at(&mask["pt"], t)
+            .and_then(|v| shape_path(&v))
+            .unwrap_or_default();
+        let opacity = numbers_at(&mask["o"], t).first().copied().unwrap_or(100.0) / 100.0;
+        let inverted = mask["inv"].as_bool() == Some(true);
+        let covered = |path: BezPath, inverted: bool| {
+            let (path, even_odd) = if inverted {
+                // Everything but the path, within bounds far beyond any composition
+                let mut outside = Rect::new(-1e6, -1e6, 1e6, 1e6).to_path(0.1);
+                outside.extend(path);
+                (outside, true)
+            } else {
+                (path, false)
+            };
+            FilledPath {
+                path,
+                color: [1.0, 1.0, 1.0, opacity],
+                even_odd,
+                ..Default::default()
+            }
+        };
+        let path = transform * path;
+        match mode {
+            "a" => {
+                any_added = true;
+                added.fills.push(covered(path, inverted));
+            }
+            // Subtracting shows what the mask doesn't cover
+            "s" => clips.push(Clip {
+                fills: vec![covered(path, false)],
+                luma: false,
+                inverted: !inverted,
+            }),
+            _ => clips.push(Clip {
+                fills: vec![covered(path, false)],
+                luma: false,
+                inverted,
+            }),
+        }
+    }
+    if any_added {
+        clips.insert(0, added);
+    }
+    clips
+}
+
+/// Paths and what's painted, in the space around the group they're from
+struct Rendered {
+    geometry: Vec<BezPath>,
+    /// Top first
+    painted: Vec<FilledPath>,
+}
+
+/// Fills and strokes apply to every path above them in their group, including those in nested
+/// groups, and repeaters copy everything above them
+fn render_items(items: &[Value], t: f64, opacity: f64) -> Rendered {
+    let (local, local_opacity) = items
+        .iter()
+        .find(|item| item["ty"].as_str() == Some("tr"))
+        .map(|tr| transform_at(tr, t))
+        .unwrap_or((Affine::IDENTITY, 1.0));
+    let opacity = opacity * local_opacity;
+
+    let mut rendered = Rendered {
+        geometry: Vec::new(),
+        painted: Vec::new(),
+    };
+    for item in items {
+        if item["hd"].as_bool() == Some(true) {
+            continue;
+        }
+        let path = match item["ty"].as_str() {
+            Some("sh") => property_at(&item["ks"], t).and_then(|v| shape_path(&v)),
+            Some("rc") => rect_path(item, t),
+            Some("el") => ellipse_path(item, t),
+            Some("sr") => star_path(item, t),
+            Some("gr") => {
+                if let Some(items) = item["it"].as_array() {
+                    let nested = render_items(items, t, opacity);
+                    rendered.geometry.extend(nested.geometry);
+                    rendered.painted.extend(nested.painted);
+                }
+                None
+            }
+            Some("fl" | "gf" | "st" | "gs") => {
+                let mut path = BezPath::new();
+                for el in rendered.geometry.iter().flat_map(|g| g.elements()) {
+                    path.push(*el);
+                }
+                if let Some(painted) = paint(item, t, path, opacity) {
+                    rendered.painted.push(painted);
+                }
+                None
+            }
+            Some("rp") => {
+                repeat(&mut rendered, item, t);
+                None
+            }
+            _ => None,
+        };
+        if let Some(path) = path {
+            rendered.geometry.push(path);
+        }
+    }
+    // Out of the group's own space, which may be singular, such as when scaled to 0
+    Rendered {
+        geometry: rendered
+            .geometry
+            .into_iter()
+            .map(|path| local * path)
+            .collect(),
+        painted: rendered
+            .painted
+            .iter()
+            .map(|fill| fill.transformed(local, 1.0))
+            .collect(),
+    }
+}
+
+/// `path`, in the space of its group, painted by fill or stroke `item`
+fn paint(item: &Value, t: f64, path: BezPath, opacity: f64) -> Option<FilledPath> {
+    if path.elements().is_empty() {
+        return None;
+    }
+    let ty = item["ty"].as_str()?;
+    let opacity = opacity * numbers_at(&item["o"], t).first().copied().unwrap_or(100.0) / 100.0;
+    let mut painted = if matches!(ty, "st" | "gs") {
+        FilledPath {
+            path: stroked(item, t, &path),
+            ..Default::default()
+        }
+    } else {
+        FilledPath {
+            path,
+            even_odd: item["r"].as_i64() == Some(2),
+            ..Default::default()
+        }
+    };
+    if matches!(ty, "fl" | "st") {
+        let color = numbers_at(&item["c"], t);
+        if color.len() < 3 {
+            return None;
+        }
+        painted.color = [
+            color[0],
+            color[1],
+            color[2],
+            opacity * color.get(3).copied().unwrap_or(1.0),
+        ];
+    } else {
+        let stops = gradient_stops(item, t, opacity)?;
+        painted.color = stops[stops.len() / 2].color;
+        painted.shader = Some(gradient(item, t, stops));
+    }
+    Some(painted)
+}
+
+/// The outline of stroke `item` along `path`, in the space of its group so the group's
+/// transform scales and skews it as it does the path
+fn stroked(item: &Value, t: f64, path: &BezPath) -> BezPath {
+    let first = |property: &Value| numbers_at(property, t).first().copied();
+    let width = first(&item["w"]).unwrap_or_default();
+    let mut style = Stroke::new(width)
+        .with_caps(match item["lc"].as_i64() {
+            Some(2) => Cap::Round,
+            Some(3) => Cap::Square,
+            _ => Cap::Butt,
+        })
+        .with_join(match item["lj"].as_i64() {
+            Some(2) => Join::Round,
+            Some(3) => Join::Bevel,
+            _ => Join::Miter,
+        })
+        .with_miter_limit(
+            number(&item["ml"])
+                .or_else(|| first(&item["ml2"]))
+                .unwrap_or(4.0),
+        );
+    let mut offset = 0.0;
+    let mut pattern = Vec::new();
+    for dash in item["d"].as_array().into_iter().flatten() {
+        let length = first(&dash["v"]).unwrap_or_default();
+        match dash["n"].as_str() {
+            Some("o") => offset = length,
+            Some("d" | "g") => pattern.push(length),
+            _ => (),
+        }
+    }
+    if pattern.iter().any(|length| *length > 0.0) {
+        // As in svg, an odd number of lengths repeats to make pairs
+        if pattern.len() % 2 == 1 {
+            pattern.extend(pattern.clone());
+        }
+        style = style.with_dashes(offset, pattern);
+    }
+    stroke(path.iter(), &style, &StrokeOpts::default(), 0.1)
+}
+
+/// The stops of gradient `item`, whose colors and opacities may be at different offsets, at
+/// every offset either is
+fn gradient_stops(item: &Value, t: f64, opacity: f64) -> Option<Vec<ColorStop>> {
+    let count = number(&item["g"]["p"])? as usize;
+    let values = numbers_at(&item["g"]["k"], t);
+    let colors: Vec<_> = values
+        .get(..count * 4)?
+        .chunks_exact(4)
+        .map(|c| (c[0], [c[1], c[2], c[3]]))
+        .collect();
+    let alphas: Vec<_> = values[count * 4..]
+        .chunks_exact(2)
+        .map(|a| (a[0], [a[1]]))
+        .collect();
+    if colors.is_empty() {
+        return None;
+    }
+    let mut offsets: Vec<_> = colors
+        .iter()
+        .map(|(offset, _)| *offset)
+        .chain(alphas.iter().map(|(offset, _)| *offset))
+        .collect();
+    offsets.sort_by(f64::total_cmp);
+    offsets.dedup();
+    Some(
+        offsets
+            .into_iter()
+            .map(|offset| {
+                let [r, g, b] = stop_at(&colors, offset);
+                let [a] = if alphas.is_empty() {
+                    [1.0]
+                } else {
+                    stop_at(&alphas, offset)
+                };
+                ColorStop {
+                    offset,
+                    color: [r, g, b, a * opacity],
+                }
+            })
+            .collect(),
+    )
+}
+
+/// The value at `offset` along non-empty `stops`, sorted by offset, held beyond either end
+fn stop_at<const N: usize>(stops: &[(f64, [f64; N])], offset: f64) -> [f64; N] {
+    match stops.iter().position(|(at, _)| *at >= offset) {
+        None => stops[stops.len() - 1].1,
+        Some(0) => stops[0].1,
+        Some(i) => {
+            let ((from, a), (to, b)) = (stops[i - 1], stops[i]);
+            let progress = (offset - from) / (to - from);
+            std::array::from_fn(|c| a[c] + (b[c] - a[c]) * progress)
+        }
+    }
+}
+
+fn gradient(item: &Value, t: f64, stops: Vec<ColorStop>) -> Shader {
+    let start = point(&numbers_at(&item["s"], t));
+    let end = point(&numbers_at(&item["e"], t));
+    if item["t"].as_i64() != Some(2) {
+        return Shader::LinearGradient {
+            start,
+            end,
+            stops,
+            transform: Affine::IDENTITY,
+        };
+    }
+    // The highlight moves where the gradient starts towards the edge, by a percentage of the
+    // radius at an angle from the end point's
+    let first = |property: &Value| numbers_at(property, t).first().copied().unwrap_or_default();
+    let radius = start.distance(end);
+    let highlight = first(&item["h"]).clamp(-99.0, 99.0) / 100.0;
+    let angle = (end - start).atan2() + first(&item["a"]).to_radians();
+    Shader::RadialGradient {
+        center: start,
+        focus: start + Vec2::from_angle(angle) * radius * highlight,
+        radius,
+        stops,
+        transform: Affine::IDENTITY,
+    }
+}
+
+/// Replace what's above repeater `item`, in the space of its group, with copies, each
+/// transformed once more than the last
+fn repeat(rendered: &mut Rendered, item: &Value, t: f64) {
+    let first = |property: &Value, default: f64| {
+        numbers_at(property, t).first().copied().unwrap_or(default)
+    };
+    let copies = first(&item["c"], 1.0).ceil().max(0.0) as usize;
+    let offset = first(&item["o"], 0.0);
+    let tr = &item["tr"];
+    let anchor = point(&numbers_at(&tr["a"], t)).to_vec2();
+    let position = point(&numbers_at(&tr["p"], t)).to_vec2();
+    let rotation = first(&tr["r"], 0.0).to_radians();
+    let scale = numbers_at(&tr["s"], t);
+    let (sx, sy) = (
+        scale.first().copied().unwrap_or(100.0) / 100.0,
+        scale.get(1).copied().unwrap_or(100.0) / 100.0,
+    );
+    let (start_opacity, end_opacity) = (
+        first(&tr["so"], 100.0) / 100.0,
+        first(&tr["eo"], 100.0) / 100.0,
+    );
+
+    let geometry = std::mem::take(&mut rendered.geometry);
+    let painted = std::mem::take(&mut rendered.painted);
+    let mut copied = Vec::new();
+    for i in 0..copies {
+        let n = i as f64 + offset;
+        // A fractional power of a negative scale is NaN, flip only for whole steps
+        let power = |s: f64| {
+            if n.fract() == 0.0 {
+                s.powi(n as i32)
+            } else {
+                s.abs().powf(n)
+            }
+        };
+        let step = Affine::translate(position * n + anchor)
+            * Affine::rotate(rotation * n)
+            * Affine::scale_non_uniform(power(sx), power(sy))
+            * Affine::translate(-anchor);
+        let fade = if copies > 1 {
+            start_opacity + (end_opacity - start_opacity) * i as f64 / (copies - 1) as f64
+        } else {
+            start_opacity
+        };
+        rendered
+            .geometry
+            .extend(geometry.iter().map(|path| step * path.clone()));
+        copied.push(
+            painted
+                .iter()
+                .map(|fill| fill.transformed(step, fade))
+                .collect::<Vec<_>>(),
+        );
+    }
+    // Painted is top first; by default each copy goes above the last
+    if item["m"].as_i64() != Some(2) {
+        copied.reverse();
+    }
+    rendered.painted = copied.into_iter().flatten().collect();
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Array(values) => values.first().and_then(Value::as_f64),
+        _ => value.as_f64(),
+    }
+}
+
+fn numbers(value: &Value) -> Vec<f64> {
+    match value {
+        Value::Array(values) => values.iter().filter_map(Value::as_f64).collect(),
+        _ => value.as_f64().into_iter().collect(),
+    }
+}
+
+pub(crate) fn numbers_at(property: &Value, t: f64) -> Vec<f64> {
+    property_at(property, t)
+        .map(|v| numbers(&v))
+        .unwrap_or_default()
+}
+
+fn point(values: &[f64]) -> Point {
+    Point::new(
+        values.first().copied().unwrap_or_default(),
+        values.get(1).copied().unwrap_or_default(),
+    )
+}
+
+fn hex_color(hex: &str) -> Option<[f64; 3]> {
+    let hex = hex.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| {
+        u8::from_str_radix(hex.get(i..i + 2)?, 16)
+            .ok()
+            .map(|c| c as f64 / 255.0)
+    };
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// Transform and opacity (0..1) for a layer "ks" or shape "tr"
+pub(crate) fn transform_at(transform: &Value, t: f64) -> (Affine, f64) {
+    let anchor = point(&numbers_at(&transform["a"], t));
+    let position = if transform["p"]["s"].as_bool() == Some(true) {
+        Point::new(
+            numbers_at(&transform["p"]["x"], t)
+                .first()
+                .copied()
+                .unwrap_or_default(),
+            numbers_at(&transform["p"]["y"], t)
+                .first()
+                .copied()
+                .unwrap_or_default(),
+        )
+    } else {
+        point(&numbers_at(&transform["p"], t))
+    };
+    let scale = numbers_at(&transform["s"], t);
+    let (sx, sy) = (
+        scale.first().copied().unwrap_or(100.0),
+        scale.get(1).copied().unwrap_or(100.0),
+    );
+    let rotation = if transform["r"].is_null() {
+        &transform["rz"]
+    } else {
+        &transform["r"]
+    };
+    let rotation = numbers_at(rotation, t).first().copied().unwrap_or_default();
+    let opacity = numbers_at(&transform["o"], t)
+        .first()
+        .copied()
+        .unwrap_or(100.0);
+
+    let affine = Affine::translate(position.to_vec2())
+        * Affine::rotate(rotation.to_radians())
+        * Affine::scale_non_uniform(sx / 100.0, sy / 100.0)
+        * Affine::translate(-anchor.to_vec2());
+    (affine, opacity / 100.0)
+}
+
+/// The combined transform of a layer's parents, if any
+pub(crate) fn parent_transform(layers: &[Value], layer: &Value, t: f64, depth: usize) -> Affine {
+    let Some(parent_index) = layer["parent"].as_i64() else {
+        return Affine::IDENTITY;
+    };
+    let Some(parent) = layers
+        .iter()
+        .find(|l| l["ind"].as_i64() == Some(parent_index))
+    else {
+        return Affine::IDENTITY;
+    };
+    if depth > 16 {
+        return Affine::IDENTITY;
+    }
+    parent_transform(layers, parent, t, depth + 1) * transform_at(&parent["ks"], t).0
+}
+
+pub(crate) fn rect_path(rect: &Value, t: f64) -> Option<BezPath> {
+    let center = point(&numbers_at(&rect["p"], t));
+    let size = numbers_at(&rect["s"], t);
+    let (width, height) = (*size.first()?, *size.get(1)?);
+    let radius = numbers_at(&rect["r"], t)
+        .first()
+        .copied()
+        .unwrap_or_default();
+    let rect = Rect::from_center_size(center, (width, height));
+    Some(RoundedRect::from_rect(rect, radius).to_path(0.1))
+}
+
+/// A star, or with "sy" 2 a polygon, its first point straight up before rotation
+pub(crate) fn star_path(star: &Value, t: f64) -> Option<BezPath> {
+    let first = |property: &Value| numbers_at(property, t).first().copied().unwrap_or_default();
+    let center = point(&numbers_at(&star["p"], t));
+    let points = first(&star["pt"]).round() as usize;
+    if points < 2 {
+        return None;
+    }
+    let is_star = star["sy"].as_i64() != Some(2);
+    let vertices = if is_star { points * 2 } else { points };
+    let mut path = BezPath::new();
+    for i in 0..vertices {
+        let radius = if is_star && i % 2 == 1 {
+            first(&star["ir"])
+        } else {
+            first(&star["or"])
+        };
+        let angle = first(&star["r"]).to_radians() - FRAC_PI_2 + TAU * i as f64 / vertices as f64;
+        let vertex = center + Vec2::from_angle(angle) * radius;
+        if i == 0 {
+            path.move_to(vertex);
+        } else {
+            path.line_to(vertex);
+        }
+    }
+    path.close_path();
+    Some(path)
+}
+
+pub(crate) fn ellipse_path(ellipse: &Value, t: f64) -> Option<BezPath> {
+    let center = point(&numbers_at(&ellipse["p"], t));
+    let size = numbers_at(&ellipse["s"], t);
+    let (width, height) = (*size.first()?, *size.get(1)?);
+    Some(Ellipse::from_rect(Rect::from_center_size(center, (width, height))).to_path(0.1))
+}
+
+/// Lottie shape data, {"v", "i", "o", "c"}, to a path
+///
+/// Keyframed shapes wrap the value in an array so we accept that too.
+pub(crate) fn shape_path(shape: &Value) -> Option<BezPath> {
+    let shape = match shape {
+        Value::Array(values) => values.first()?,
+        _ => shape,
+    };
+    let points = |key: &str| -> Vec<Point> {
+        shape[key]
+            .as_array()
+            .map(|values| values.iter().map(|v| point(&numbers(v))).collect())
+            .unwrap_or_default()
+    };
+    let (vertices, in_points, out_points) = (points("v"), points("i"), points("o"));
+    if vertices.is_empty() {
+        return None;
+    }
+    let control = |points: &[Point], i: usize| points.get(i).copied().unwrap_or_default().to_vec2();
+
+    let mut path = BezPath::new();
+    path.move_to(vertices[0]);
+    let closed = shape["c"].as_bool().unwrap_or_default();
+    let segments = if closed {
+        vertices.len()
+    } else {
+        vertices.len() - 1
+    };
+    for i in 0..segments {
+        let j = (i + 1) % vertices.len();
+        path.curve_to(
+            vertices[i] + control(&out_points, i),
+            vertices[j] + control(&in_points, j),
+            vertices[j],
+        );
+    }
+    if closed {
+        path.close_path();
+    }
+    Some(path)
+}
+
+/// The value of an animatable property at `t`
+pub(crate) fn property_at(property: &Value, t: f64) -> Option<Value> {
+    let value = property.get("k")?;
+    let Some(keyframes) = value
+        .as_array()
+        .filter(|values| values.first().map(|v| v.get("t").is_some()) == Some(true))
+    else {
+        return Some(value.clone());
+    };
+
+    let first = keyframes.first()?;
+    if t <= number(&first["t"])? {
+        return first.get("s").cloned();
+    }
+    for pair in keyframes.windows(2) {
+        let (from, to) = (&pair[0], &pair[1]);
+        let (t0, t1) = (number(&from["t"])?, number(&to["t"])?);
+        if t >= t1 {
+            continue;
+        }
+        let start = from.get("s")?;
+        if from["h"].as_i64() == Some(1) || t1 <= t0 {
+            return Some(start.clone());
+        }
+        // Older files put the end value on the keyframe itself
+        let end = from.get("e").or_else(|| to.get("s"))?;
+        let progress = ease(from, (t - t0) / (t1 - t0));
+        return Some(lerp(start, end, progress));
+    }
+    let last = keyframes.last()?;
+    last.get("s")
+        .or_else(|| keyframes.iter().rev().find_map(|k| k.get("e")))
+        .cloned()
+}
+
+/// Map linear progress through a keyframe to eased progress
+fn ease(keyframe: &Value, progress: f64) -> f64 {
+    let coord =
+        |handle: &str, axis: &str, default: f64| number(&keyframe[handle][axis]).unwrap_or(default);
+    let (x1, y1) = (coord("o", "x", 0.0), coord("o", "y", 0.0));
+    let (x2, y2) = (coord("i", "x", 1.0), coord("i", "y", 1.0));
+    cubic_bezier(x1, y1, x2, y2, progress)
+}
+
+/// Solve a css-style cubic-bezier timing function, (0, 0) and (1, 1) implied
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    let bezier = |p1: f64, p2: f64, s: f64| {
+        let inv = 1.0 - s;
+        3.0 * inv * inv * s * p1 + 3.0 * inv * s * s * p2 + s * s * s
+    };
+    // x is monotonic in s for valid timing functions so bisection always converges
+    let (mut lo, mut hi) = (0.0, 1.0);
+    let mut s = x.clamp(0.0, 1.0);
+    for _ in 0..32 {
+        let value = bezier(x1, x2, s);
+        if (value - x).abs() < 1e-7 {
+            break;
+        }
+        if value < x {
+            lo = s;
+        } else {
+            hi = s;
+        }
+        s = (lo + hi) / 2.0;
+    }
+    bezier(y1, y2, s)
+}
+
+/// Interpolate numbers, recursing through arrays and objects; anything else holds `from`
+fn lerp(from: &Value, to: &Value, t: f64) -> Value {
+    match (from, to) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => (a + (b - a) * t).into(),
+            _ => from.clone(),
+        },
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            Value::Array(a.iter().zip(b).map(|(a, b)| lerp(a, b, t)).collect())
+        }
+        (Value::Object(a), Value::Object(b)) => Value::Object(
+            a.iter()
+                .map(|(key, value)| {
+                    let value = match b.get(key) {
+                        Some(other) => lerp(value, other, t),
+                        None => value.clone(),
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        _ => from.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn repeater_in_a_layer_scaled_to_nothing() {
+        let lottie = json!({
+            "fr": 60, "ip": 0, "op": 60, "w": 100, "h": 100,
+            "layers": [{
+                "ty": 4, "ind": 1, "ip": 0, "op": 60, "st": 0,
+                "ks": { "s": { "a": 0, "k": [0, 0] } },
+                "shapes": [{
+                    "ty": "gr",
+                    "it": [
+                        { "ty": "rc", "p": { "a": 0, "k": [50, 20] }, "s": { "a": 0, "k": [4, 10] }, "r": { "a": 0, "k": 0 } },
+                        { "ty": "fl", "c": { "a": 0, "k": [1, 0, 0] }, "o": { "a": 0, "k": 100 } },
+                        { "ty": "rp", "c": { "a": 0, "k": 4 }, "o": { "a": 0, "k": 0 }, "tr": {
+                            "a": { "a": 0, "k": [50, 50] }, "p": { "a": 0, "k": [0, 0] },
+                            "s": { "a": 0, "k": [100, 100] }, "r": { "a": 0, "k": 90 },
+                        } },
+                        { "ty": "tr" },
+                    ],
+                }],
+            }],
+        });
+        let frame = Evaluator::from_json(lottie).unwrap().frame(0.0);
+        assert_eq!(frame.fills.len(), 4);
+        for fill in frame.fills.iter() {
+            let finite = |el: &kurbo::PathEl| el.end_point().into_iter().all(|p| p.is_finite());
+            assert!(fill.path.elements().iter().all(finite), "{fill:?}");
+        }
+    }
+}
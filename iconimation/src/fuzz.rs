@@ -0,0 +1,124 @@
+//! Entry points for fuzzing, driven by the targets in fuzz/ at the root of the repository.
+//!
+//! Each takes input the fuzzer can generate and must return, never panic, however odd the
+//! input. Invariants that should hold for any input are asserted; a failure is a finding.
+
+use arbitrary::Arbitrary;
+use bodymovin::{shapes::SubPath, Bodymovin as Lottie};
+use kurbo::{BezPath, PathEl};
+use skrifa::{outline::OutlinePen, raw::FontRef, GlyphId};
+
+use crate::{
+    animate::Animation, bez_for_subpath, error::Error, lottie_for_glyph, placement::Placement,
+    shape_pen::SubPathPen, DesignLocation,
+};
+
+/// A path command, in font units as a pen would receive it
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum FuzzEl {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    QuadTo(f32, f32, f32, f32),
+    CurveTo(f32, f32, f32, f32, f32, f32),
+    Close,
+}
+
+#[derive(Arbitrary, Clone, Debug)]
+pub struct FuzzPath(pub Vec<FuzzEl>);
+
+impl FuzzPath {
+    fn draw(&self, pen: &mut impl OutlinePen) {
+        for el in self.0.iter() {
+            match *el {
+                FuzzEl::MoveTo(x, y) => pen.move_to(x, y),
+                FuzzEl::LineTo(x, y) => pen.line_to(x, y),
+                FuzzEl::QuadTo(cx, cy, x, y) => pen.quad_to(cx, cy, x, y),
+                FuzzEl::CurveTo(cx0, cy0, cx1, cy1, x, y) => pen.curve_to(cx0, cy0, cx1, cy1, x, y),
+                FuzzEl::Close => pen.close(),
+            }
+        }
+    }
+}
+
+/// The animations that need nothing but the glyph, so any input can pick one
+#[derive(Arbitrary, Clone, Copy, Debug)]
+pub enum FuzzAnimation {
+    None,
+    PulseWhole,
+    PulseParts,
+    TwirlWhole,
+    TwirlParts,
+    Shake,
+}
+
+impl From<FuzzAnimation> for Animation {
+    fn from(value: FuzzAnimation) -> Self {
+        match value {
+            FuzzAnimation::None => Animation::None,
+            FuzzAnimation::PulseWhole => Animation::PulseWhole,
+            FuzzAnimation::PulseParts => Animation::PulseParts {
+                compensate_stroke: true,
+                parts: Default::default(),
+            },
+            FuzzAnimation::TwirlWhole => Animation::TwirlWhole,
+            FuzzAnimation::TwirlParts => Animation::TwirlParts {
+                parts: Default::default(),
+            },
+            FuzzAnimation::Shake => Animation::Shake,
+        }
+    }
+}
+
+/// Convert each subpath to a Lottie shape and back
+///
+/// Every on-curve point must survive as a vertex.
+pub fn bez_round_trip(path: &FuzzPath) -> Result<Vec<BezPath>, Error> {
+    let mut pen = SubPathPen::default();
+    path.draw(&mut pen);
+    pen.into_shapes()
+        .into_iter()
+        .map(|(bez, subpath)| {
+            let round_trip = bez_for_subpath(&subpath)?;
+            let on_curve = bez
+                .elements()
+                .iter()
+                .filter(|el| !matches!(el, PathEl::ClosePath))
+                .count();
+            let implicit_start = !matches!(bez.elements().first(), Some(PathEl::MoveTo(..)) | None);
+            assert_eq!(
+                on_curve + implicit_start as usize,
+                round_trip.elements().len(),
+                "{} became {}",
+                bez.to_svg(),
+                round_trip.to_svg()
+            );
+            Ok(round_trip)
+        })
+        .collect()
+}
+
+/// Read a Lottie shape, such as a template placeholder, from arbitrary json
+pub fn subpath_from_json(json: &[u8]) -> Result<BezPath, Error> {
+    let subpath: SubPath = serde_json::from_slice(json).map_err(Error::JsonError)?;
+    bez_for_subpath(&subpath)
+}
+
+/// Animate `gid` of an arbitrary font into an arbitrary template
+pub fn replace_in_template(
+    template: &[u8],
+    font: &[u8],
+    gid: u16,
+    animation: FuzzAnimation,
+) -> Result<Lottie, Error> {
+    let template: Lottie = serde_json::from_slice(template).map_err(Error::JsonError)?;
+    let font = FontRef::new(font).map_err(Error::ReadError)?;
+    lottie_for_glyph(
+        &font,
+        GlyphId::new(gid),
+        &DesignLocation::default(),
+        Some(template),
+        &animation.into(),
+        &Style::default(),
+        &Placement::default(),
+    )
+}
@@ -0,0 +1,108 @@
+//! Golden frames, to catch changes to animations as visual differences rather than json ones.
+//!
+//! [Golden::check] renders a Lottie at fixed frames and compares each against a reference
+//! png, checked in beside the tests, allowing for small differences such as anti-aliasing.
+//! Run with `ICONIMATION_BLESS=1` set to write references, new or changed, rather than fail.
+//!
+//! Frames are drawn by [Evaluator], so Lotties it can't draw fail rather than being blessed
+//! with parts missing, and references written before it drew strokes, gradients, masks and
+//! mattes need blessing again.
+
+use std::{env, fs, path::PathBuf};
+
+use bodymovin::Bodymovin as Lottie;
+use tiny_skia::Pixmap;
+
+use crate::{
+    error::Error,
+    evaluate::Evaluator,
+    raster::{png, rasterize},
+};
+
+/// Set to write references rather than compare against them
+pub const BLESS: &str = "ICONIMATION_BLESS";
+
+#[derive(Clone, Debug)]
+pub struct Golden {
+    /// Where references are, named for the check and frame such as `pulse-whole-12.png`
+    pub dir: PathBuf,
+    /// How many pixels square to render
+    pub size: u32,
+    /// How far, from 0 to 255, any channel of a pixel may be off before it counts as different
+    pub threshold: u8,
+    /// The fraction of pixels, from 0 to 1, that may differ
+    pub tolerance: f64,
+}
+
+impl Golden {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Golden {
+            dir: dir.into(),
+            size: 128,
+            threshold: 16,
+            tolerance: 0.001,
+        }
+    }
+
+    /// Compare `lottie` at each of `frames` against its reference, named for `name`
+    ///
+    /// Frames that differ are written beside their reference, as `pulse-whole-12.actual.png`,
+    /// to look at; the error lists every one.
+    pub fn check(&self, lottie: &Lottie, name: &str, frames: &[f64]) -> Result<(), Error> {
+        let evaluator = Evaluator::new(lottie)?;
+        let bless = env::var_os(BLESS).is_some();
+        let mut mismatches = Vec::new();
+        for frame in frames.iter() {
+            let reference = self.dir.join(format!("{name}-{frame}.png"));
+            let rendered = evaluator.frame(*frame);
+            let actual = rasterize(&rendered, self.size, self.size)?;
+            if bless {
+                fs::create_dir_all(&self.dir).map_err(Error::IoError)?;
+                fs::write(&reference, png(&rendered, self.size, self.size)?)
+                    .map_err(Error::IoError)?;
+                continue;
+            }
+            let expected = Pixmap::load_png(&reference).map_err(|e| {
+                Error::GoldenMismatch(format!(
+                    "unable to load {}, set {BLESS}=1 to write it: {e}",
+                    reference.display()
+                ))
+            })?;
+            let different = self.different_pixels(&expected, &actual);
+            if different > self.tolerance {
+                let actual_file = reference.with_extension("actual.png");
+                fs::write(&actual_file, png(&rendered, self.size, self.size)?)
+                    .map_err(Error::IoError)?;
+                mismatches.push(format!(
+                    "{} differs in {:.2}% of pixels, see {}",
+                    reference.display(),
+                    different * 100.0,
+                    actual_file.display()
+                ));
+            }
+        }
+        if !mismatches.is_empty() {
+            return Err(Error::GoldenMismatch(mismatches.join("; ")));
+        }
+        Ok(())
+    }
+
+    /// The fraction of pixels with a channel more than [Golden::threshold] off, all of them if
+    /// the sizes don't match
+    fn different_pixels(&self, expected: &Pixmap, actual: &Pixmap) -> f64 {
+        if (expected.width(), expected.height()) != (actual.width(), actual.height()) {
+            return 1.0;
+        }
+        let different = expected
+            .data()
+            .chunks_exact(4)
+            .zip(actual.data().chunks_exact(4))
+            .filter(|(e, a)| {
+                e.iter()
+                    .zip(a.iter())
+                    .any(|(e, a)| e.abs_diff(*a) > self.threshold)
+            })
+            .count();
+        different as f64 / (expected.width() * expected.height()).max(1) as f64
+    }
+}
@@ -0,0 +1,71 @@
+//! Fonts fetched from Google Fonts by family name, cached so each is only downloaded once.
+//!
+//! Families are requested from the CSS API, as a web page would, optionally with axis ranges in
+//! its syntax such as `Material Symbols Outlined:opsz,wght,FILL,GRAD@20..48,100..700,0..1,-50..200`
+//! to get a variable font rather than the default instance.
+//!
+//! See <https://developers.google.com/fonts/docs/css2>.
+
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::error::Error;
+
+const CSS_API: &str = "https://fonts.googleapis.com/css2";
+
+/// Where fetched fonts are kept, under $XDG_CACHE_HOME or ~/.cache
+pub fn default_cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("iconimation")
+        .join("google-fonts")
+}
+
+/// The font file for `family`, from `cache_dir` if it was fetched before
+///
+/// Families with several fonts, such as for unicode ranges, give the first.
+pub fn fetch_family(family: &str, cache_dir: &Path) -> Result<Vec<u8>, Error> {
+    let cached = cache_dir.join(cache_name(family));
+    if let Ok(bytes) = fs::read(&cached) {
+        return Ok(bytes);
+    }
+
+    let fetch_error = |e: ureq::Error| Error::FetchError(format!("{family}: {e}"));
+    // Without a browser's user agent the CSS API links TrueType rather than WOFF2
+    let css = ureq::get(CSS_API)
+        .query("family", family)
+        .call()
+        .map_err(fetch_error)?
+        .into_string()
+        .map_err(Error::IoError)?;
+    let url = css
+        .split("url(")
+        .nth(1)
+        .and_then(|rest| rest.split(')').next())
+        .ok_or_else(|| Error::FetchError(format!("{family}: no font in {css:?}")))?;
+    let mut bytes = Vec::new();
+    ureq::get(url)
+        .call()
+        .map_err(fetch_error)?
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .map_err(Error::IoError)?;
+
+    fs::create_dir_all(cache_dir).map_err(Error::IoError)?;
+    fs::write(&cached, &bytes).map_err(Error::IoError)?;
+    Ok(bytes)
+}
+
+/// A file name for `family`, axis ranges and all
+fn cache_name(family: &str) -> String {
+    let name: String = family
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{name}.font")
+}
@@ -1,9 +1,53 @@
 //! Shove glyphs from a variable font into a Lottie template.
 
 pub mod animate;
+pub mod animated_svg;
+pub mod annotations;
+pub mod bitmap;
+pub mod builtin;
+pub mod codepoints;
+pub mod color;
+pub mod compare;
+pub mod compat;
+pub mod correspondence;
+pub mod css;
 pub mod debug_pen;
+pub mod dotlottie;
+pub mod effects;
 pub mod error;
+pub mod evaluate;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "raster")]
+pub mod golden;
+#[cfg(feature = "google_fonts")]
+pub mod google_fonts;
+mod names;
+pub mod optimize;
+pub mod output;
+pub mod placement;
+pub mod pool;
+#[cfg(feature = "raster")]
+pub mod raster;
+pub mod report;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod semantics;
 mod shape_pen;
+pub mod sink;
+pub mod style;
+#[cfg(feature = "svg")]
+pub mod svg;
+pub mod template;
+pub mod text;
+pub mod tgs;
+pub mod unmodeled;
+pub mod validate;
+#[cfg(feature = "video")]
+pub mod video;
+pub mod webfont;
+
+use std::{cmp::Ordering, collections::HashMap, ops::RangeInclusive, str::FromStr};
 
 use bodymovin::{
     layers::{AnyLayer, ShapeMixin},
@@ -12,51 +56,802 @@ use bodymovin::{
     sources::Asset,
     Bodymovin as Lottie,
 };
-use kurbo::{Affine, BezPath, Rect};
-use skrifa::{instance::Size, OutlineGlyph};
-use write_fonts::pens::TransformPen;
+use kurbo::{Affine, BezPath, PathEl, Point, Rect, Vec2};
+use serde_json::json;
+use skrifa::{
+    instance::{Location, Size},
+    outline::{DrawSettings, OutlineGlyphCollection, OutlinePen},
+    raw::{FontRef, TableProvider},
+    setting::VariationSetting,
+    AxisCollection, GlyphId, MetadataProvider, OutlineGlyph, Tag,
+};
+use write_fonts::pens::{ControlBoundsPen, TransformPen};
 
-use crate::{animate::Animator, error::Error, shape_pen::SubPathPen};
+use crate::{
+    animate::{Animation, Animator, Sample},
+    bitmap::{bitmap_glyph, replace_with_bitmap},
+    color::{color_layers, split_by_layer, ColorLayer, Paint},
+    correspondence::area_centroid,
+    effects::{add_layer_effect, add_layer_masks, alpha_matte, edit_layer},
+    error::Error,
+    names::name_shapes,
+    placement::{Align, Drawbox, FitMode, Placeholder, Placement, VerticalAlign, PLACEHOLDER},
+    shape_pen::SubPathPen,
+    style::{is_paint, remove_fills, FillColor, FillStyle, Rgb, Style},
+    template::TemplateOptions,
+    text::Line,
+};
 
 pub fn default_template(font_drawbox: &Rect) -> Lottie {
-    Lottie {
-        in_point: 0.0,
-        out_point: 60.0, // 60fps total animation = 1s
-        frame_rate: 60.0,
-        width: font_drawbox.width() as i64,
-        height: font_drawbox.height() as i64,
-        layers: vec![AnyLayer::Shape(bodymovin::layers::Shape {
-            in_point: 0.0,
-            out_point: 60.0, // 60fps total animation = 1s
-            mixin: ShapeMixin {
-                shapes: vec![AnyShape::Group(Group {
-                    name: Some("placeholder".into()),
-                    items: vec![
-                        // de facto standard is shape(s), fill, transform
-                        AnyShape::Rect(bodymovin::shapes::Rect {
-                            position: Property {
-                                value: Value::Fixed(vec![0.0, 0.0]),
-                                ..Default::default()
-                            },
-                            size: Property {
-                                value: Value::Fixed(vec![
-                                    font_drawbox.width(),
-                                    font_drawbox.height(),
-                                ]),
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        }),
-                        AnyShape::Fill(Default::default()),
-                        AnyShape::Transform(Default::default()),
-                    ],
-                    ..Default::default()
-                })],
+    default_template_with(font_drawbox, &TemplateOptions::default())
+}
+
+/// A template whose single placeholder is the size of `font_drawbox`, as `options` say
+pub fn default_template_with(font_drawbox: &Rect, options: &TemplateOptions) -> Lottie {
+    let frames = (options.duration * options.frame_rate).round();
+    let (width, height) = options
+        .canvas
+        .map(|(w, h)| (w as f64, h as f64))
+        .unwrap_or((font_drawbox.width(), font_drawbox.height()));
+    // de facto standard is shape(s), fill, transform; no fill unless asked
+    // for, the glyph brings its own
+    let placeholder = [
+        Some(rect_at_origin(font_drawbox.width(), font_drawbox.height())),
+        options.fill.map(solid_fill),
+        Some(AnyShape::Transform(Default::default())),
+    ];
+    let mut layers = vec![shape_layer(
+        frames,
+        Group {
+            name: Some(PLACEHOLDER.into()),
+            items: placeholder.into_iter().flatten().collect(),
+            ..Default::default()
+        },
+    )];
+    // Earlier layers are drawn on top
+    if let Some(background) = options.background {
+        layers.push(shape_layer(
+            frames,
+            Group {
+                name: Some("background".into()),
+                items: vec![
+                    rect_at_origin(width, height),
+                    solid_fill(background),
+                    AnyShape::Transform(Default::default()),
+                ],
                 ..Default::default()
             },
+        ));
+    }
+    Lottie {
+        in_point: 0.0,
+        out_point: frames,
+        frame_rate: options.frame_rate,
+        width: width as i64,
+        height: height as i64,
+        layers,
+        ..Default::default()
+    }
+}
+
+fn shape_layer(frames: f64, group: Group) -> AnyLayer {
+    AnyLayer::Shape(bodymovin::layers::Shape {
+        in_point: 0.0,
+        out_point: frames,
+        mixin: ShapeMixin {
+            shapes: vec![AnyShape::Group(group)],
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+}
+
+fn rect_at_origin(width: f64, height: f64) -> AnyShape {
+    AnyShape::Rect(bodymovin::shapes::Rect {
+        position: Property {
+            value: Value::Fixed(vec![0.0, 0.0]),
             ..Default::default()
-        })],
+        },
+        size: Property {
+            value: Value::Fixed(vec![width, height]),
+            ..Default::default()
+        },
         ..Default::default()
+    })
+}
+
+fn solid_fill(color: Rgb) -> AnyShape {
+    let style = FillStyle {
+        color: FillColor::Solid(color),
+        ..Default::default()
+    };
+    AnyShape::Fill(style.fill(0))
+}
+
+/// The box, in font units, glyphs are drawn into: an upem by upem square
+pub fn font_drawbox(font: &FontRef) -> Result<Rect, Error> {
+    let upem = font.head().map_err(Error::ReadError)?.units_per_em() as f64;
+    Ok((Point::ZERO, Point::new(upem, upem)).into())
+}
+
+/// The box, in font units, glyphs are drawn into, as `placement` says
+///
+/// [Drawbox::Bounds] and [VerticalAlign::BboxCenter] vary it for each glyph, see
+/// [glyph_drawbox]; for them this is the em, which sizes the default template.
+pub fn font_drawbox_for(font: &FontRef, placement: &Placement) -> Result<Rect, Error> {
+    let em = font_drawbox(font)?;
+    let mut drawbox = match placement.drawbox {
+        Drawbox::Em | Drawbox::Bounds => em,
+        Drawbox::Metrics => {
+            let hhea = font.hhea().map_err(Error::ReadError)?;
+            let ascender = hhea.ascender().to_i16() as f64;
+            let descender = hhea.descender().to_i16() as f64;
+            // Some fonts leave metrics unset, don't make an empty box of them
+            if ascender > descender {
+                Rect::new(0.0, descender, em.width(), ascender)
+            } else {
+                em
+            }
+        }
+    };
+    if placement.vertical_align == VerticalAlign::CapHeight {
+        // Only OS/2 version 2 and later has a cap height, without one stay on the baseline
+        if let Some(cap_height) = font.os2().ok().and_then(|os2| os2.s_cap_height()) {
+            drawbox = drawbox + Vec2::new(0.0, cap_height as f64 / 2.0 - drawbox.center().y);
+        }
+    }
+    Ok(drawbox)
+}
+
+/// How far, in font units, to move `glyph` for the centroid of its area to be where the center
+/// of its bounds is
+fn optical_nudge(glyph: &Glyph) -> Result<Vec2, Error> {
+    let (Some(bounds), Some(centroid)) = (glyph.bounds()?, glyph.centroid()?) else {
+        return Ok(Vec2::ZERO);
+    };
+    Ok(bounds.center() - centroid)
+}
+
+/// The box `glyph` is drawn into, `font_drawbox` moved as `placement` says for this glyph
+fn glyph_drawbox(font_drawbox: &Rect, glyph: &Glyph, placement: &Placement) -> Result<Rect, Error> {
+    if placement.drawbox != Drawbox::Bounds && placement.vertical_align != VerticalAlign::BboxCenter
+    {
+        return Ok(*font_drawbox);
+    }
+    // Color glyphs may draw nothing themselves, leave them where they are
+    let Some(bounds) = glyph.bounds()? else {
+        return Ok(*font_drawbox);
+    };
+    // A lone line, say, has no area to fit; its em does
+    if placement.drawbox == Drawbox::Bounds && bounds.width() > 0.0 && bounds.height() > 0.0 {
+        return Ok(bounds);
+    }
+    if placement.vertical_align != VerticalAlign::BboxCenter {
+        return Ok(*font_drawbox);
+    }
+    Ok(*font_drawbox + Vec2::new(0.0, bounds.center().y - font_drawbox.center().y))
+}
+
+/// Parse a codepoint written as 0xE87C, U+E87C, decimal 59516 or the character itself
+///
+/// A lone digit is decimal, not the character; write 0x37 for "7".
+pub fn parse_codepoint(s: &str) -> Result<u32, Error> {
+    let bad = || Error::BadCodepoint(s.to_string());
+    let s = s.trim();
+    let hex = ["0x", "0X", "U+", "u+"]
+        .iter()
+        .find_map(|prefix| s.strip_prefix(prefix));
+    let mut chars = s.chars();
+    let codepoint = if let Some(hex) = hex {
+        u32::from_str_radix(hex, 16).map_err(|_| bad())?
+    } else if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) {
+        s.parse().map_err(|_| bad())?
+    } else if let (Some(c), None) = (chars.next(), chars.next()) {
+        c as u32
+    } else {
+        return Err(bad());
+    };
+    char::from_u32(codepoint).ok_or_else(bad)?;
+    Ok(codepoint)
+}
+
+/// Parse a sequence of codepoints, such as an emoji ZWJ sequence, written as the characters
+/// themselves or as codepoints in any [parse_codepoint] syntax separated by spaces, such as
+/// "U+1F469 U+200D U+1F4BB"
+pub fn parse_sequence(s: &str) -> Result<Vec<u32>, Error> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(Error::BadCodepoint(s.to_string()));
+    }
+    if s.contains(char::is_whitespace) {
+        return s.split_whitespace().map(parse_codepoint).collect();
+    }
+    if let Ok(codepoint) = parse_codepoint(s) {
+        return Ok(vec![codepoint]);
+    }
+    Ok(s.chars().map(|c| c as u32).collect())
+}
+
+/// Parse a codepoint, or an inclusive range of them such as 0xE000-0xE0FF, in any [parse_codepoint] syntax
+pub fn parse_codepoint_range(s: &str) -> Result<RangeInclusive<u32>, Error> {
+    let range = s
+        .trim()
+        .split_once('-')
+        .filter(|(start, end)| !start.is_empty() && !end.is_empty());
+    let Some((start, end)) = range else {
+        let codepoint = parse_codepoint(s)?;
+        return Ok(codepoint..=codepoint);
+    };
+    let (start, end) = (parse_codepoint(start)?, parse_codepoint(end)?);
+    if start > end {
+        return Err(Error::BadCodepoint(s.to_string()));
+    }
+    Ok(start..=end)
+}
+
+/// Codepoints mapped by the charmap to glyphs that draw something, in codepoint order
+pub fn mapped_glyphs(font: &FontRef) -> Result<Vec<(u32, GlyphId)>, Error> {
+    let outlines = font.outline_glyphs();
+    let location = Location::default();
+    let mut mapped = Vec::new();
+    for (codepoint, gid) in font.charmap().mappings() {
+        let Some(outline) = outlines.get(gid) else {
+            continue;
+        };
+        let mut pen = ControlBoundsPen::new();
+        outline
+            .draw(
+                DrawSettings::unhinted(Size::unscaled(), &location),
+                &mut pen,
+            )
+            .map_err(Error::DrawError)?;
+        // Color glyphs commonly draw nothing themselves, only their layers do
+        let colored = match color_layers(font, gid, 0, &location) {
+            Ok(layers) => !layers.is_empty(),
+            // Drawn as its outline, see Glyph::unusable_svg
+            Err(Error::BadSvg(_)) => false,
+            Err(e) => return Err(e),
+        };
+        if pen.bounds().is_some() || colored {
+            mapped.push((codepoint, gid));
+        }
+    }
+    mapped.sort();
+    Ok(mapped)
+}
+
+/// Find a glyph by its name in the post table, such as "alarm" in Material Symbols
+pub fn glyph_id_for_name(font: &FontRef, name: &str) -> Result<Option<GlyphId>, Error> {
+    let post = font.post().map_err(Error::ReadError)?;
+    let num_glyphs = font.maxp().map_err(Error::ReadError)?.num_glyphs();
+    Ok((0..num_glyphs)
+        .map(GlyphId::new)
+        .find(|gid| post.glyph_name(*gid) == Some(name)))
+}
+
+/// A user-space location in a variable font, such as wght=700 FILL=1
+///
+/// Axes that aren't mentioned are at their default.
+#[derive(Clone, Debug, Default)]
+pub struct DesignLocation(pub Vec<VariationSetting>);
+
+impl FromStr for DesignLocation {
+    type Err = Error;
+
+    /// Parse comma separated tag=value pairs, such as "wght=700,FILL=1", each axis at most once
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let settings: Vec<VariationSetting> = s
+            .split(',')
+            .map(str::trim)
+            .filter(|setting| !setting.is_empty())
+            .map(|setting| {
+                let bad = || Error::BadDesignLocation(setting.to_string());
+                let (tag, value) = setting.split_once('=').ok_or_else(bad)?;
+                let tag = Tag::new_checked(tag.trim().as_bytes()).map_err(|_| bad())?;
+                let value = value.trim().parse::<f32>().map_err(|_| bad())?;
+                Ok(VariationSetting::new(tag, value))
+            })
+            .collect::<Result<_, _>>()?;
+        for (i, setting) in settings.iter().enumerate() {
+            if settings[..i].iter().any(|s| s.selector == setting.selector) {
+                return Err(Error::DuplicateAxis(setting.selector));
+            }
+        }
+        Ok(DesignLocation(settings))
+    }
+}
+
+/// Animate a glyph, placing it in `template` or, if there isn't one, in a [default_template]
+///
+/// Glyphs with no outline but a bitmap, such as emoji in CBDT or sbix fonts, are placed as
+/// images, see [bitmap].
+pub fn lottie_for_glyph(
+    font: &FontRef,
+    gid: GlyphId,
+    location: &DesignLocation,
+    template: Option<Lottie>,
+    animation: &Animation,
+    style: &Style,
+    placement: &Placement,
+) -> Result<Lottie, Error> {
+    let font_drawbox = font_drawbox_for(font, placement)?;
+    if font.outline_glyphs().get(gid).is_none() {
+        if let Some(bitmap) = bitmap_glyph(font, gid)? {
+            let drawbox = if placement.drawbox == Drawbox::Bounds {
+                bitmap.bounds
+            } else {
+                font_drawbox
+            };
+            let mut lottie = template.unwrap_or_else(|| default_template(&font_drawbox));
+            let label = format!("glyph {}", gid.to_u16());
+            let animator = animation.animator();
+            replace_with_bitmap(
+                &mut lottie,
+                &bitmap,
+                &drawbox,
+                &label,
+                animator.as_ref(),
+                placement,
+            )?;
+            return Ok(lottie);
+        }
+    }
+    let glyph =
+        Glyph::new(font, gid, location)?.with_palettes(font, style.palette, style.palette_to)?;
+    let mut glyphs = Glyphs::new(glyph);
+    bind_slots(&mut glyphs, font, location, style, placement)?;
+    let mut lottie = template.unwrap_or_else(|| default_template(&font_drawbox));
+    lottie.replace_shape(
+        &font_drawbox,
+        &glyphs,
+        animation.animator().as_ref(),
+        style,
+        placement,
+    )?;
+    Ok(lottie)
+}
+
+/// Animate the glyphs of `line`, scaled together to fit placeholders, as [lottie_for_glyph]
+/// animates a single glyph
+///
+/// Lay the line out with [text::layout_by_advance] or, with the `shaping` feature,
+/// [text::shape].
+pub fn lottie_for_text(
+    font: &FontRef,
+    line: &Line,
+    location: &DesignLocation,
+    template: Option<Lottie>,
+    animation: &Animation,
+    style: &Style,
+    placement: &Placement,
+) -> Result<Lottie, Error> {
+    // As tall as a glyph's drawbox, as wide as the line
+    let em = font_drawbox_for(font, placement)?;
+    let font_drawbox = Rect::new(0.0, em.y0, line.advance, em.y1);
+    let mut glyphs = Glyphs::new(Glyph::line(font, line, location)?);
+    bind_slots(&mut glyphs, font, location, style, placement)?;
+    let mut lottie = template.unwrap_or_else(|| default_template(&font_drawbox));
+    lottie.replace_shape(
+        &font_drawbox,
+        &glyphs,
+        animation.animator().as_ref(),
+        style,
+        placement,
+    )?;
+    Ok(lottie)
+}
+
+/// Animate an SVG icon, placing it in `template` or, if there isn't one, in a [default_template]
+/// the size of the icon's view box
+///
+/// `name`, such as the file's stem, labels it in output. Icons are drawn as [Glyph::drawing]
+/// draws them; they have no axes and [Placement::slots] don't apply.
+#[cfg(feature = "svg")]
+pub fn lottie_for_svg(
+    icon: &svg::SvgIcon,
+    name: &str,
+    template: Option<Lottie>,
+    animation: &Animation,
+    style: &Style,
+    placement: &Placement,
+) -> Result<Lottie, Error> {
+    let glyphs = Glyphs::new(Glyph::drawing(&format!("svg {name}"), &icon.paths));
+    let mut lottie = template.unwrap_or_else(|| default_template(&icon.drawbox));
+    lottie.replace_shape(
+        &icon.drawbox,
+        &glyphs,
+        animation.animator().as_ref(),
+        style,
+        placement,
+    )?;
+    Ok(lottie)
+}
+
+/// Bind the glyphs for the codepoints of [Placement::slots]
+fn bind_slots<'a>(
+    glyphs: &mut Glyphs<'a>,
+    font: &FontRef<'a>,
+    location: &DesignLocation,
+    style: &Style,
+    placement: &Placement,
+) -> Result<(), Error> {
+    for (slot, codepoint) in placement.slots.iter() {
+        let gid = font
+            .charmap()
+            .map(*codepoint)
+            .ok_or(Error::NoGlyphForCodepoint(*codepoint))?;
+        let glyph = Glyph::new(font, gid, location)?.with_palettes(
+            font,
+            style.palette,
+            style.palette_to,
+        )?;
+        glyphs.bind(slot.clone(), glyph);
+    }
+    Ok(())
+}
+
+/// A glyph at a location in design space, and the axes of its font so it can be drawn elsewhere
+pub struct Glyph<'a> {
+    gid: GlyphId,
+    /// The first codepoint mapped to the glyph, if any
+    codepoint: Option<u32>,
+    /// None for drawings that aren't from a font, see [Glyph::drawing]
+    font: Option<FontOutlines<'a>>,
+    location: DesignLocation,
+    /// Empty unless this is a color glyph
+    color_layers: Vec<ColorLayer>,
+    /// The color layers in the palette to shift to as the glyph animates, empty if it doesn't
+    shifted_color_layers: Vec<ColorLayer>,
+    /// Why the glyph's OT-SVG document couldn't be drawn, in which case its outline is
+    unusable_svg: Option<Error>,
+    /// For text, the glyphs drawn in place of `outline`, each with its offset in font units
+    run: Vec<(OutlineGlyph<'a>, Vec2)>,
+    /// For drawings of one color, the paths drawn in place of `outline`, in font units
+    paths: Vec<BezPath>,
+    /// What to call the glyph in place of its codepoint, such as `text "abc"` for a line
+    name: Option<String>,
+}
+
+/// The outline of a font's glyph and what's needed to draw it, and others, elsewhere
+struct FontOutlines<'a> {
+    outline: OutlineGlyph<'a>,
+    outlines: OutlineGlyphCollection<'a>,
+    axes: AxisCollection<'a>,
+}
+
+impl<'a> Glyph<'a> {
+    pub fn new(font: &FontRef<'a>, gid: GlyphId, location: &DesignLocation) -> Result<Self, Error> {
+        let outlines = font.outline_glyphs();
+        let outline = outlines.get(gid).ok_or(Error::NoOutline(gid))?;
+        let glyph = Glyph {
+            gid,
+            codepoint: font
+                .charmap()
+                .mappings()
+                .find_map(|(codepoint, mapped)| (mapped == gid).then_some(codepoint)),
+            font: Some(FontOutlines {
+                outline,
+                outlines,
+                axes: font.axes(),
+            }),
+            location: location.clone(),
+            color_layers: Vec::new(),
+            shifted_color_layers: Vec::new(),
+            unusable_svg: None,
+            run: Vec::new(),
+            paths: Vec::new(),
+            name: None,
+        };
+        // Fail early on axes the font doesn't have
+        let location = glyph.location(&[])?;
+        // One unusable SVG document mustn't stop the rest of the font, draw its outline instead
+        let (color_layers, unusable_svg) = match color_layers(font, gid, 0, &location) {
+            Ok(layers) => (layers, None),
+            Err(e @ Error::BadSvg(_)) => (Vec::new(), Some(e)),
+            Err(e) => return Err(e),
+        };
+        Ok(Glyph {
+            color_layers,
+            unusable_svg,
+            ..glyph
+        })
+    }
+
+    /// The glyphs of `line`, to be drawn and animated as one glyph
+    ///
+    /// Color glyphs in the line are drawn as their plain outlines.
+    pub fn line(font: &FontRef<'a>, line: &Line, location: &DesignLocation) -> Result<Self, Error> {
+        let Some((first, _)) = line.glyphs.first() else {
+            return Err(Error::EmptyText);
+        };
+        let glyph = Glyph::new(font, *first, location)?;
+        let outlines = &glyph.font()?.outlines;
+        let run = line
+            .glyphs
+            .iter()
+            .map(|(gid, offset)| {
+                let outline = outlines.get(*gid).ok_or(Error::NoOutline(*gid))?;
+                Ok((outline, *offset))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Glyph {
+            codepoint: None,
+            color_layers: Vec::new(),
+            unusable_svg: None,
+            run,
+            name: Some(format!("text {:?}", line.text)),
+            ..glyph
+        })
+    }
+
+    /// Filled paths that aren't from a font, such as an SVG icon's, in font units, to be drawn
+    /// and animated as a glyph labelled `name`
+    ///
+    /// Paths all of one solid color are drawn as a plain glyph, in the style's fill; others as
+    /// a color glyph, a layer per path. Drawings have no axes, nor other glyphs to swap to.
+    pub fn drawing(name: &str, paths: &[(BezPath, Paint)]) -> Glyph<'static> {
+        let one_color = paths.windows(2).all(|pair| match (&pair[0].1, &pair[1].1) {
+            (
+                Paint::Solid { color, alpha },
+                Paint::Solid {
+                    color: other_color,
+                    alpha: other_alpha,
+                },
+            ) => color == other_color && alpha == other_alpha,
+            _ => false,
+        });
+        let (paths, color_layers) = if one_color {
+            (
+                paths.iter().map(|(path, _)| path.clone()).collect(),
+                Vec::new(),
+            )
+        } else {
+            let layers = paths
+                .iter()
+                .map(|(path, paint)| ColorLayer {
+                    gid: GlyphId::NOTDEF,
+                    transform: Affine::IDENTITY,
+                    paint: paint.clone(),
+                    path: Some(path.clone()),
+                })
+                .collect();
+            (Vec::new(), layers)
+        };
+        Glyph {
+            gid: GlyphId::NOTDEF,
+            codepoint: None,
+            font: None,
+            location: DesignLocation::default(),
+            color_layers,
+            shifted_color_layers: Vec::new(),
+            unusable_svg: None,
+            run: Vec::new(),
+            paths,
+            name: Some(name.to_string()),
+        }
+    }
+
+    /// Draw, in font units, at the glyph's location
+    pub fn draw(&self, pen: &mut impl OutlinePen) -> Result<(), Error> {
+        self.draw_at(&[], pen)
+    }
+
+    /// The control box of the glyph's outline, None if it draws nothing as color glyphs may not
+    pub fn bounds(&self) -> Result<Option<Rect>, Error> {
+        let mut pen = ControlBoundsPen::new();
+        self.draw(&mut pen)?;
+        Ok(pen.bounds())
+    }
+
+    /// The centroid of the area the glyph's outline fills, None if it fills nothing
+    pub fn centroid(&self) -> Result<Option<Point>, Error> {
+        let mut pen = SubPathPen::default();
+        self.draw(&mut pen)?;
+        let paths: Vec<_> = pen.into_shapes().into_iter().map(|(bez, _)| bez).collect();
+        Ok(area_centroid(&paths))
+    }
+
+    /// Color from CPAL palette `palette` rather than the first and, if `palette_to` is set,
+    /// shift to that palette's colors as the glyph animates
+    ///
+    /// Does nothing for glyphs that aren't color glyphs.
+    pub fn with_palettes(
+        self,
+        font: &FontRef,
+        palette: u16,
+        palette_to: Option<u16>,
+    ) -> Result<Self, Error> {
+        if self.color_layers.is_empty() {
+            return Ok(self);
+        }
+        let location = self.location(&[])?;
+        let shifted_color_layers = match palette_to {
+            Some(palette_to) => color_layers(font, self.gid, palette_to, &location)?,
+            None => Vec::new(),
+        };
+        Ok(Glyph {
+            color_layers: color_layers(font, self.gid, palette, &location)?,
+            shifted_color_layers,
+            ..self
+        })
+    }
+
+    /// What to call the glyph in output, such as "glyph U+E87C", or "glyph 12" by glyph id if
+    /// it isn't mapped
+    pub fn label(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+        match self.codepoint {
+            Some(codepoint) => format!("glyph U+{codepoint:04X}"),
+            None => format!("glyph {}", self.gid.to_u16()),
+        }
+    }
+
+    /// The COLR layers, bottom to top, empty if this isn't a color glyph
+    pub fn color_layers(&self) -> &[ColorLayer] {
+        &self.color_layers
+    }
+
+    /// The COLR layers in the palette to shift to, empty unless the glyph changes palette
+    pub fn shifted_color_layers(&self) -> &[ColorLayer] {
+        &self.shifted_color_layers
+    }
+
+    /// Why the glyph's OT-SVG document couldn't be read, if it couldn't; the glyph is then
+    /// drawn as its plain outline
+    pub fn unusable_svg(&self) -> Option<&Error> {
+        self.unusable_svg.as_ref()
+    }
+
+    /// Draw, in font units, at the glyph's location overridden by `settings`
+    ///
+    /// Color glyphs draw every layer, bottom to top, text every glyph of its line.
+    fn draw_at(
+        &self,
+        settings: &[VariationSetting],
+        pen: &mut impl OutlinePen,
+    ) -> Result<(), Error> {
+        if !self.run.is_empty() {
+            for (outline, offset) in self.run.iter() {
+                let mut pen = TransformPen::new(&mut *pen, Affine::translate(*offset));
+                self.draw_outline(outline, settings, &mut pen)?;
+            }
+            return Ok(());
+        }
+        if !self.paths.is_empty() {
+            self.location(settings)?;
+            for path in self.paths.iter() {
+                draw_path(path, pen);
+            }
+            return Ok(());
+        }
+        if self.color_layers.is_empty() {
+            return self.draw_outline(&self.font()?.outline, settings, pen);
+        }
+        for layer in 0..self.color_layers.len() {
+            self.draw_layer(layer, settings, pen)?;
+        }
+        Ok(())
+    }
+
+    /// Draw one of the [Glyph::color_layers]
+    fn draw_layer(
+        &self,
+        layer: usize,
+        settings: &[VariationSetting],
+        pen: &mut impl OutlinePen,
+    ) -> Result<(), Error> {
+        let layer = &self.color_layers[layer];
+        if let Some(path) = &layer.path {
+            self.location(settings)?;
+            draw_path(&(layer.transform * path.clone()), pen);
+            return Ok(());
+        }
+        let outline = self
+            .font()?
+            .outlines
+            .get(layer.gid)
+            .ok_or(Error::NoOutline(layer.gid))?;
+        self.draw_outline(
+            &outline,
+            settings,
+            &mut TransformPen::new(pen, layer.transform),
+        )
+    }
+
+    fn draw_outline(
+        &self,
+        outline: &OutlineGlyph,
+        settings: &[VariationSetting],
+        pen: &mut impl OutlinePen,
+    ) -> Result<(), Error> {
+        let location = self.location(settings)?;
+        outline
+            .draw(DrawSettings::unhinted(Size::unscaled(), &location), pen)
+            .map_err(Error::DrawError)?;
+        Ok(())
+    }
+
+    /// Draw, in font units, what `sample` asks for: this glyph or another from the same font,
+    /// at the glyph's location overridden by the sample's settings
+    fn draw_sample(&self, sample: &Sample, pen: &mut impl OutlinePen) -> Result<(), Error> {
+        let Some(gid) = sample.gid else {
+            return self.draw_at(&sample.settings, pen);
+        };
+        let outline = self
+            .font()?
+            .outlines
+            .get(gid)
+            .ok_or(Error::NoOutline(gid))?;
+        self.draw_outline(&outline, &sample.settings, pen)
+    }
+
+    fn font(&self) -> Result<&FontOutlines<'a>, Error> {
+        self.font.as_ref().ok_or(Error::NotFromFont)
+    }
+
+    /// Resolve the glyph's location, overridden by `settings` such as wght=700, to a normalized one
+    ///
+    /// Settings for axes the font doesn't have are an error rather than being ignored;
+    /// animating an axis that isn't there would silently produce a still.
+    fn location(&self, settings: &[VariationSetting]) -> Result<Location, Error> {
+        let settings: Vec<_> = self
+            .location
+            .0
+            .iter()
+            .filter(|base| settings.iter().all(|s| s.selector != base.selector))
+            .chain(settings.iter())
+            .copied()
+            .collect();
+        // Drawings have no axes at all
+        let Some(font) = &self.font else {
+            return match settings.first() {
+                Some(setting) => Err(Error::NoSuchAxis(setting.selector)),
+                None => Ok(Location::default()),
+            };
+        };
+        if let Some(missing) = settings
+            .iter()
+            .find(|s| !font.axes.iter().any(|axis| axis.tag() == s.selector))
+        {
+            return Err(Error::NoSuchAxis(missing.selector));
+        }
+        Ok(font.axes.location(settings))
+    }
+}
+
+/// The glyph to place in every placeholder, bar those whose slot has a glyph of its own
+pub struct Glyphs<'a> {
+    glyph: Glyph<'a>,
+    by_slot: HashMap<String, Glyph<'a>>,
+}
+
+impl<'a> Glyphs<'a> {
+    pub fn new(glyph: Glyph<'a>) -> Self {
+        Glyphs {
+            glyph,
+            by_slot: Default::default(),
+        }
+    }
+
+    /// Place `glyph` in placeholders with the slot `slot`, such as "2" for `placeholder:2`
+    pub fn bind(&mut self, slot: String, glyph: Glyph<'a>) {
+        self.by_slot.insert(slot, glyph);
+    }
+
+    pub fn for_placeholder(&self, placeholder: &Placeholder) -> &Glyph<'a> {
+        placeholder
+            .slot
+            .as_ref()
+            .and_then(|slot| self.by_slot.get(slot))
+            .unwrap_or(&self.glyph)
+    }
+}
+
+impl<'a> From<Glyph<'a>> for Glyphs<'a> {
+    fn from(glyph: Glyph<'a>) -> Self {
+        Glyphs::new(glyph)
     }
 }
 
@@ -64,85 +859,197 @@ pub trait Template {
     fn replace_shape(
         &mut self,
         font_drawbox: &Rect,
-        glyph: &OutlineGlyph,
+        glyphs: &Glyphs,
         animator: &dyn Animator,
+        style: &Style,
+        placement: &Placement,
     ) -> Result<(), Error>;
 }
 
 fn replace_placeholders(
-    layers: &mut [AnyLayer],
+    layers: &mut Vec<AnyLayer>,
     font_drawbox: &Rect,
-    glyph: &OutlineGlyph,
+    glyphs: &Glyphs,
     animator: &dyn Animator,
+    style: &Style,
+    placement: &Placement,
 ) -> Result<usize, Error> {
     let mut shapes_updated = 0;
-    for layer in layers.iter_mut() {
+    // Layer indices only need be unique, number mattes after every existing layer
+    let mut next_index = layers
+        .iter()
+        .filter_map(|l| serde_json::to_value(l).ok()?["ind"].as_i64())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut mattes = Vec::new();
+    for (layer_idx, layer) in layers.iter_mut().enumerate() {
         let AnyLayer::Shape(layer) = layer else {
             continue;
         };
-        let placeholders: Vec<_> = layer
-            .mixin
-            .shapes
-            .iter_mut()
-            .filter_map(|any| match any {
-                AnyShape::Group(group) if group.name.as_deref() == Some("placeholder") => {
-                    Some(group)
-                }
-                _ => None,
-            })
-            .collect();
+        let time_range = layer_time_range(layer)?;
+        if let Some(name) =
+            layer_name(layer)?.filter(|n| placement.is_placeholder(Some(n.as_str())))
+        {
+            placeholder_layer_to_group(layer, name);
+        }
+        let mut placeholders = Vec::new();
+        find_placeholders(&mut layer.mixin.shapes, placement, &mut placeholders)?;
 
         let mut insert_at = Vec::with_capacity(1);
-        for placeholder in placeholders {
+        // Where glyphs were placed, across all the layer's placeholders
+        let mut layer_bounds: Option<Rect> = None;
+        // The layer is named, and given effects, for the first placeholder filled in it
+        let mut layer_placeholder = None;
+        for (placeholder, name) in placeholders {
             insert_at.clear();
-            for (i, item) in placeholder.items.iter_mut().enumerate() {
-                let lottie_box = match item {
-                    AnyShape::Shape(shape) => Some(bez_for_subpath(shape).control_box()),
-                    AnyShape::Rect(rect) => {
-                        let Value::Fixed(pos) = &rect.position.value else {
-                            panic!("Unable to process {rect:#?} position, must be fixed");
-                        };
-                        let Value::Fixed(size) = &rect.size.value else {
-                            panic!("Unable to process {rect:#?} size, must be fixed");
-                        };
-                        assert_eq!(2, pos.len());
-                        assert_eq!(2, size.len());
-                        // https://lottiefiles.github.io/lottie-docs/schema/#/$defs/shapes/rectangle notes position
-                        // of a rect is the center; what we want is top-left, bottom-right
-                        let (x0, y0) = (pos[0] - size[0] / 2.0, pos[1] - size[1] / 2.0);
-                        Some(Rect {
-                            x0,
-                            y0,
-                            x1: x0 + size[0],
-                            y1: y0 + size[1],
-                        })
-                    }
-                    _ => None,
-                };
-                let Some(lottie_box) = lottie_box else {
+            let glyph = glyphs.for_placeholder(&name);
+            let drawbox = glyph_drawbox(font_drawbox, glyph, placement)?;
+            let nudge = if placement.optical_center {
+                optical_nudge(glyph)?
+            } else {
+                Vec2::ZERO
+            };
+            let directed = name.animation.as_ref().map(Animation::animator);
+            let animator = directed.as_deref().unwrap_or(animator);
+            // Designers may have painted the placeholder as they want the glyph painted
+            let inherit_paint =
+                style.inherits_placeholder_paint() && placeholder.items.iter().any(is_paint);
+            for (i, item) in placeholder.items.iter().enumerate() {
+                let Some(lottie_box) = placeholder_box(item)
+                    .map_err(|e| e.within(&item_path(placeholder.name.as_deref(), i)))?
+                else {
                     continue;
                 };
-                let font_to_lottie = font_units_to_lottie_units(font_drawbox, &lottie_box);
+                let lottie_box = name.padding.unwrap_or(placement.padding).inset(lottie_box);
+                let font_to_lottie = font_units_to_lottie_units(
+                    &drawbox,
+                    &lottie_box,
+                    name.fit.unwrap_or(placement.fit),
+                    name.align.unwrap_or_default(),
+                )? * Affine::translate(nudge);
                 insert_at.push((i, font_to_lottie));
             }
+            // animations assume time moves forward
+            if !insert_at.is_empty()
+                && layer.in_point.partial_cmp(&layer.out_point) != Some(Ordering::Less)
+            {
+                return Err(Error::UnsupportedTemplate(format!(
+                    "layer plays from {} to {}, it must end after it starts",
+                    layer.in_point, layer.out_point
+                )));
+            }
+            let Some((start, end)) = time_range else {
+                if insert_at.is_empty() {
+                    continue;
+                }
+                return Err(Error::UnsupportedTemplate(
+                    "layer has a time stretch that isn't positive, it must play forwards"
+                        .to_string(),
+                ));
+            };
             // reverse because replacing 1:n shifts indices past our own
             for (i, transform) in insert_at.iter().rev() {
-                eprintln!("Replace {} using {:?}", shapes_updated + i, transform);
-                let mut glyph_shapes: Vec<_> = subpaths_for_glyph(glyph, *transform)?;
-                glyph_shapes.sort_by_cached_key(|(b, _)| {
-                    let bbox = b.control_box();
-                    (
-                        (bbox.min_y() * 1000.0) as i64,
-                        (bbox.min_x() * 1000.0) as i64,
-                    )
-                });
-                eprintln!("Animating {} glyph shapes", glyph_shapes.len());
-                let animated_shapes =
-                    animator.animate(layer.in_point, layer.out_point, glyph_shapes)?;
+                let samples = animator.samples();
+                let mut drawn = if samples.is_empty() {
+                    vec![subpaths_for_glyph(glyph, *transform, &Sample::default())?]
+                } else {
+                    samples
+                        .iter()
+                        .map(|sample| subpaths_for_glyph(glyph, *transform, sample))
+                        .collect::<Result<Vec<_>, _>>()?
+                };
+
+                // Which color layer each of the first sample's shapes is from, if any
+                let mut layer_of = match samples.first() {
+                    Some(Sample { gid: Some(..), .. }) => Vec::new(),
+                    sample => color_layer_of_subpaths(
+                        glyph,
+                        *transform,
+                        sample.unwrap_or(&Sample::default()),
+                    )?,
+                };
+
+                // When samples have the same subpaths, such as one glyph at several locations,
+                // apply the first sample's order to all so shapes still correspond; morphs
+                // verify that they do with animate::verify_contour_correspondence
+                if !animator.contour_order() {
+                    let shared_order = drawn
+                        .iter()
+                        .all(|d| d.len() == drawn[0].len())
+                        .then(|| draw_order(&drawn[0]));
+                    for (n, shapes) in drawn.iter_mut().enumerate() {
+                        let order = shared_order.clone().unwrap_or_else(|| draw_order(shapes));
+                        if n == 0 && !layer_of.is_empty() {
+                            layer_of = order.iter().map(|i| layer_of[*i]).collect();
+                        }
+                        let ordered = order.iter().map(|i| shapes[*i].clone()).collect();
+                        *shapes = ordered;
+                    }
+                }
+                let drawn_subpaths: Vec<_> = if layer_of.is_empty() {
+                    Vec::new()
+                } else {
+                    drawn[0].iter().map(|(_, s)| s.clone()).collect()
+                };
+
+                let mut animated_shapes = if samples.is_empty() {
+                    animator.animate(start, end, drawn.remove(0))?
+                } else {
+                    animator.animate_samples(start, end, drawn)?
+                };
+                // Color glyphs bring their own colors
+                if inherit_paint && layer_of.is_empty() {
+                    remove_fills(&mut animated_shapes);
+                }
+                let bounds = transform.transform_rect_bbox(drawbox);
+                layer_bounds = Some(layer_bounds.map_or(bounds, |b| b.union(bounds)));
+                layer_placeholder.get_or_insert_with(|| name.clone());
+                style.apply(&mut animated_shapes, bounds, start, end)?;
+                if !layer_of.is_empty() {
+                    split_by_layer(
+                        &mut animated_shapes,
+                        &drawn_subpaths,
+                        &layer_of,
+                        glyph,
+                        *transform,
+                        start,
+                        end,
+                    )?;
+                }
+                style.add_accents(&mut animated_shapes, bounds, start, end)?;
+                name_shapes(&mut animated_shapes)?;
                 placeholder.items.splice(*i..(*i + 1), animated_shapes);
             }
             shapes_updated += insert_at.len();
         }
+        if let (Some(bounds), Some(placeholder), Some((start, end))) =
+            (layer_bounds, layer_placeholder, time_range)
+        {
+            let glyph = glyphs.for_placeholder(&placeholder);
+            let directed = placeholder.animation.as_ref().map(Animation::animator);
+            let animator = directed.as_deref().unwrap_or(animator);
+            for effect in animator.layer_effects(start, end)? {
+                add_layer_effect(layer, effect)?;
+            }
+            let masks = animator.layer_masks(start, end, bounds)?;
+            add_layer_masks(layer, masks)?;
+            edit_layer(layer, "nm", |name| {
+                if name.is_null() {
+                    *name = json!(glyph.label());
+                }
+            })?;
+            style.apply_to_layer(layer)?;
+            let matte = animator.matte(start, end, bounds)?;
+            if !matte.is_empty() {
+                mattes.push((layer_idx, alpha_matte(layer, matte, next_index)?));
+                next_index += 1;
+            }
+        }
+    }
+    // A matte applies to the layer just below it, earlier layers being on top
+    for (layer_idx, matte) in mattes.into_iter().rev() {
+        layers.insert(layer_idx, AnyLayer::Shape(matte));
     }
     Ok(shapes_updated)
 }
@@ -151,37 +1058,136 @@ impl Template for Lottie {
     fn replace_shape(
         &mut self,
         font_drawbox: &Rect,
-        glyph: &OutlineGlyph,
+        glyphs: &Glyphs,
         animator: &dyn Animator,
+        style: &Style,
+        placement: &Placement,
     ) -> Result<(), Error> {
-        let mut shapes_updated =
-            replace_placeholders(&mut self.layers, font_drawbox, glyph, animator)?;
+        let mut shapes_updated = replace_placeholders(
+            &mut self.layers,
+            font_drawbox,
+            glyphs,
+            animator,
+            style,
+            placement,
+        )?;
         for asset in self.assets.iter_mut() {
             shapes_updated += match asset {
-                Asset::PreComp(precomp) => {
-                    replace_placeholders(&mut precomp.layers, font_drawbox, glyph, animator)?
-                }
+                Asset::PreComp(precomp) => replace_placeholders(
+                    &mut precomp.layers,
+                    font_drawbox,
+                    glyphs,
+                    animator,
+                    style,
+                    placement,
+                )?,
                 Asset::Image(..) => 0,
             }
         }
         if shapes_updated == 0 {
-            return Err(Error::NoShapesUpdated);
+            return Err(Error::NoPlaceholder {
+                marker: placement.marker.clone(),
+                searched_layers: searched_layers(self),
+            });
         }
         Ok(())
     }
 }
 
+/// Placeholder groups in `shapes`, however deeply nested in other groups
+///
+/// Placeholders within placeholders aren't looked for, the outer one is replaced.
+fn find_placeholders<'a>(
+    shapes: &'a mut [AnyShape],
+    placement: &Placement,
+    found: &mut Vec<(&'a mut Group, Placeholder)>,
+) -> Result<(), Error> {
+    for shape in shapes.iter_mut() {
+        let AnyShape::Group(group) = shape else {
+            continue;
+        };
+        if let Some(placeholder) = placement.placeholder(group.name.as_deref())? {
+            found.push((group, placeholder));
+        } else {
+            find_placeholders(&mut group.items, placement, found)?;
+        }
+    }
+    Ok(())
+}
+
+fn layer_name(layer: &bodymovin::layers::Shape) -> Result<Option<String>, Error> {
+    let json = serde_json::to_value(layer).map_err(Error::JsonError)?;
+    Ok(json["nm"].as_str().map(str::to_string))
+}
+
+/// Replace everything in a layer that is a placeholder with a placeholder group named `name`
+///
+/// The group's box is the first rectangle or path in the layer, found as drawn without the
+/// transforms of groups it's in, and it keeps the layer's fills and strokes.
+fn placeholder_layer_to_group(layer: &mut bodymovin::layers::Shape, name: String) {
+    fn collect(shapes: &[AnyShape], found_box: &mut Option<AnyShape>, paint: &mut Vec<AnyShape>) {
+        for shape in shapes {
+            match shape {
+                AnyShape::Rect(..) | AnyShape::Shape(..) if found_box.is_none() => {
+                    *found_box = Some(shape.clone());
+                }
+                AnyShape::Group(group) => collect(&group.items, found_box, paint),
+                shape if is_paint(shape) => paint.push(shape.clone()),
+                _ => (),
+            }
+        }
+    }
+    let (mut found_box, mut paint) = (None, Vec::new());
+    collect(&layer.mixin.shapes, &mut found_box, &mut paint);
+    // Without a box nothing is placed, which is reported as nothing being replaced
+    let mut items: Vec<_> = found_box.into_iter().collect();
+    items.extend(paint);
+    items.push(AnyShape::Transform(Default::default()));
+    layer.mixin.shapes = vec![AnyShape::Group(Group {
+        name: Some(name),
+        items,
+        ..Default::default()
+    })];
+}
+
+/// When `layer` plays, from its in to its out point, in the layer's own time, which its
+/// keyframes are in; None if it doesn't play forwards
+///
+/// In and out points are in the time of the composition; the layer's time starts at its start
+/// time and runs at its stretch.
+fn layer_time_range(layer: &bodymovin::layers::Shape) -> Result<Option<(f64, f64)>, Error> {
+    let json = serde_json::to_value(layer).map_err(Error::JsonError)?;
+    let start_time = json["st"].as_f64().unwrap_or(0.0);
+    let stretch = json["sr"].as_f64().unwrap_or(1.0);
+    if stretch.partial_cmp(&0.0) != Some(Ordering::Greater) {
+        return Ok(None);
+    }
+    Ok(Some((
+        (layer.in_point - start_time) / stretch,
+        (layer.out_point - start_time) / stretch,
+    )))
+}
+
 /// Simplified version of [Affine2D::rect_to_rect](https://github.com/googlefonts/picosvg/blob/a0bcfade7a60cbd6f47d8bfe65b6d471cee628c0/src/picosvg/svg_transform.py#L216-L263)
-fn font_units_to_lottie_units(font_box: &Rect, lottie_box: &Rect) -> Affine {
-    assert!(font_box.width() > 0.0);
-    assert!(font_box.height() > 0.0);
-    assert!(lottie_box.width() > 0.0);
-    assert!(lottie_box.height() > 0.0);
-
-    let (sx, sy) = (
-        lottie_box.width() / font_box.width(),
-        lottie_box.height() / font_box.height(),
-    );
+///
+/// `fit` decides the scale and `align` where the result sits in `lottie_box` if it isn't
+/// the same size.
+fn font_units_to_lottie_units(
+    font_box: &Rect,
+    lottie_box: &Rect,
+    fit: FitMode,
+    align: Align,
+) -> Result<Affine, Error> {
+    // Negated so NaN is rejected too
+    for (what, rect) in [("font", font_box), ("placeholder", lottie_box)] {
+        if !(rect.width() > 0.0 && rect.height() > 0.0 && rect.is_finite()) {
+            return Err(Error::UnsupportedTemplate(format!(
+                "{what} box {rect:?} must have a finite, positive size"
+            )));
+        }
+    }
+
+    let (sx, sy) = fit.scale(font_box, lottie_box);
     let transform = Affine::IDENTITY
         // Move the font box to touch the origin
         .then_translate((-font_box.min_x(), -font_box.min_y()).into())
@@ -192,18 +1198,93 @@ fn font_units_to_lottie_units(font_box: &Rect, lottie_box: &Rect) -> Affine {
 
     // Line up
     let adjusted_font_box = transform.transform_rect_bbox(*font_box);
-    transform.then_translate(
+    let (ax, ay) = align.fractions();
+    Ok(transform.then_translate(
         (
-            lottie_box.min_x() - adjusted_font_box.min_x(),
-            lottie_box.min_y() - adjusted_font_box.min_y(),
+            lottie_box.min_x() - adjusted_font_box.min_x()
+                + ax * (lottie_box.width() - adjusted_font_box.width()),
+            lottie_box.min_y() - adjusted_font_box.min_y()
+                + ay * (lottie_box.height() - adjusted_font_box.height()),
         )
             .into(),
-    )
+    ))
+}
+
+/// The names of the layers of `lottie`, and of its precomps, where placeholders are looked for
+pub fn searched_layers(lottie: &Lottie) -> Vec<String> {
+    let precomp_layers = lottie.assets.iter().flat_map(|asset| match asset {
+        Asset::PreComp(precomp) => precomp.layers.as_slice(),
+        Asset::Image(..) => &[],
+    });
+    lottie
+        .layers
+        .iter()
+        .chain(precomp_layers)
+        .filter_map(|layer| {
+            let json = serde_json::to_value(layer).ok()?;
+            Some(json["nm"].as_str().unwrap_or("unnamed").to_string())
+        })
+        .collect()
+}
+
+/// Where item `i` of the placeholder group `name` is, for errors about it
+fn item_path(name: Option<&str>, i: usize) -> String {
+    format!("{}.it[{i}]", name.unwrap_or_default())
 }
 
-fn bez_for_subpath(subpath: &SubPath) -> BezPath {
+/// The box a placeholder item marks out for the glyph, None if it's not a rectangle or path
+fn placeholder_box(item: &AnyShape) -> Result<Option<Rect>, Error> {
+    let unsupported = |path: &str, reason: String| Error::UnsupportedPropertyValue {
+        path: path.to_string(),
+        reason,
+    };
+    Ok(match item {
+        AnyShape::Shape(shape) => Some(bez_for_subpath(shape)?.control_box()),
+        AnyShape::Rect(rect) => {
+            let Value::Fixed(pos) = &rect.position.value else {
+                return Err(unsupported(
+                    "p",
+                    "placeholder rect position must not be animated".to_string(),
+                ));
+            };
+            let Value::Fixed(size) = &rect.size.value else {
+                return Err(unsupported(
+                    "s",
+                    "placeholder rect size must not be animated".to_string(),
+                ));
+            };
+            let [x, y] = pos.as_slice() else {
+                return Err(unsupported(
+                    "p",
+                    format!("placeholder rect position must be 2d, got {pos:?}"),
+                ));
+            };
+            let [width, height] = size.as_slice() else {
+                return Err(unsupported(
+                    "s",
+                    format!("placeholder rect size must be 2d, got {size:?}"),
+                ));
+            };
+            // https://lottiefiles.github.io/lottie-docs/schema/#/$defs/shapes/rectangle notes position
+            // of a rect is the center; what we want is top-left, bottom-right
+            let (x0, y0) = (x - width / 2.0, y - height / 2.0);
+            Some(Rect {
+                x0,
+                y0,
+                x1: x0 + width,
+                y1: y0 + height,
+            })
+        }
+        _ => None,
+    })
+}
+
+fn bez_for_subpath(subpath: &SubPath) -> Result<BezPath, Error> {
     let Value::Fixed(value) = &subpath.vertices.value else {
-        panic!("what is {subpath:?}");
+        return Err(Error::UnsupportedPropertyValue {
+            path: "ks".to_string(),
+            reason: "placeholder paths must not be animated".to_string(),
+        });
     };
 
     let mut path = BezPath::new();
@@ -218,30 +1299,287 @@ fn bez_for_subpath(subpath: &SubPath) -> BezPath {
         let end = start_end[1];
         path.curve_to(*c0, *c1, end);
     }
-    path
+    Ok(path)
+}
+
+/// Top to bottom, left to right
+fn draw_order(shapes: &[(BezPath, SubPath)]) -> Vec<usize> {
+    let mut order: Vec<_> = (0..shapes.len()).collect();
+    order.sort_by_cached_key(|i| {
+        let bbox = shapes[*i].0.control_box();
+        (
+            (bbox.min_y() * 1000.0) as i64,
+            (bbox.min_x() * 1000.0) as i64,
+        )
+    });
+    order
+}
+
+/// Draw `path` with `pen`
+fn draw_path(path: &BezPath, pen: &mut impl OutlinePen) {
+    for element in path.elements() {
+        match *element {
+            PathEl::MoveTo(p) => pen.move_to(p.x as f32, p.y as f32),
+            PathEl::LineTo(p) => pen.line_to(p.x as f32, p.y as f32),
+            PathEl::QuadTo(c, p) => pen.quad_to(c.x as f32, c.y as f32, p.x as f32, p.y as f32),
+            PathEl::CurveTo(c0, c1, p) => pen.curve_to(
+                c0.x as f32,
+                c0.y as f32,
+                c1.x as f32,
+                c1.y as f32,
+                p.x as f32,
+                p.y as f32,
+            ),
+            PathEl::ClosePath => pen.close(),
+        }
+    }
 }
 
 /// Returns a [SubPath] and [BezPath] in Lottie units for each subpath of a glyph
+///
+/// Draws what `sample` asks for: the glyph, or another from the same font, at the glyph's
+/// location overridden by the sample's settings.
 fn subpaths_for_glyph(
-    glyph: &OutlineGlyph,
+    glyph: &Glyph,
     font_units_to_lottie_units: Affine,
+    sample: &Sample,
 ) -> Result<Vec<(BezPath, SubPath)>, Error> {
     // Fonts draw Y-up, Lottie Y-down. The transform to transition should be negative determinant.
     // Normally a negative determinant flips curve direction but since we're also moving
     // to a coordinate system with Y flipped it should cancel out.
-    assert!(
-        font_units_to_lottie_units.determinant() < 0.0,
-        "We assume a negative determinant"
-    );
+    if font_units_to_lottie_units.determinant().partial_cmp(&0.0) != Some(Ordering::Less) {
+        return Err(Error::UnsupportedTemplate(
+            "the placeholder is too small to draw into".to_string(),
+        ));
+    }
 
     let mut subpath_pen = SubPathPen::default();
     let mut transform_pen = TransformPen::new(&mut subpath_pen, font_units_to_lottie_units);
-    glyph
-        .draw(Size::unscaled(), &mut transform_pen)
-        .map_err(Error::DrawError)?;
+    glyph.draw_sample(sample, &mut transform_pen)?;
 
     Ok(subpath_pen.into_shapes())
 }
 
+/// The index of the color layer each subpath [subpaths_for_glyph] draws is from
+///
+/// Empty for glyphs that aren't color glyphs.
+fn color_layer_of_subpaths(
+    glyph: &Glyph,
+    font_units_to_lottie_units: Affine,
+    sample: &Sample,
+) -> Result<Vec<usize>, Error> {
+    let mut layer_of = Vec::new();
+    for layer in 0..glyph.color_layers().len() {
+        let mut subpath_pen = SubPathPen::default();
+        let mut transform_pen = TransformPen::new(&mut subpath_pen, font_units_to_lottie_units);
+        glyph.draw_layer(layer, &sample.settings, &mut transform_pen)?;
+        layer_of.extend(std::iter::repeat(layer).take(subpath_pen.into_shapes().len()));
+    }
+    Ok(layer_of)
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codepoint_in_any_syntax() {
+        for s in ["0xE87C", "0Xe87c", "U+E87C", "u+e87c", "59516", " 59516 "] {
+            assert_eq!(parse_codepoint(s).unwrap(), 0xE87C, "{s}");
+        }
+        assert_eq!(parse_codepoint("★").unwrap(), 0x2605);
+        // A lone digit is decimal, not the character
+        assert_eq!(parse_codepoint("7").unwrap(), 7);
+    }
+
+    #[test]
+    fn bad_codepoints() {
+        for s in [
+            "",
+            "0x",
+            "U+",
+            "0xZZ",
+            "ab",
+            "0xD800",
+            "0x110000",
+            "99999999999",
+        ] {
+            assert!(
+                matches!(parse_codepoint(s), Err(Error::BadCodepoint(..))),
+                "{s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn codepoint_ranges() {
+        assert_eq!(
+            parse_codepoint_range("0xE000-0xE0FF").unwrap(),
+            0xE000..=0xE0FF
+        );
+        assert_eq!(
+            parse_codepoint_range("U+E000-U+E0FF").unwrap(),
+            0xE000..=0xE0FF
+        );
+        assert_eq!(parse_codepoint_range("0xE000").unwrap(), 0xE000..=0xE000);
+        // The character itself, not an empty range
+        assert_eq!(parse_codepoint_range("-").unwrap(), 0x2D..=0x2D);
+    }
+
+    #[test]
+    fn bad_codepoint_ranges() {
+        for s in ["", "0xE0FF-0xE000", "0xE000-", "-0xE000", "0xE000-0xZZ"] {
+            assert!(
+                matches!(parse_codepoint_range(s), Err(Error::BadCodepoint(..))),
+                "{s:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn sequences() {
+        assert_eq!(
+            parse_sequence("U+1F469 U+200D U+1F4BB").unwrap(),
+            vec![0x1F469, 0x200D, 0x1F4BB]
+        );
+        assert_eq!(
+            parse_sequence("\u{1F469}\u{200D}\u{1F4BB}").unwrap(),
+            vec![0x1F469, 0x200D, 0x1F4BB]
+        );
+        assert_eq!(parse_sequence("0x1F600").unwrap(), vec![0x1F600]);
+        assert!(parse_sequence(" ").is_err());
+        assert!(parse_sequence("U+1F469 nope").is_err());
+    }
+
+    #[test]
+    fn design_locations() {
+        let location: DesignLocation = " wght = 700 , FILL=1,".parse().unwrap();
+        let settings: Vec<_> = location.0.iter().map(|s| (s.selector, s.value)).collect();
+        assert_eq!(
+            settings,
+            vec![(Tag::new(b"wght"), 700.0), (Tag::new(b"FILL"), 1.0)]
+        );
+        assert!("".parse::<DesignLocation>().unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn bad_design_locations() {
+        for s in ["wght", "wght=bold", "toolong=1", "wght=700;FILL=1"] {
+            assert!(
+                matches!(
+                    s.parse::<DesignLocation>(),
+                    Err(Error::BadDesignLocation(..))
+                ),
+                "{s:?}"
+            );
+        }
+        assert!(matches!(
+            "wght=400,FILL=1,wght=700".parse::<DesignLocation>(),
+            Err(Error::DuplicateAxis(tag)) if tag == Tag::new(b"wght")
+        ));
+    }
+
+    /// Where a 100x50 font box lands in a 200x200 placeholder
+    fn placed(fit: FitMode, align: Align) -> Rect {
+        let font_box = Rect::new(0.0, 0.0, 100.0, 50.0);
+        let lottie_box = Rect::new(0.0, 0.0, 200.0, 200.0);
+        let transform = font_units_to_lottie_units(&font_box, &lottie_box, fit, align).unwrap();
+        // Fonts are y-up, Lottie y-down
+        assert!(transform.determinant() < 0.0, "{fit:?} doesn't flip");
+        transform.transform_rect_bbox(font_box)
+    }
+
+    fn assert_rect_eq(actual: Rect, expected: Rect) {
+        let close = [
+            (actual.x0, expected.x0),
+            (actual.y0, expected.y0),
+            (actual.x1, expected.x1),
+            (actual.y1, expected.y1),
+        ]
+        .iter()
+        .all(|(a, e)| (a - e).abs() < 1e-9);
+        assert!(close, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn fit_modes() {
+        let center = Align::Center;
+        assert_rect_eq(
+            placed(FitMode::Contain, center),
+            Rect::new(0.0, 50.0, 200.0, 150.0),
+        );
+        assert_rect_eq(
+            placed(FitMode::Cover, center),
+            Rect::new(-100.0, 0.0, 300.0, 200.0),
+        );
+        assert_rect_eq(
+            placed(FitMode::Stretch, center),
+            Rect::new(0.0, 0.0, 200.0, 200.0),
+        );
+        assert_rect_eq(
+            placed(FitMode::None, center),
+            Rect::new(50.0, 75.0, 150.0, 125.0),
+        );
+    }
+
+    #[test]
+    fn alignment() {
+        assert_rect_eq(
+            placed(FitMode::None, Align::TopLeft),
+            Rect::new(0.0, 0.0, 100.0, 50.0),
+        );
+        assert_rect_eq(
+            placed(FitMode::None, Align::BottomRight),
+            Rect::new(100.0, 150.0, 200.0, 200.0),
+        );
+        assert_rect_eq(
+            placed(FitMode::Contain, Align::Top),
+            Rect::new(0.0, 0.0, 200.0, 100.0),
+        );
+    }
+
+    #[test]
+    fn glyph_top_stays_on_top() {
+        let font_box = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let lottie_box = Rect::new(0.0, 0.0, 100.0, 100.0);
+        let transform =
+            font_units_to_lottie_units(&font_box, &lottie_box, FitMode::Contain, Align::Center)
+                .unwrap();
+        assert_eq!(transform * Point::new(0.0, 100.0), Point::new(0.0, 0.0));
+        assert_eq!(transform * Point::new(100.0, 0.0), Point::new(100.0, 100.0));
+    }
+
+    #[test]
+    fn empty_boxes_are_errors() {
+        let lottie_box = Rect::new(0.0, 0.0, 200.0, 200.0);
+        for font_box in [
+            Rect::new(0.0, 0.0, 0.0, 50.0),
+            Rect::new(0.0, 0.0, 100.0, 0.0),
+            Rect::new(0.0, 0.0, f64::NAN, 50.0),
+            Rect::new(0.0, 0.0, f64::INFINITY, 50.0),
+        ] {
+            for fit in [
+                FitMode::Contain,
+                FitMode::Cover,
+                FitMode::Stretch,
+                FitMode::None,
+            ] {
+                assert!(
+                    matches!(
+                        font_units_to_lottie_units(&font_box, &lottie_box, fit, Align::Center),
+                        Err(Error::UnsupportedTemplate(..))
+                    ),
+                    "{font_box:?} {fit:?}"
+                );
+            }
+        }
+        let font_box = Rect::new(0.0, 0.0, 100.0, 50.0);
+        assert!(font_units_to_lottie_units(
+            &font_box,
+            &Rect::ZERO,
+            FitMode::Contain,
+            Align::Center
+        )
+        .is_err());
+    }
+}
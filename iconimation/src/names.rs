@@ -0,0 +1,152 @@
+//! Names for generated shapes so output is navigable in Lottie editors.
+//!
+//! Contours are numbered in drawing order across the whole glyph, `part-0`, `part-1` and so
+//! on, holes noting the part they cut, as in `part-2 (hole of part-1)`. Groups are named for
+//! the parts they hold. Anything already named, such as a shadow, keeps its name.
+
+use bodymovin::shapes::AnyShape;
+use kurbo::{BezPath, Point, Shape};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{animate::a_contained_point, error::Error};
+
+/// Name every unnamed group, contour, fill, stroke and transform in `shapes`
+pub(crate) fn name_shapes(shapes: &mut Vec<AnyShape>) -> Result<(), Error> {
+    let mut json = serde_json::to_value(&*shapes).map_err(Error::JsonError)?;
+    let Value::Array(items) = &mut json else {
+        unreachable!("We serialized a Vec");
+    };
+    let mut next_part = 0;
+    name_items(items, &mut next_part);
+    *shapes = Vec::<AnyShape>::deserialize(json).map_err(Error::JsonError)?;
+    Ok(())
+}
+
+/// Name the items of one group, returning the range of parts they hold
+fn name_items(items: &mut [Value], next_part: &mut usize) -> Option<(usize, usize)> {
+    let first_part = *next_part;
+
+    // Contours in this group, and whether each is filled or a hole, as group_icon_parts decides
+    let contours: Vec<_> = items
+        .iter()
+        .enumerate()
+        .filter(|(_, item)| item["ty"] == "sh")
+        .map(|(i, item)| (i, bez_for_shape(item)))
+        .collect();
+    let filled: Vec<_> = contours
+        .iter()
+        .map(|(_, bez)| {
+            let Some(contained) = bez.as_ref().and_then(a_contained_point) else {
+                return true;
+            };
+            contours
+                .iter()
+                .filter_map(|(_, bez)| bez.as_ref())
+                .map(|bez| bez.winding(contained))
+                .sum::<i32>()
+                != 0
+        })
+        .collect();
+    let parts: Vec<_> = (0..contours.len()).map(|n| first_part + n).collect();
+    *next_part += contours.len();
+
+    for (n, (i, bez)) in contours.iter().enumerate() {
+        let name = if filled[n] {
+            format!("part-{}", parts[n])
+        } else {
+            // The smallest filled contour around the hole
+            let cut = bez.as_ref().and_then(|bez| {
+                let bbox = bez.bounding_box();
+                (0..contours.len())
+                    .filter(|m| filled[*m])
+                    .filter_map(|m| {
+                        let outer = contours[m].1.as_ref()?.bounding_box();
+                        (outer.intersect(bbox) == bbox).then_some((m, outer.area()))
+                    })
+                    .min_by(|a, b| a.1.total_cmp(&b.1))
+                    .map(|(m, _)| parts[m])
+            });
+            match cut {
+                Some(cut) => format!("part-{} (hole of part-{cut})", parts[n]),
+                None => format!("part-{} (hole)", parts[n]),
+            }
+        };
+        name_if_unnamed(&mut items[*i], name);
+    }
+
+    for item in items.iter_mut() {
+        let name = match item["ty"].as_str() {
+            Some("gr") => {
+                let Some(Value::Array(children)) = item.get_mut("it") else {
+                    continue;
+                };
+                match name_items(children, next_part) {
+                    Some((first, last)) if first == last => format!("part-{first}"),
+                    Some((first, last)) => format!("parts {first}-{last}"),
+                    None => continue,
+                }
+            }
+            Some("fl") => "fill".to_string(),
+            Some("gf") => "gradient fill".to_string(),
+            Some("st") => "stroke".to_string(),
+            Some("tr") if is_animated(item) => "animated transform".to_string(),
+            Some("tr") => "transform".to_string(),
+            _ => continue,
+        };
+        name_if_unnamed(item, name);
+    }
+
+    (*next_part > first_part).then(|| (first_part, *next_part - 1))
+}
+
+fn name_if_unnamed(item: &mut Value, name: String) {
+    if item["nm"].is_null() {
+        item["nm"] = json!(name);
+    }
+}
+
+/// Whether any property within `value` is keyframed
+pub(crate) fn is_animated(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => {
+            fields.get("a").and_then(Value::as_i64) == Some(1) || fields.values().any(is_animated)
+        }
+        Value::Array(values) => values.iter().any(is_animated),
+        _ => false,
+    }
+}
+
+/// The path of a shape, as first drawn if it's keyframed
+fn bez_for_shape(shape: &Value) -> Option<BezPath> {
+    let path = &shape["ks"]["k"];
+    let path = if path.is_array() {
+        &path[0]["s"][0]
+    } else {
+        path
+    };
+    let points = |key: &str| -> Option<Vec<Point>> {
+        path[key]
+            .as_array()?
+            .iter()
+            .map(|p| Some(Point::new(p[0].as_f64()?, p[1].as_f64()?)))
+            .collect()
+    };
+    let (vertices, ins, outs) = (points("v")?, points("i")?, points("o")?);
+    if vertices.is_empty() || ins.len() != vertices.len() || outs.len() != vertices.len() {
+        return None;
+    }
+    // Tangents are relative to their vertex
+    let mut bez = BezPath::new();
+    bez.move_to(vertices[0]);
+    for i in 1..=vertices.len() {
+        let (prev, curr) = (i - 1, i % vertices.len());
+        bez.curve_to(
+            vertices[prev] + outs[prev].to_vec2(),
+            vertices[curr] + ins[curr].to_vec2(),
+            vertices[curr],
+        );
+    }
+    bez.close_path();
+    Some(bez)
+}
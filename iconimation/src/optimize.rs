@@ -0,0 +1,552 @@
+//! Make written Lottie smaller without changing how it plays.
+//!
+//! Works on the json, after everything else, so it can leave out what bodymovin would write
+//! back: players fill in defaults, bodymovin may not, so optimized output is for players
+//! rather than for loading back in.
+//!
+//! See <https://lottiefiles.github.io/lottie-docs/concepts/#transform> for the defaults.
+
+use serde_json::{json, Value};
+
+/// Optimize `lottie`, its layers and those of its precomps
+///
+/// * Drops layer and transform properties equal to their defaults
+/// * Removes groups that draw nothing
+/// * Removes static shapes identical to one before them in the same group, where that can't
+///   change how they're painted
+/// * Drops keyframes that hold the value of those either side of them, and animations that
+///   never change value
+pub fn optimize(lottie: &mut Value) {
+    // Keyframes first, properties that turn static may then be defaults
+    drop_redundant_keyframes(lottie);
+    for layers in layer_lists(lottie) {
+        for layer in layers.iter_mut() {
+            optimize_layer(layer);
+        }
+    }
+}
+
+/// The layers of `lottie` and of each of its precomps
+fn layer_lists(lottie: &mut Value) -> Vec<&mut Vec<Value>> {
+    let Value::Object(fields) = lottie else {
+        return Vec::new();
+    };
+    let mut lists = Vec::new();
+    for (key, value) in fields.iter_mut() {
+        match (key.as_str(), value) {
+            ("layers", Value::Array(layers)) => lists.push(layers),
+            ("assets", Value::Array(assets)) => lists.extend(
+                assets
+                    .iter_mut()
+                    .filter_map(|asset| asset.get_mut("layers"))
+                    .filter_map(Value::as_array_mut),
+            ),
+            _ => (),
+        }
+    }
+    lists
+}
+
+fn optimize_layer(layer: &mut Value) {
+    let Value::Object(fields) = layer else {
+        return;
+    };
+    for (key, default) in [
+        ("ddd", json!(0)),
+        ("ao", json!(0)),
+        ("bm", json!(0)),
+        ("sr", json!(1)),
+        ("hd", json!(false)),
+    ] {
+        if fields.get(key) == Some(&default) {
+            fields.remove(key);
+        }
+    }
+    if let Some(transform) = fields.get_mut("ks") {
+        drop_default_transform(transform);
+    }
+    let Some(Value::Array(items)) = fields.get_mut("shapes") else {
+        return;
+    };
+    // Only where drawing a shape twice looks the same as drawing it once
+    let dedupe = !items.iter().any(changes_with_duplicates);
+    optimize_items(items, dedupe);
+}
+
+/// Whether `value` paints or modifies shapes such that a shape drawn twice isn't the same as
+/// drawn once: even-odd fills cut it out, trims, merges and other modifiers treat each copy
+/// as a path of its own
+fn changes_with_duplicates(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => {
+            let even_odd = matches!(fields.get("ty").and_then(Value::as_str), Some("fl" | "gf"))
+                && fields.get("r").and_then(Value::as_i64) == Some(2);
+            let per_path = matches!(
+                fields.get("ty").and_then(Value::as_str),
+                Some("tm" | "mm" | "rp" | "op" | "zz" | "pb" | "rd" | "tw")
+            );
+            even_odd || per_path || fields.values().any(changes_with_duplicates)
+        }
+        Value::Array(values) => values.iter().any(changes_with_duplicates),
+        _ => false,
+    }
+}
+
+/// Optimize the items of one group, deduplicating shapes if `dedupe`
+fn optimize_items(items: &mut Vec<Value>, dedupe: bool) {
+    for item in items.iter_mut() {
+        match item["ty"].as_str() {
+            Some("gr") => {
+                if let Some(Value::Array(items)) = item.get_mut("it") {
+                    optimize_items(items, dedupe);
+                }
+            }
+            Some("tr") => drop_default_transform(item),
+            _ => (),
+        }
+        if let Value::Object(fields) = item {
+            if fields.get("hd") == Some(&json!(false)) {
+                fields.remove("hd");
+            }
+        }
+    }
+    items.retain(|item| !draws_nothing(item));
+    if dedupe {
+        let mut seen: Vec<Value> = Vec::new();
+        items.retain(|item| {
+            if !matches!(item["ty"].as_str(), Some("sh" | "rc" | "el" | "sr")) || !is_static(item) {
+                return true;
+            }
+            let geometry = geometry(item);
+            if seen.contains(&geometry) {
+                return false;
+            }
+            seen.push(geometry);
+            true
+        });
+    }
+}
+
+/// Whether `item` is a group with no shapes, only a transform, paints or nothing at all
+fn draws_nothing(item: &Value) -> bool {
+    if item["ty"].as_str() != Some("gr") {
+        return false;
+    }
+    let empty = Vec::new();
+    !item["it"]
+        .as_array()
+        .unwrap_or(&empty)
+        .iter()
+        .any(|item| matches!(item["ty"].as_str(), Some("sh" | "rc" | "el" | "sr" | "gr")))
+}
+
+/// `shape` without what doesn't change what it draws, such as its name
+fn geometry(shape: &Value) -> Value {
+    let mut shape = shape.clone();
+    if let Value::Object(fields) = &mut shape {
+        for key in ["nm", "mn", "ind", "cix", "ix"] {
+            fields.remove(key);
+        }
+    }
+    shape
+}
+
+fn is_static(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => {
+            fields.get("a").and_then(Value::as_i64) != Some(1) && fields.values().all(is_static)
+        }
+        Value::Array(values) => values.iter().all(is_static),
+        _ => true,
+    }
+}
+
+/// Drop the properties of a layer or group `transform` that are static and the identity
+fn drop_default_transform(transform: &mut Value) {
+    let Value::Object(fields) = transform else {
+        return;
+    };
+    fields.retain(|key, property| {
+        let Value::Object(property) = property else {
+            return true;
+        };
+        if property.get("a").and_then(Value::as_i64) == Some(1) {
+            return true;
+        }
+        let value = property.get("k");
+        let numbers: Vec<_> = match value {
+            Some(Value::Array(values)) => values.iter().map(Value::as_f64).collect(),
+            Some(value) => vec![value.as_f64()],
+            None => return true,
+        };
+        let Some(numbers) = numbers.into_iter().collect::<Option<Vec<_>>>() else {
+            return true;
+        };
+        let all = |n: f64| !numbers.is_empty() && numbers.iter().all(|v| *v == n);
+        let default = match key.as_str() {
+            "a" | "p" | "r" | "sk" | "sa" => all(0.0),
+            "s" => all(100.0),
+            "o" => all(100.0),
+            _ => false,
+        };
+        !default
+    });
+}
+
+/// Drop keyframes, anywhere in `value`, whose value holds from the keyframe before to the
+/// keyframe after, and make properties whose keyframes all have one value static
+fn drop_redundant_keyframes(value: &mut Value) {
+    match value {
+        Value::Object(fields) => {
+            if fields.get("a").and_then(Value::as_i64) == Some(1) {
+                if let Some(Value::Array(keyframes)) = fields.get_mut("k") {
+                    drop_held_keyframes(keyframes);
+                    if let Some(value) = constant_value(keyframes) {
+                        fields.insert("a".to_string(), json!(0));
+                        fields.insert("k".to_string(), value);
+                    }
+                }
+            }
+            fields.values_mut().for_each(drop_redundant_keyframes);
+        }
+        Value::Array(values) => values.iter_mut().for_each(drop_redundant_keyframes),
+        _ => (),
+    }
+}
+
+/// Whether `keyframe` moves between its value and the next along a straight line, if at all;
+/// spatial tangents curve position away and back even between equal values
+fn no_spatial_tangents(keyframe: &Value) -> bool {
+    ["ti", "to"].iter().all(|key| match &keyframe[key] {
+        Value::Null => true,
+        Value::Array(values) => values.iter().all(|v| v.as_f64() == Some(0.0)),
+        _ => false,
+    })
+}
+
+fn drop_held_keyframes(keyframes: &mut Vec<Value>) {
+    // Keyframes with an end value, from older exporters, don't go from one start to the next
+    if keyframes.iter().any(|keyframe| !keyframe["e"].is_null()) {
+        return;
+    }
+    let mut i = 1;
+    while i + 1 < keyframes.len() {
+        let (before, this, after) = (&keyframes[i - 1], &keyframes[i], &keyframes[i + 1]);
+        let held = !this["s"].is_null()
+            && before["s"] == this["s"]
+            && this["s"] == after["s"]
+            && no_spatial_tangents(before)
+            && no_spatial_tangents(this);
+        if held {
+            keyframes.remove(i);
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// The static value of a property with `keyframes`, if they all have the same one
+fn constant_value(keyframes: &[Value]) -> Option<Value> {
+    if keyframes.iter().any(|keyframe| !keyframe["e"].is_null()) {
+        return None;
+    }
+    let first = keyframes.first()?.get("s")?;
+    let constant = keyframes.iter().all(|keyframe| {
+        // The last keyframe may only mark when the one before ends
+        keyframe.get("s").map_or(true, |s| s == first) && no_spatial_tangents(keyframe)
+    });
+    if !constant {
+        return None;
+    }
+    // Keyframes hold a list even of one number or one shape, static values don't
+    Some(match first {
+        Value::Array(values) if values.len() == 1 => values[0].clone(),
+        value => value.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::PathEl;
+
+    use super::*;
+    use crate::evaluate::Evaluator;
+
+    /// A one second, 100 by 100 composition of one shape layer
+    fn lottie(transform: Value, shapes: Value) -> Value {
+        json!({
+            "fr": 60, "ip": 0, "op": 60, "w": 100, "h": 100,
+            "layers": [{
+                "ty": 4, "ind": 1, "ip": 0, "op": 60, "st": 0,
+                "ks": transform,
+                "shapes": shapes,
+            }],
+        })
+    }
+
+    fn rect(x: f64, y: f64) -> Value {
+        json!({
+            "ty": "rc",
+            "p": { "a": 0, "k": [x, y] },
+            "s": { "a": 0, "k": [20, 20] },
+            "r": { "a": 0, "k": 0 },
+        })
+    }
+
+    /// A red fill, nonzero for `rule` 1 and even-odd for 2
+    fn fill(rule: i64) -> Value {
+        json!({
+            "ty": "fl",
+            "c": { "a": 0, "k": [1, 0, 0] },
+            "o": { "a": 0, "k": 100 },
+            "r": rule,
+        })
+    }
+
+    fn group(items: Value) -> Value {
+        json!({ "ty": "gr", "it": items })
+    }
+
+    /// What `lottie` paints at each frame; a nonzero fill covers the same area however often
+    /// it repeats a subpath, so repeats are left out of those
+    fn painted(lottie: &Value) -> Vec<Vec<String>> {
+        let evaluator = Evaluator::from_json(lottie.clone()).unwrap();
+        (0..=60)
+            .map(|frame| {
+                evaluator
+                    .frame(frame as f64)
+                    .fills
+                    .iter()
+                    .map(|fill| {
+                        let mut subpaths: Vec<Vec<PathEl>> = Vec::new();
+                        for el in fill.path.elements() {
+                            if subpaths.is_empty() || matches!(el, PathEl::MoveTo(_)) {
+                                subpaths.push(Vec::new());
+                            }
+                            subpaths.last_mut().unwrap().push(*el);
+                        }
+                        if !fill.even_odd {
+                            let mut seen = Vec::new();
+                            subpaths.retain(|subpath| {
+                                let repeat = seen.contains(subpath);
+                                seen.push(subpath.clone());
+                                !repeat
+                            });
+                        }
+                        format!("{:?} {} {subpaths:?}", fill.color, fill.even_odd)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// `lottie` optimized, having checked it plays the same as before
+    fn optimized(lottie: &Value) -> Value {
+        let mut optimized = lottie.clone();
+        optimize(&mut optimized);
+        assert_eq!(painted(lottie), painted(&optimized));
+        optimized
+    }
+
+    fn shapes(lottie: &Value) -> &Value {
+        &lottie["layers"][0]["shapes"]
+    }
+
+    #[test]
+    fn drops_repeated_shapes() {
+        let lottie = lottie(
+            json!({}),
+            json!([group(json!([rect(50.0, 50.0), rect(50.0, 50.0), fill(1)]))]),
+        );
+        let optimized = optimized(&lottie);
+        let items = &shapes(&optimized)[0]["it"];
+        assert_eq!(items.as_array().unwrap().len(), 2, "{items}");
+    }
+
+    #[test]
+    fn keeps_repeated_shapes_filled_even_odd() {
+        let lottie = lottie(
+            json!({}),
+            json!([
+                group(json!([rect(50.0, 50.0), rect(50.0, 50.0), fill(2)])),
+                // Elsewhere in the layer, a nonzero fill that could be deduped alone
+                group(json!([rect(20.0, 20.0), rect(20.0, 20.0), fill(1)])),
+            ]),
+        );
+        let optimized = optimized(&lottie);
+        let shapes = shapes(&optimized);
+        for group in shapes.as_array().unwrap() {
+            assert_eq!(group["it"].as_array().unwrap().len(), 3, "{shapes}");
+        }
+    }
+
+    #[test]
+    fn keeps_repeated_shapes_trimmed_or_repeated() {
+        let trimmed = lottie(
+            json!({}),
+            json!([group(json!([
+                rect(50.0, 50.0),
+                rect(50.0, 50.0),
+                {
+                    "ty": "tm",
+                    "s": { "a": 0, "k": 0 },
+                    "e": { "a": 0, "k": 50 },
+                    "o": { "a": 0, "k": 0 },
+                },
+                fill(1),
+            ]))]),
+        );
+        // Trims can't be evaluated, so only check nothing is dropped
+        let mut optimized_trim = trimmed.clone();
+        optimize(&mut optimized_trim);
+        assert_eq!(shapes(&optimized_trim), shapes(&trimmed));
+
+        let repeated = lottie(
+            json!({}),
+            json!([group(json!([
+                rect(20.0, 50.0),
+                rect(20.0, 50.0),
+                fill(1),
+                { "ty": "rp", "c": { "a": 0, "k": 3 }, "o": { "a": 0, "k": 0 }, "tr": {
+                    "a": { "a": 0, "k": [0, 0] }, "p": { "a": 0, "k": [30, 0] },
+                    "s": { "a": 0, "k": [100, 100] }, "r": { "a": 0, "k": 0 },
+                } },
+            ]))]),
+        );
+        let optimized = optimized(&repeated);
+        let items = &shapes(&optimized)[0]["it"];
+        assert_eq!(items.as_array().unwrap().len(), 4, "{items}");
+    }
+
+    #[test]
+    fn keeps_keyframes_with_spatial_tangents() {
+        let keyframes = json!([
+            { "t": 0, "s": [50, 50], "to": [20, 0], "ti": [-20, 0] },
+            { "t": 30, "s": [50, 50], "to": [0, 20], "ti": [0, -20] },
+            { "t": 60, "s": [50, 50] },
+        ]);
+        let lottie = lottie(
+            json!({ "p": { "a": 1, "k": keyframes.clone() } }),
+            json!([group(json!([rect(0.0, 0.0), fill(1)]))]),
+        );
+        let optimized = optimized(&lottie);
+        let position = &optimized["layers"][0]["ks"]["p"];
+        assert_eq!(position, &json!({ "a": 1, "k": keyframes }));
+    }
+
+    #[test]
+    fn keeps_keyframes_with_end_values() {
+        // Older exporters give each keyframe where it ends, rotating 0 to 90 twice here
+        let keyframes = json!([
+            { "t": 0, "s": [0], "e": [90] },
+            { "t": 20, "s": [0], "e": [90] },
+            { "t": 40, "s": [0] },
+        ]);
+        let lottie = lottie(
+            json!({
+                "a": { "a": 0, "k": [50, 50] },
+                "p": { "a": 0, "k": [50, 50] },
+                "r": { "a": 1, "k": keyframes.clone() },
+            }),
+            json!([group(json!([rect(50.0, 30.0), fill(1)]))]),
+        );
+        let optimized = optimized(&lottie);
+        let rotation = &optimized["layers"][0]["ks"]["r"];
+        assert_eq!(rotation, &json!({ "a": 1, "k": keyframes }));
+    }
+
+    #[test]
+    fn drops_default_properties() {
+        let identity = json!({
+            "a": { "a": 0, "k": [0, 0] },
+            "p": { "a": 0, "k": [0, 0] },
+            "s": { "a": 0, "k": [100, 100] },
+            "r": { "a": 0, "k": 0 },
+            "o": { "a": 0, "k": 100 },
+            "sk": { "a": 0, "k": 0 },
+            "sa": { "a": 0, "k": 0 },
+        });
+        let mut transform = identity.clone();
+        transform["p"] = json!({ "a": 0, "k": [10, 10] });
+        let mut group_transform = identity;
+        group_transform["ty"] = json!("tr");
+        let mut lottie = lottie(
+            transform,
+            json!([group(json!([rect(50.0, 50.0), fill(1), group_transform]))]),
+        );
+        for (key, default) in [
+            ("ddd", json!(0)),
+            ("ao", json!(0)),
+            ("bm", json!(0)),
+            ("sr", json!(1)),
+        ] {
+            lottie["layers"][0][key] = default;
+        }
+
+        let optimized = optimized(&lottie);
+        let layer = &optimized["layers"][0];
+        for key in ["ddd", "ao", "bm", "sr"] {
+            assert!(layer.get(key).is_none(), "{layer}");
+        }
+        assert_eq!(layer["ks"], json!({ "p": { "a": 0, "k": [10, 10] } }));
+        assert_eq!(layer["shapes"][0]["it"][2], json!({ "ty": "tr" }));
+    }
+
+    #[test]
+    fn drops_groups_that_draw_nothing() {
+        let lottie = lottie(
+            json!({}),
+            json!([
+                group(json!([fill(1), { "ty": "tr" }])),
+                group(json!([group(json!([])), { "ty": "tr" }])),
+                group(json!([rect(50.0, 50.0), fill(1), { "ty": "tr" }])),
+            ]),
+        );
+        let optimized = optimized(&lottie);
+        let shapes = shapes(&optimized).as_array().unwrap();
+        assert_eq!(shapes.len(), 1, "{shapes:?}");
+        assert_eq!(shapes[0]["it"][0]["ty"], "rc");
+    }
+
+    #[test]
+    fn drops_held_keyframes() {
+        let lottie = lottie(
+            json!({ "p": { "a": 1, "k": [
+                { "t": 0, "s": [0, 0] },
+                { "t": 10, "s": [50, 50] },
+                { "t": 20, "s": [50, 50] },
+                { "t": 30, "s": [50, 50] },
+                { "t": 40, "s": [0, 0] },
+            ] } }),
+            json!([group(json!([rect(0.0, 0.0), fill(1)]))]),
+        );
+        let optimized = optimized(&lottie);
+        let position = &optimized["layers"][0]["ks"]["p"];
+        let times: Vec<_> = position["k"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|keyframe| keyframe["t"].as_i64().unwrap())
+            .collect();
+        assert_eq!(times, vec![0, 10, 30, 40]);
+    }
+
+    #[test]
+    fn makes_unchanging_properties_static() {
+        let lottie = lottie(
+            json!({
+                "a": { "a": 0, "k": [50, 50] },
+                "p": { "a": 0, "k": [50, 50] },
+                "r": { "a": 1, "k": [
+                    { "t": 0, "s": [45] },
+                    { "t": 30, "s": [45] },
+                    { "t": 60, "s": [45] },
+                ] },
+            }),
+            json!([group(json!([rect(50.0, 30.0), fill(1)]))]),
+        );
+        let optimized = optimized(&lottie);
+        let rotation = &optimized["layers"][0]["ks"]["r"];
+        assert_eq!(rotation, &json!({ "a": 0, "k": 45 }));
+    }
+}
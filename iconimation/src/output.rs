@@ -0,0 +1,126 @@
+//! Turn generated Lottie into the bytes that get written out.
+
+use bodymovin::Bodymovin as Lottie;
+use serde_json::{json, Map, Number, Value};
+
+use crate::{error::Error, optimize::optimize, pool::SharedTemplate};
+
+#[derive(Clone, Debug, Default)]
+pub struct OutputOptions {
+    /// Minified json rather than pretty printed; pretty printing can double the size of
+    /// animated shapes
+    pub compact: bool,
+    /// Round every fractional number to this many decimals
+    ///
+    /// Outlines otherwise serialize with as many as 17 significant digits, far more than
+    /// anyone can see.
+    pub precision: Option<u32>,
+    /// Route fill colors through slots so apps can re-theme them at runtime; not every
+    /// player supports slots
+    pub slots: bool,
+    /// Leave out defaults, empty groups, duplicate shapes and keyframes that change nothing,
+    /// see [crate::optimize]
+    pub optimize: bool,
+    /// The template the Lottie was made from, to restore the fields of it that bodymovin
+    /// doesn't model and so dropped
+    pub template: Option<SharedTemplate>,
+}
+
+pub fn to_json(lottie: &Lottie, options: &OutputOptions) -> Result<String, Error> {
+    let unmodeled = options
+        .template
+        .as_ref()
+        .map(SharedTemplate::unmodeled)
+        .filter(|unmodeled| !unmodeled.is_empty());
+    if options.precision.is_none() && !options.slots && !options.optimize && unmodeled.is_none() {
+        return serialize(lottie, options.compact);
+    }
+    let mut value = serde_json::to_value(lottie).map_err(Error::JsonError)?;
+    if let Some(unmodeled) = unmodeled {
+        unmodeled.restore(&mut value);
+    }
+    if let Some(precision) = options.precision {
+        round_numbers(&mut value, precision);
+    }
+    // After rounding, which can make values equal
+    if options.optimize {
+        optimize(&mut value);
+    }
+    if options.slots {
+        add_color_slots(&mut value);
+    }
+    serialize(&value, options.compact)
+}
+
+fn serialize(value: &impl serde::Serialize, compact: bool) -> Result<String, Error> {
+    if compact {
+        serde_json::to_string(value)
+    } else {
+        serde_json::to_string_pretty(value)
+    }
+    .map_err(Error::JsonError)
+}
+
+/// Round every float in `value` to `decimals` places, leaving integers alone
+///
+/// Floats that round to a whole number are written as integers, "1" rather than "1.0".
+pub fn round_numbers(value: &mut Value, decimals: u32) {
+    match value {
+        Value::Number(n) if n.is_f64() => {
+            let scale = 10f64.powi(decimals as i32);
+            let rounded = (n.as_f64().unwrap() * scale).round() / scale;
+            *n = if rounded.fract() == 0.0 && rounded.abs() < i64::MAX as f64 {
+                Number::from(rounded as i64)
+            } else {
+                // Non-finite values can't be in json in the first place
+                Number::from_f64(rounded).unwrap_or_else(|| n.clone())
+            };
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| round_numbers(v, decimals)),
+        Value::Object(fields) => fields.values_mut().for_each(|v| round_numbers(v, decimals)),
+        _ => (),
+    }
+}
+
+/// Give each distinct fill color a slot, "color0", "color1" and so on, in order of appearance
+///
+/// Fills keep their color, with a "sid" naming the slot, so players without slots still draw it.
+pub fn add_color_slots(lottie: &mut Value) {
+    let mut slots: Vec<Value> = Vec::new();
+    assign_color_slots(lottie, &mut slots);
+    if slots.is_empty() {
+        return;
+    }
+    lottie["slots"] = slots
+        .into_iter()
+        .enumerate()
+        .map(|(i, color)| (format!("color{i}"), json!({ "p": color })))
+        .collect::<Map<_, _>>()
+        .into();
+}
+
+fn assign_color_slots(value: &mut Value, slots: &mut Vec<Value>) {
+    match value {
+        Value::Object(fields) => {
+            if fields.get("ty").and_then(Value::as_str) == Some("fl") {
+                if let Some(Value::Object(color)) = fields.get_mut("c") {
+                    color.remove("sid");
+                    let color_value = Value::Object(color.clone());
+                    let i = slots
+                        .iter()
+                        .position(|c| *c == color_value)
+                        .unwrap_or_else(|| {
+                            slots.push(color_value);
+                            slots.len() - 1
+                        });
+                    color.insert("sid".to_string(), format!("color{i}").into());
+                }
+            }
+            fields
+                .values_mut()
+                .for_each(|v| assign_color_slots(v, slots));
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| assign_color_slots(v, slots)),
+        _ => (),
+    }
+}
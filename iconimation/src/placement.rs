@@ -0,0 +1,309 @@
+//! Where in a template glyphs go.
+//!
+//! A placeholder is a group, or a whole shape layer, named for the marker, "placeholder" unless
+//! told otherwise. Its first rectangle or path is the box the glyph is placed into.
+//!
+//! Templates hosting several icons, such as a before and after card, number their placeholders
+//! `placeholder:1`, `placeholder:2` and so on and [Placement::slots] binds each to a codepoint.
+//!
+//! Names may go on to give directives, space separated, such as
+//! `placeholder:2 fit=contain align=bottom pad=10% anim=pulse-whole`, so templates can set how each
+//! slot is filled and animated without flags.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use kurbo::Rect;
+
+use crate::{animate::Animation, error::Error, parse_codepoint};
+
+/// The name that marks placeholders unless [Placement::marker] says otherwise
+pub const PLACEHOLDER: &str = "placeholder";
+
+#[derive(Clone, Debug)]
+pub struct Placement {
+    /// Groups and shape layers with this name are placeholders
+    pub marker: String,
+    /// The box glyphs are drawn into, in font units, before fitting it to placeholders
+    pub drawbox: Drawbox,
+    /// Where glyphs sit vertically in the drawbox
+    pub vertical_align: VerticalAlign,
+    /// How to fit glyphs into placeholders whose names don't say
+    pub fit: FitMode,
+    /// Move glyphs so the centroid of their area, rather than the center of their bounds, is
+    /// where the center of their bounds was, as a play triangle looks centered
+    pub optical_center: bool,
+    /// How far inside placeholders whose names don't say glyphs go, leaving room for
+    /// animations that grow them
+    pub padding: Padding,
+    /// The codepoint to place in each slot, keyed by what follows the marker and a colon,
+    /// such as "2" for `placeholder:2`; placeholders not bound here get the glyph being animated
+    pub slots: BTreeMap<String, u32>,
+}
+
+/// A placeholder, as its name describes it
+#[derive(Clone, Debug, Default)]
+pub struct Placeholder {
+    /// The slot, such as "2" for `placeholder:2`, if the name has one
+    pub slot: Option<String>,
+    /// From `fit=`
+    pub fit: Option<FitMode>,
+    /// From `align=`
+    pub align: Option<Align>,
+    /// From `pad=`
+    pub padding: Option<Padding>,
+    /// From `anim=`, to animate this placeholder's glyph other than as asked
+    pub animation: Option<Animation>,
+}
+
+/// The box, in font units, glyphs are drawn into
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Drawbox {
+    /// An upem square with the baseline along its bottom, as icon fonts are drawn
+    #[default]
+    Em,
+    /// Upem wide, from the descender to the ascender in the hhea table, as text is set
+    Metrics,
+    /// The bounding box of each glyph, so small glyphs fill placeholders rather than sitting
+    /// small within the em
+    Bounds,
+}
+
+/// Where glyphs sit vertically in the drawbox
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VerticalAlign {
+    /// Wherever the drawbox puts the baseline
+    #[default]
+    Baseline,
+    /// With half the cap height, from the OS/2 table, at the middle of the drawbox so capitals
+    /// and icons drawn to cap height are centered
+    CapHeight,
+    /// With the middle of each glyph's bounding box at the middle of the drawbox
+    BboxCenter,
+}
+
+/// How the font's drawbox is scaled into a placeholder's box
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FitMode {
+    /// Scale uniformly so the drawbox fits within the box, keeping the glyph's proportions
+    /// however the box is shaped
+    #[default]
+    Contain,
+    /// Scale uniformly so the drawbox covers the box, spilling out of it along one axis
+    Cover,
+    /// Scale each axis to fill the box exactly, distorting the glyph if their shapes differ
+    Stretch,
+    /// Leave the drawbox at its size, one font unit to one Lottie unit
+    None,
+}
+
+impl FromStr for FitMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "contain" => Ok(FitMode::Contain),
+            "cover" => Ok(FitMode::Cover),
+            "stretch" => Ok(FitMode::Stretch),
+            "none" => Ok(FitMode::None),
+            _ => Err(Error::UnknownFit(s.to_string())),
+        }
+    }
+}
+
+impl FitMode {
+    /// The x and y scale taking `font_box` to `lottie_box`
+    pub(crate) fn scale(self, font_box: &Rect, lottie_box: &Rect) -> (f64, f64) {
+        let (sx, sy) = (
+            lottie_box.width() / font_box.width(),
+            lottie_box.height() / font_box.height(),
+        );
+        match self {
+            FitMode::Contain => (sx.min(sy), sx.min(sy)),
+            FitMode::Cover => (sx.max(sy), sx.max(sy)),
+            FitMode::Stretch => (sx, sy),
+            FitMode::None => (1.0, 1.0),
+        }
+    }
+}
+
+/// An inset on every side of a placeholder's box, applied before fitting the glyph to it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Padding {
+    /// In the units of the placeholder's layer
+    Units(f64),
+    /// Of the box's width at the left and right and of its height at the top and bottom
+    Percent(f64),
+}
+
+impl Default for Padding {
+    fn default() -> Self {
+        Padding::Units(0.0)
+    }
+}
+
+impl FromStr for Padding {
+    type Err = Error;
+
+    /// Parse units such as 16 or a percentage such as 10%
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || Error::BadPadding(s.to_string());
+        let s = s.trim();
+        let (number, padding): (_, fn(f64) -> Padding) = match s.strip_suffix('%') {
+            Some(percent) => (percent, Padding::Percent),
+            None => (s, Padding::Units),
+        };
+        let number: f64 = number.trim().parse().map_err(|_| bad())?;
+        if !number.is_finite() {
+            return Err(bad());
+        }
+        Ok(padding(number))
+    }
+}
+
+impl Padding {
+    /// `lottie_box` less the padding
+    pub(crate) fn inset(self, lottie_box: Rect) -> Rect {
+        let (dx, dy) = match self {
+            Padding::Units(units) => (units, units),
+            Padding::Percent(percent) => (
+                lottie_box.width() * percent / 100.0,
+                lottie_box.height() * percent / 100.0,
+            ),
+        };
+        Rect::new(
+            lottie_box.x0 + dx,
+            lottie_box.y0 + dy,
+            lottie_box.x1 - dx,
+            lottie_box.y1 - dy,
+        )
+    }
+}
+
+/// Where the fitted drawbox sits in a placeholder's box when they differ in size
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl FromStr for Align {
+    type Err = Error;
+
+    /// Parse a kebab-case position, such as "top-left"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "center" => Ok(Align::Center),
+            "top" => Ok(Align::Top),
+            "bottom" => Ok(Align::Bottom),
+            "left" => Ok(Align::Left),
+            "right" => Ok(Align::Right),
+            "top-left" => Ok(Align::TopLeft),
+            "top-right" => Ok(Align::TopRight),
+            "bottom-left" => Ok(Align::BottomLeft),
+            "bottom-right" => Ok(Align::BottomRight),
+            _ => Err(Error::UnknownAlign(s.to_string())),
+        }
+    }
+}
+
+impl Align {
+    /// How far across and down the box the drawbox sits, 0 at the left or top, 1 at the right
+    /// or bottom
+    pub(crate) fn fractions(self) -> (f64, f64) {
+        match self {
+            Align::Center => (0.5, 0.5),
+            Align::Top => (0.5, 0.0),
+            Align::Bottom => (0.5, 1.0),
+            Align::Left => (0.0, 0.5),
+            Align::Right => (1.0, 0.5),
+            Align::TopLeft => (0.0, 0.0),
+            Align::TopRight => (1.0, 0.0),
+            Align::BottomLeft => (0.0, 1.0),
+            Align::BottomRight => (1.0, 1.0),
+        }
+    }
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement {
+            marker: PLACEHOLDER.to_string(),
+            drawbox: Default::default(),
+            vertical_align: Default::default(),
+            fit: Default::default(),
+            optical_center: false,
+            padding: Default::default(),
+            slots: Default::default(),
+        }
+    }
+}
+
+impl Placement {
+    /// Whether `name` is a placeholder's, its directives unread
+    pub fn is_placeholder(&self, name: Option<&str>) -> bool {
+        name.and_then(|name| name.split_whitespace().next())
+            .and_then(|marker| self.slot(marker))
+            .is_some()
+    }
+
+    /// The placeholder `name` describes, None if it isn't a placeholder's name and an error if
+    /// it is but its directives don't parse
+    pub fn placeholder(&self, name: Option<&str>) -> Result<Option<Placeholder>, Error> {
+        let mut words = name.unwrap_or_default().split_whitespace();
+        let Some(slot) = words.next().and_then(|marker| self.slot(marker)) else {
+            return Ok(None);
+        };
+        let mut placeholder = Placeholder {
+            slot: slot.map(str::to_string),
+            ..Default::default()
+        };
+        for directive in words {
+            let bad = || Error::BadDirective(directive.to_string());
+            let (key, value) = directive.split_once('=').ok_or_else(bad)?;
+            match key {
+                "fit" => placeholder.fit = Some(value.parse()?),
+                "align" => placeholder.align = Some(value.parse()?),
+                "pad" => placeholder.padding = Some(value.parse()?),
+                "anim" => placeholder.animation = Some(value.parse()?),
+                _ => return Err(bad()),
+            }
+        }
+        Ok(Some(placeholder))
+    }
+
+    /// The slot of `marker`, the first word of a name, Some(None) if it's a placeholder without
+    /// one and None if it isn't a placeholder
+    fn slot<'n>(&self, marker: &'n str) -> Option<Option<&'n str>> {
+        let rest = marker.strip_prefix(self.marker.as_str())?;
+        if rest.is_empty() {
+            return Some(None);
+        }
+        let slot = rest.strip_prefix(':').filter(|slot| !slot.is_empty())?;
+        Some(Some(slot))
+    }
+
+    /// The codepoint bound to `placeholder`, None if it gets the glyph being animated
+    pub fn codepoint(&self, placeholder: &Placeholder) -> Option<u32> {
+        self.slots.get(placeholder.slot.as_ref()?).copied()
+    }
+}
+
+/// Parse a slot binding such as 2=0xE87D, the codepoint in any [parse_codepoint] syntax
+pub fn parse_slot(s: &str) -> Result<(String, u32), Error> {
+    let Some((slot, codepoint)) = s.split_once('=') else {
+        return Err(Error::BadSlot(s.to_string()));
+    };
+    let slot = slot.trim();
+    if slot.is_empty() {
+        return Err(Error::BadSlot(s.to_string()));
+    }
+    Ok((slot.to_string(), parse_codepoint(codepoint.trim())?))
+}
@@ -0,0 +1,107 @@
+//! Reuse parsed templates across many generations.
+//!
+//! [crate::Template::replace_shape] mutates the template it's given so every glyph needs its
+//! own copy. Reading and parsing the file each time dominates batch runs; instead parse once
+//! and hand out copies of the parsed json, which is much cheaper than going back to text.
+//!
+//! Each template also knows what bodymovin drops from it, see [crate::unmodeled], so it can be
+//! put back in the output.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use bodymovin::Bodymovin as Lottie;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    builtin::{builtin_template, BUILTIN},
+    error::{Error, TemplateLoadError},
+    unmodeled::Unmodeled,
+};
+
+/// A parsed template, cheap to clone and safe to share between threads
+#[derive(Clone, Debug)]
+pub struct SharedTemplate {
+    json: Arc<Value>,
+    unmodeled: Arc<Unmodeled>,
+}
+
+impl SharedTemplate {
+    pub fn new(lottie: &Lottie) -> Result<Self, Error> {
+        Ok(Self::from_json(
+            serde_json::to_value(lottie).map_err(Error::JsonError)?,
+        ))
+    }
+
+    pub fn from_json(json: Value) -> Self {
+        // Templates bodymovin can't parse fail to instantiate anyway
+        let unmodeled = Unmodeled::of(&json).unwrap_or_default();
+        SharedTemplate {
+            json: Arc::new(json),
+            unmodeled: Arc::new(unmodeled),
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let load_error = |source| Error::TemplateLoad {
+            path: path.display().to_string(),
+            source,
+        };
+        let text = fs::read_to_string(path).map_err(|e| load_error(TemplateLoadError::Io(e)))?;
+        Ok(Self::from_json(
+            serde_json::from_str(&text).map_err(|e| load_error(TemplateLoadError::Json(e)))?,
+        ))
+    }
+
+    /// A fresh copy of the template, ready to have shapes replaced
+    pub fn instantiate(&self) -> Result<Lottie, Error> {
+        Lottie::deserialize(self.json.as_ref()).map_err(Error::JsonError)
+    }
+
+    /// The template as parsed, before bodymovin
+    pub fn json(&self) -> &Value {
+        &self.json
+    }
+
+    /// What bodymovin drops from the template, to restore to what's made from it
+    pub fn unmodeled(&self) -> &Unmodeled {
+        &self.unmodeled
+    }
+}
+
+/// Templates by path, or [BUILTIN] name, each loaded on first use
+#[derive(Debug, Default)]
+pub struct TemplatePool {
+    templates: Mutex<HashMap<PathBuf, SharedTemplate>>,
+}
+
+impl TemplatePool {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn get(&self, path: impl AsRef<Path>) -> Result<SharedTemplate, Error> {
+        let path = path.as_ref();
+        let mut templates = self.templates.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(template) = templates.get(path) {
+            return Ok(template.clone());
+        }
+        let template = match path.to_str().and_then(|p| p.strip_prefix(BUILTIN)) {
+            Some(name) => builtin_template(name)?,
+            None => SharedTemplate::load(path)?,
+        };
+        templates.insert(path.to_path_buf(), template.clone());
+        Ok(template)
+    }
+
+    /// A fresh copy of the template at `path`, ready to have shapes replaced
+    pub fn instantiate(&self, path: impl AsRef<Path>) -> Result<Lottie, Error> {
+        self.get(path)?.instantiate()
+    }
+}
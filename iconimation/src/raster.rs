@@ -0,0 +1,426 @@
+//! Rasterize evaluated frames with tiny-skia.
+//!
+//! Besides stills, whole animations can be rendered to GIF or APNG, and with the `webp`
+//! feature, which needs libwebp, animated WebP, to preview and share without a Lottie player,
+//! or to a sprite sheet for game engines and the like.
+
+use bodymovin::Bodymovin as Lottie;
+use kurbo::{Affine, BezPath, PathEl};
+use serde_json::{json, Value};
+use tiny_skia::{
+    Color, FillRule, FilterQuality, GradientStop, LinearGradient, Mask, MaskType, Paint, Path,
+    PathBuilder, Pattern, Pixmap, PixmapPaint, RadialGradient, SpreadMode, Transform,
+};
+
+use crate::{
+    error::Error,
+    evaluate::{ColorStop, Evaluator, FilledPath, Frame, Shader},
+};
+
+/// Paint a frame into a `width` by `height` pixmap, scaled to fit and centered
+pub fn rasterize(frame: &Frame, width: u32, height: u32) -> Result<Pixmap, Error> {
+    let mut pixmap = new_pixmap(width, height)?;
+    if frame.width <= 0.0 || frame.height <= 0.0 {
+        return Ok(pixmap);
+    }
+    let scale = (width as f64 / frame.width).min(height as f64 / frame.height);
+    let transform = Transform::from_row(
+        scale as f32,
+        0.0,
+        0.0,
+        scale as f32,
+        ((width as f64 - frame.width * scale) / 2.0) as f32,
+        ((height as f64 - frame.height * scale) / 2.0) as f32,
+    );
+    paint_fills(&mut pixmap, &frame.fills, transform)?;
+    Ok(pixmap)
+}
+
+fn new_pixmap(width: u32, height: u32) -> Result<Pixmap, Error> {
+    Pixmap::new(width, height)
+        .ok_or_else(|| Error::RasterError(format!("Unable to create a {width}x{height} pixmap")))
+}
+
+/// Paint `fills`, back to front, each only where its clips show
+fn paint_fills(
+    pixmap: &mut Pixmap,
+    fills: &[FilledPath],
+    transform: Transform,
+) -> Result<(), Error> {
+    for fill in fills.iter() {
+        let Some(path) = tiny_path(&fill.path) else {
+            continue;
+        };
+        let mask = clip_mask(fill, pixmap.width(), pixmap.height(), transform)?;
+        // Lives as long as the paint that borrows it
+        let image;
+        let mut paint = Paint::default();
+        let [r, g, b, a] = fill.color.map(|c| c.clamp(0.0, 1.0) as f32);
+        paint.set_color(Color::from_rgba(r, g, b, a).unwrap_or(Color::BLACK));
+        paint.anti_alias = true;
+        match &fill.shader {
+            None => (),
+            Some(Shader::LinearGradient {
+                start,
+                end,
+                stops,
+                transform,
+            }) => {
+                // Degenerate gradients are left the solid color near them
+                if let Some(shader) = LinearGradient::new(
+                    tiny_point(*start),
+                    tiny_point(*end),
+                    tiny_stops(stops),
+                    SpreadMode::Pad,
+                    tiny_transform(*transform),
+                ) {
+                    paint.shader = shader;
+                }
+            }
+            Some(Shader::RadialGradient {
+                center,
+                focus,
+                radius,
+                stops,
+                transform,
+            }) => {
+                if let Some(shader) = RadialGradient::new(
+                    tiny_point(*focus),
+                    tiny_point(*center),
+                    *radius as f32,
+                    tiny_stops(stops),
+                    SpreadMode::Pad,
+                    tiny_transform(*transform),
+                ) {
+                    paint.shader = shader;
+                }
+            }
+            Some(Shader::Image {
+                png,
+                transform,
+                opacity,
+                ..
+            }) => {
+                image = Pixmap::decode_png(png).map_err(|e| Error::RasterError(e.to_string()))?;
+                paint.shader = Pattern::new(
+                    image.as_ref(),
+                    SpreadMode::Pad,
+                    FilterQuality::Bilinear,
+                    opacity.clamp(0.0, 1.0) as f32,
+                    tiny_transform(*transform),
+                );
+            }
+        }
+        let fill_rule = if fill.even_odd {
+            FillRule::EvenOdd
+        } else {
+            FillRule::Winding
+        };
+        pixmap.fill_path(&path, &paint, fill_rule, transform, mask.as_ref());
+    }
+    Ok(())
+}
+
+/// Where every clip of `fill` shows, None if it has none
+fn clip_mask(
+    fill: &FilledPath,
+    width: u32,
+    height: u32,
+    transform: Transform,
+) -> Result<Option<Mask>, Error> {
+    let mut combined: Option<Mask> = None;
+    for clip in fill.clips.iter() {
+        let mut covered = new_pixmap(width, height)?;
+        paint_fills(&mut covered, &clip.fills, transform)?;
+        let mask_type = if clip.luma {
+            MaskType::Luminance
+        } else {
+            MaskType::Alpha
+        };
+        let mut mask = Mask::from_pixmap(covered.as_ref(), mask_type);
+        if clip.inverted {
+            mask.invert();
+        }
+        match combined.as_mut() {
+            Some(combined) => {
+                for (shown, also) in combined.data_mut().iter_mut().zip(mask.data()) {
+                    *shown = ((*shown as u16 * *also as u16 + 127) / 255) as u8;
+                }
+            }
+            None => combined = Some(mask),
+        }
+    }
+    Ok(combined)
+}
+
+fn tiny_path(path: &BezPath) -> Option<Path> {
+    let mut builder = PathBuilder::new();
+    for el in path.elements() {
+        match *el {
+            PathEl::MoveTo(p) => builder.move_to(p.x as f32, p.y as f32),
+            PathEl::LineTo(p) => builder.line_to(p.x as f32, p.y as f32),
+            PathEl::QuadTo(c, p) => builder.quad_to(c.x as f32, c.y as f32, p.x as f32, p.y as f32),
+            PathEl::CurveTo(c0, c1, p) => builder.cubic_to(
+                c0.x as f32,
+                c0.y as f32,
+                c1.x as f32,
+                c1.y as f32,
+                p.x as f32,
+                p.y as f32,
+            ),
+            PathEl::ClosePath => builder.close(),
+        }
+    }
+    builder.finish()
+}
+
+fn tiny_point(point: kurbo::Point) -> tiny_skia::Point {
+    tiny_skia::Point::from_xy(point.x as f32, point.y as f32)
+}
+
+fn tiny_transform(affine: Affine) -> Transform {
+    let [a, b, c, d, e, f] = affine.as_coeffs().map(|c| c as f32);
+    Transform::from_row(a, b, c, d, e, f)
+}
+
+fn tiny_stops(stops: &[ColorStop]) -> Vec<GradientStop> {
+    stops
+        .iter()
+        .map(|stop| {
+            let [r, g, b, a] = stop.color.map(|c| c.clamp(0.0, 1.0) as f32);
+            GradientStop::new(
+                stop.offset as f32,
+                Color::from_rgba(r, g, b, a).unwrap_or(Color::BLACK),
+            )
+        })
+        .collect()
+}
+
+pub fn png(frame: &Frame, width: u32, height: u32) -> Result<Vec<u8>, Error> {
+    rasterize(frame, width, height)?
+        .encode_png()
+        .map_err(|e| Error::RasterError(e.to_string()))
+}
+
+/// A `size` by `size` png of `lottie` at `frame` or, if None, at [most_interesting_frame]
+pub fn thumbnail(lottie: &Lottie, frame: Option<f64>, size: u32) -> Result<Vec<u8>, Error> {
+    let evaluator = Evaluator::new(lottie)?;
+    let frame = match frame {
+        Some(frame) => frame,
+        None => most_interesting_frame(&evaluator)?,
+    };
+    png(&evaluator.frame(frame), size, size)
+}
+
+/// The frame that looks least like the first, such as the peak of a pulse
+///
+/// Renders small samples across the animation and compares pixels so it works
+/// for any animation, not just those whose keyframes we know about.
+pub fn most_interesting_frame(evaluator: &Evaluator) -> Result<f64, Error> {
+    const SAMPLES: usize = 24;
+    const SIZE: u32 = 48;
+
+    let (start, end) = (evaluator.in_point(), evaluator.out_point());
+    if end <= start {
+        return Ok(start);
+    }
+    let rest = rasterize(&evaluator.frame(start), SIZE, SIZE)?;
+    let mut best = (start, 0u64);
+    for i in 1..SAMPLES {
+        let frame = start + (end - start) * i as f64 / SAMPLES as f64;
+        let pixmap = rasterize(&evaluator.frame(frame), SIZE, SIZE)?;
+        let difference = rest
+            .data()
+            .iter()
+            .zip(pixmap.data())
+            .map(|(a, b)| a.abs_diff(*b) as u64)
+            .sum();
+        if difference > best.1 {
+            best = (frame, difference);
+        }
+    }
+    Ok(best.0)
+}
+
+/// GIF delays are in hundredths of a second and browsers slow anything faster than 50fps
+pub const MAX_GIF_FRAME_RATE: f64 = 50.0;
+
+/// `lottie` rendered `size` pixels square at `frame_rate`, or its own frame rate if None,
+/// from its in point to its out point; returns the frames and the rate they were taken at
+pub fn frames(
+    lottie: &Lottie,
+    size: u32,
+    frame_rate: Option<f64>,
+) -> Result<(Vec<Pixmap>, f64), Error> {
+    let evaluator = Evaluator::new(lottie)?;
+    let (start, end) = (evaluator.in_point(), evaluator.out_point());
+    let frame_rate = frame_rate.unwrap_or_else(|| evaluator.frame_rate());
+    if frame_rate.is_nan() || frame_rate <= 0.0 {
+        return Err(Error::RasterError(format!(
+            "Unable to render at {frame_rate}fps"
+        )));
+    }
+    // Every frame_rate'th of a second, in composition frames
+    let step = evaluator.frame_rate() / frame_rate;
+    let count = (((end - start) / step).ceil() as usize).max(1);
+    let frames = (0..count)
+        .map(|i| rasterize(&evaluator.frame(start + i as f64 * step), size, size))
+        .collect::<Result<_, _>>()?;
+    Ok((frames, frame_rate))
+}
+
+/// Straight, rather than premultiplied, RGBA
+pub(crate) fn rgba(pixmap: &Pixmap) -> Vec<u8> {
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect()
+}
+
+/// A looping GIF of `lottie`, `size` pixels square, at `frame_rate` or its own rate, at most
+/// [MAX_GIF_FRAME_RATE]
+///
+/// GIF has only on or off transparency and 256 colors a frame, so edges may be rough.
+pub fn gif(lottie: &Lottie, size: u32, frame_rate: Option<f64>) -> Result<Vec<u8>, Error> {
+    let frame_rate = frame_rate
+        .unwrap_or(lottie.frame_rate)
+        .min(MAX_GIF_FRAME_RATE);
+    let (frames, frame_rate) = frames(lottie, size, Some(frame_rate))?;
+    let too_big =
+        || Error::RasterError(format!("GIFs are at most 65535 pixels square, not {size}"));
+    let side = u16::try_from(size).map_err(|_| too_big())?;
+    let gif_error = |e: gif::EncodingError| Error::RasterError(e.to_string());
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = gif::Encoder::new(&mut bytes, side, side, &[]).map_err(gif_error)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(gif_error)?;
+        // Delays are whole hundredths, round when each frame ends rather than how long it lasts
+        // so the rounding doesn't add up, 30fps alternating 3 and 4 rather than all 3
+        let ends_at = |i: usize| (i as f64 * 100.0 / frame_rate).round() as u64;
+        for (i, pixmap) in frames.iter().enumerate() {
+            let mut frame = gif::Frame::from_rgba_speed(side, side, &mut rgba(pixmap), 10);
+            frame.delay = (ends_at(i + 1) - ends_at(i)) as u16;
+            // Clear each frame, the next may be transparent where this isn't
+            frame.dispose = gif::DisposalMethod::Background;
+            encoder.write_frame(&frame).map_err(gif_error)?;
+        }
+    }
+    Ok(bytes)
+}
+
+/// A looping animated PNG of `lottie`, `size` pixels square, at `frame_rate` or its own rate
+pub fn apng(lottie: &Lottie, size: u32, frame_rate: Option<f64>) -> Result<Vec<u8>, Error> {
+    let (frames, frame_rate) = frames(lottie, size, frame_rate)?;
+    let png_error = |e: png::EncodingError| Error::RasterError(e.to_string());
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, size, size);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        // 0 plays loops forever
+        encoder
+            .set_animated(frames.len() as u32, 0)
+            .map_err(png_error)?;
+        // Delays are fractions of a second; over hundredths keeps rates such as 29.97fps
+        encoder
+            .set_frame_delay(
+                100,
+                (frame_rate * 100.0).round().min(u16::MAX as f64) as u16,
+            )
+            .map_err(png_error)?;
+        let mut writer = encoder.write_header().map_err(png_error)?;
+        for pixmap in frames.iter() {
+            writer.write_image_data(&rgba(pixmap)).map_err(png_error)?;
+        }
+        writer.finish().map_err(png_error)?;
+    }
+    Ok(bytes)
+}
+
+/// A looping animated WebP of `lottie`, `size` pixels square, at `frame_rate` or its own rate
+#[cfg(feature = "webp")]
+pub fn webp(lottie: &Lottie, size: u32, frame_rate: Option<f64>) -> Result<Vec<u8>, Error> {
+    let (frames, frame_rate) = frames(lottie, size, frame_rate)?;
+    let webp_error = |e: webp_animation::Error| Error::RasterError(format!("{e:?}"));
+
+    let mut encoder = webp_animation::Encoder::new((size, size)).map_err(webp_error)?;
+    // Timestamps are in milliseconds
+    let millis = |frame: usize| (frame as f64 * 1000.0 / frame_rate).round() as i32;
+    for (i, pixmap) in frames.iter().enumerate() {
+        encoder
+            .add_frame(&rgba(pixmap), millis(i))
+            .map_err(webp_error)?;
+    }
+    let webp = encoder.finalize(millis(frames.len())).map_err(webp_error)?;
+    Ok(webp.to_vec())
+}
+
+/// Frames of an animation in a grid, left to right then top to bottom
+pub struct SpriteSheet {
+    pub png: Vec<u8>,
+    /// Where each frame is in the sheet and when it's shown
+    pub index: Value,
+}
+
+/// `frame_count` frames of `lottie`, evenly spaced from its in point and each `size` pixels
+/// square, packed into a grid as near square as can be
+///
+/// The index has the sheet's size, the animation's length in seconds and, for each frame, its
+/// box in the sheet and the time it's shown from, such as
+/// `{"x": 256, "y": 0, "width": 256, "height": 256, "time": 0.125}`.
+pub fn sprite_sheet(lottie: &Lottie, frame_count: usize, size: u32) -> Result<SpriteSheet, Error> {
+    if frame_count == 0 {
+        return Err(Error::RasterError(
+            "A sprite sheet needs at least one frame".to_string(),
+        ));
+    }
+    let evaluator = Evaluator::new(lottie)?;
+    let (start, end) = (evaluator.in_point(), evaluator.out_point());
+    let columns = (frame_count as f64).sqrt().ceil() as usize;
+    let rows = frame_count.div_ceil(columns);
+    let (width, height) = (columns as u32 * size, rows as u32 * size);
+    let mut sheet = Pixmap::new(width, height).ok_or_else(|| {
+        Error::RasterError(format!("Unable to create a {width}x{height} sprite sheet"))
+    })?;
+
+    let mut frames = Vec::with_capacity(frame_count);
+    for i in 0..frame_count {
+        let frame = start + (end - start).max(0.0) * i as f64 / frame_count as f64;
+        let (x, y) = ((i % columns) as u32 * size, (i / columns) as u32 * size);
+        let pixmap = rasterize(&evaluator.frame(frame), size, size)?;
+        sheet.draw_pixmap(
+            x as i32,
+            y as i32,
+            pixmap.as_ref(),
+            &PixmapPaint::default(),
+            Transform::identity(),
+            None,
+        );
+        frames.push(json!({
+            "x": x,
+            "y": y,
+            "width": size,
+            "height": size,
+            "time": (frame - start) / evaluator.frame_rate(),
+        }));
+    }
+    Ok(SpriteSheet {
+        png: sheet
+            .encode_png()
+            .map_err(|e| Error::RasterError(e.to_string()))?,
+        index: json!({
+            "width": width,
+            "height": height,
+            "duration": (end - start).max(0.0) / evaluator.frame_rate(),
+            "frames": frames,
+        }),
+    })
+}
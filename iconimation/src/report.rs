@@ -0,0 +1,356 @@
+//! Human readable summaries of what a Lottie animates and when, and how it's put together.
+//!
+//! Like [crate::evaluate], works on json so it reports on whatever a template contains,
+//! not just what bodymovin models.
+
+use bodymovin::Bodymovin as Lottie;
+use serde_json::Value;
+
+use crate::{error::Error, placement::Placement};
+
+/// One keyframe of an animated property
+#[derive(Clone, Debug)]
+pub struct KeyframeRow {
+    /// Where the property lives, such as `Icon / Group 1 / tr / s`
+    pub property: String,
+    /// In composition frames
+    pub frame: f64,
+    /// In seconds from the start of the animation
+    pub seconds: f64,
+    pub value: String,
+    /// The ease out of this keyframe, such as cubic-bezier(0.4, 0, 0.6, 1) or hold
+    pub ease: String,
+}
+
+/// Every keyframe of every animated property, in document order
+pub fn timing(lottie: &Lottie) -> Result<Vec<KeyframeRow>, Error> {
+    let json = serde_json::to_value(lottie).map_err(Error::JsonError)?;
+    let frame_rate = json["fr"].as_f64().filter(|fr| *fr > 0.0).unwrap_or(60.0);
+    let in_point = json["ip"].as_f64().unwrap_or_default();
+
+    let mut rows = Vec::new();
+    for (key, label) in [("layers", ""), ("assets", "assets")] {
+        walk(&json[key], label, &mut |property, keyframe| {
+            let frame = keyframe["t"].as_f64().unwrap_or_default();
+            rows.push(KeyframeRow {
+                property: property.to_string(),
+                frame,
+                seconds: (frame - in_point) / frame_rate,
+                value: describe_value(keyframe.get("s").unwrap_or(&Value::Null)),
+                ease: describe_ease(keyframe),
+            });
+        });
+    }
+    Ok(rows)
+}
+
+/// [timing] as a fixed-width table, one keyframe per line
+pub fn timing_table(lottie: &Lottie) -> Result<String, Error> {
+    let rows = timing(lottie)?;
+    let cells: Vec<[String; 5]> = rows
+        .into_iter()
+        .map(|row| {
+            [
+                row.property,
+                round(row.frame).to_string(),
+                format!("{:.3}", row.seconds),
+                row.value,
+                row.ease,
+            ]
+        })
+        .collect();
+
+    let header = ["property", "frame", "seconds", "value", "ease"].map(String::from);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in cells.iter() {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut table = String::new();
+    let mut push_row = |row: &[String; 5]| {
+        let line: Vec<_> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        table.push_str(line.join("  ").trim_end());
+        table.push('\n');
+    };
+    push_row(&header);
+    push_row(&widths.map(|w| "-".repeat(w)));
+    for row in cells.iter() {
+        push_row(row);
+    }
+    Ok(table)
+}
+
+/// An outline of `lottie`, json so anything a template holds shows: each layer, group and shape
+/// on a line of its own, indented under what holds it, with its timing and the properties it
+/// animates, and placeholders as `placement` finds them flagged
+pub fn structure(lottie: &Value, placement: &Placement) -> String {
+    let number = |key: &str| round(lottie[key].as_f64().unwrap_or_default());
+    let mut out = format!(
+        "{}x{}, {} fps, frames {} to {}\n",
+        number("w"),
+        number("h"),
+        number("fr"),
+        number("ip"),
+        number("op")
+    );
+    let mut placeholders = 0;
+    let empty = Vec::new();
+    out.push_str("layers\n");
+    for (i, layer) in lottie["layers"]
+        .as_array()
+        .unwrap_or(&empty)
+        .iter()
+        .enumerate()
+    {
+        placeholders += outline_layer(&mut out, 1, i, layer, placement);
+    }
+    let assets = lottie["assets"].as_array().unwrap_or(&empty);
+    if !assets.is_empty() {
+        out.push_str("assets\n");
+    }
+    for (i, asset) in assets.iter().enumerate() {
+        let id = asset["id"].as_str().unwrap_or_default();
+        let Some(layers) = asset["layers"].as_array() else {
+            out.push_str(&format!("  [{i}] {id:?} image\n"));
+            continue;
+        };
+        out.push_str(&format!("  [{i}] {id:?} precomp\n"));
+        for (i, layer) in layers.iter().enumerate() {
+            placeholders += outline_layer(&mut out, 2, i, layer, placement);
+        }
+    }
+    if placeholders == 0 {
+        out.push_str(&format!(
+            "no groups or shape layers named {:?}, nor {:?}:N\n",
+            placement.marker, placement.marker
+        ));
+    }
+    out
+}
+
+/// Outline one layer at `depth`, returning how many placeholders it holds
+fn outline_layer(
+    out: &mut String,
+    depth: usize,
+    i: usize,
+    layer: &Value,
+    placement: &Placement,
+) -> usize {
+    let name = layer["nm"].as_str();
+    let kind = match layer["ty"].as_i64() {
+        Some(0) => format!("precomp {:?}", layer["refId"].as_str().unwrap_or_default()),
+        Some(1) => "solid".to_string(),
+        Some(2) => "image".to_string(),
+        Some(3) => "null".to_string(),
+        Some(4) => "shape".to_string(),
+        Some(5) => "text".to_string(),
+        Some(ty) => format!("type {ty}"),
+        None => "untyped".to_string(),
+    };
+    let mut notes = vec![format!(
+        "frames {} to {}",
+        round(layer["ip"].as_f64().unwrap_or_default()),
+        round(layer["op"].as_f64().unwrap_or_default())
+    )];
+    if let Some(parent) = layer["parent"].as_i64() {
+        notes.push(format!("parent {parent}"));
+    }
+    if !layer["tt"].is_null() {
+        notes.push("matted".to_string());
+    }
+    if layer["td"].as_i64() == Some(1) {
+        notes.push("matte".to_string());
+    }
+    if layer["hd"].as_bool() == Some(true) {
+        notes.push("hidden".to_string());
+    }
+    let mut placeholders = 0;
+    let flag = if layer["ty"].as_i64() == Some(4) && placement.is_placeholder(name) {
+        placeholders += 1;
+        "  <- placeholder"
+    } else {
+        ""
+    };
+    out.push_str(&format!(
+        "{}[{i}] {}{kind}, {}{flag}\n",
+        "  ".repeat(depth),
+        name.map(|name| format!("{name:?} ")).unwrap_or_default(),
+        notes.join(", ")
+    ));
+    let animated = animated_properties(&layer["ks"]);
+    if !animated.is_empty() {
+        out.push_str(&format!(
+            "{}transform animates {animated}\n",
+            "  ".repeat(depth + 1)
+        ));
+    }
+    if let Some(items) = layer["shapes"].as_array() {
+        placeholders += outline_items(out, depth + 1, items, placement);
+    }
+    placeholders
+}
+
+/// Outline shape `items` at `depth`, returning how many placeholders they hold
+fn outline_items(out: &mut String, depth: usize, items: &[Value], placement: &Placement) -> usize {
+    let mut placeholders = 0;
+    for (i, item) in items.iter().enumerate() {
+        let name = item["nm"].as_str();
+        let ty = item["ty"].as_str().unwrap_or_default();
+        let kind = match ty {
+            "gr" => "group",
+            "sh" => "path",
+            "rc" => "rectangle",
+            "el" => "ellipse",
+            "sr" => "star",
+            "fl" => "fill",
+            "st" => "stroke",
+            "gf" => "gradient fill",
+            "gs" => "gradient stroke",
+            "tr" => "transform",
+            "tm" => "trim",
+            "rp" => "repeater",
+            "mm" => "merge",
+            "rd" => "round corners",
+            "op" => "offset path",
+            "pb" => "pucker",
+            "tw" => "twist",
+            "zz" => "zig zag",
+            other => other,
+        };
+        let mut line = format!(
+            "{}[{i}] {}{kind}",
+            "  ".repeat(depth),
+            name.map(|name| format!("{name:?} ")).unwrap_or_default()
+        );
+        if item["hd"].as_bool() == Some(true) {
+            line.push_str(", hidden");
+        }
+        let animated = animated_properties(item);
+        if !animated.is_empty() {
+            line.push_str(&format!(", animates {animated}"));
+        }
+        if ty == "gr" && placement.is_placeholder(name) {
+            placeholders += 1;
+            line.push_str("  <- placeholder");
+        }
+        out.push_str(&line);
+        out.push('\n');
+        if let Some(items) = item["it"].as_array() {
+            placeholders += outline_items(out, depth + 1, items, placement);
+        }
+    }
+    placeholders
+}
+
+/// The animated properties of `value`, such as `p (3 keyframes), s (2 keyframes)`
+fn animated_properties(value: &Value) -> String {
+    let Some(fields) = value.as_object() else {
+        return String::new();
+    };
+    fields
+        .iter()
+        .filter(|(_, property)| property["a"].as_i64() == Some(1))
+        .map(|(key, property)| {
+            let keyframes = property["k"].as_array().map(Vec::len).unwrap_or_default();
+            format!("{key} ({keyframes} keyframes)")
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Visit each keyframe of each animated property under `value`, labelled by where it is
+fn walk(value: &Value, label: &str, visit: &mut impl FnMut(&str, &Value)) {
+    match value {
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                let name = item
+                    .get("nm")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("{i}"));
+                walk(item, &join(label, &name), visit);
+            }
+        }
+        Value::Object(fields) => {
+            if fields.get("a").and_then(Value::as_i64) == Some(1) {
+                if let Some(keyframes) = fields.get("k").and_then(Value::as_array) {
+                    for keyframe in keyframes {
+                        visit(label, keyframe);
+                    }
+                    return;
+                }
+            }
+            for (key, field) in fields {
+                if field.is_object() || field.is_array() {
+                    walk(field, &join(label, key), visit);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+fn join(label: &str, name: &str) -> String {
+    if label.is_empty() {
+        name.to_string()
+    } else {
+        format!("{label} / {name}")
+    }
+}
+
+fn describe_value(value: &Value) -> String {
+    match value {
+        Value::Number(n) => round(n.as_f64().unwrap_or_default()).to_string(),
+        Value::Array(values) => {
+            // Shape keyframes hold a single path in an array
+            if let [Value::Object(shape)] = values.as_slice() {
+                if let Some(vertices) = shape.get("v").and_then(Value::as_array) {
+                    return format!("path ({} vertices)", vertices.len());
+                }
+            }
+            let values: Vec<_> = values.iter().map(describe_value).collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::Null => "-".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn describe_ease(keyframe: &Value) -> String {
+    if keyframe["h"].as_i64() == Some(1) {
+        return "hold".to_string();
+    }
+    // Handles may be a number or, for multi-dimensional properties, one per dimension
+    let coord = |handle: &str, axis: &str| {
+        let value = &keyframe[handle][axis];
+        value
+            .as_f64()
+            .or_else(|| value.get(0).and_then(Value::as_f64))
+    };
+    match (
+        coord("o", "x"),
+        coord("o", "y"),
+        coord("i", "x"),
+        coord("i", "y"),
+    ) {
+        (Some(x1), Some(y1), Some(x2), Some(y2)) => format!(
+            "cubic-bezier({}, {}, {}, {})",
+            round(x1),
+            round(y1),
+            round(x2),
+            round(y2)
+        ),
+        _ => "-".to_string(),
+    }
+}
+
+/// Drop float noise, 0.30000000000000004 is 0.3 to a reviewer
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
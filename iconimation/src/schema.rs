@@ -0,0 +1,34 @@
+//! Check output against the Lottie JSON schema.
+//!
+//! bodymovin can serialize structures real players reject, and the options applied on output,
+//! such as slots, add more; checking what's written against the schema catches them before a
+//! player does. The schema isn't bundled, use the one published with the Lottie specification,
+//! <https://lottie.github.io/lottie-spec/latest/lottie.schema.json>.
+
+use jsonschema::JSONSchema;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Error;
+
+/// Somewhere `lottie` breaks the schema
+#[derive(Clone, Debug, Serialize)]
+pub struct SchemaViolation {
+    /// A JSON pointer to what's wrong, such as `/layers/0/ks/o`
+    pub path: String,
+    pub message: String,
+}
+
+/// Everywhere `lottie`, as json, breaks `schema`, empty if it's valid
+pub fn schema_violations(lottie: &Value, schema: &Value) -> Result<Vec<SchemaViolation>, Error> {
+    let schema = JSONSchema::compile(schema).map_err(|e| Error::BadSchema(e.to_string()))?;
+    let Err(errors) = schema.validate(lottie) else {
+        return Ok(Vec::new());
+    };
+    Ok(errors
+        .map(|error| SchemaViolation {
+            path: error.instance_path.to_string(),
+            message: error.to_string(),
+        })
+        .collect())
+}
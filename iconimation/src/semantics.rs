@@ -0,0 +1,91 @@
+//! Pick a sensible animation from an icon's name, such as twirling anything named *refresh*.
+//!
+//! Rules are glob patterns, where `*` matches anything and `?` any one character, tried in
+//! order; the first match wins. A built-in set for Material Symbols ships with the crate.
+
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::{animate::Animation, error::Error};
+
+const BUILTIN: &str = include_str!("../resources/semantic_animations.json");
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct SemanticRule {
+    /// Such as "*refresh*"
+    pub pattern: String,
+    /// The kebab-case name of an [Animation], such as "twirl-whole"
+    pub animation: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Semantics {
+    rules: Vec<SemanticRule>,
+}
+
+impl Semantics {
+    /// The rules that ship with iconimation
+    pub fn builtin() -> Self {
+        BUILTIN.parse().expect("Built-in semantic rules must parse")
+    }
+
+    /// Try the rules of `overrides` before these
+    pub fn with_overrides(self, overrides: Semantics) -> Self {
+        Semantics {
+            rules: overrides.rules.into_iter().chain(self.rules).collect(),
+        }
+    }
+
+    /// The animation of the first rule matching `icon_name`, if any
+    pub fn animation_for(&self, icon_name: &str) -> Result<Option<Animation>, Error> {
+        self.rules
+            .iter()
+            .find(|rule| glob_match(&rule.pattern, icon_name))
+            .map(|rule| rule.animation.parse())
+            .transpose()
+    }
+}
+
+impl FromStr for Semantics {
+    type Err = Error;
+
+    /// Parse a json array of {"pattern", "animation"}, checking each animation is known
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules: Vec<SemanticRule> = serde_json::from_str(s).map_err(Error::JsonError)?;
+        for rule in rules.iter() {
+            Animation::from_str(&rule.animation)?;
+        }
+        Ok(Semantics { rules })
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` is any run of characters and `?` any one
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // where to resume if the most recent * should have consumed more
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, consumed)) => {
+                    p = star + 1;
+                    n = consumed + 1;
+                    backtrack = Some((star, consumed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
@@ -4,11 +4,14 @@
 
 use bodymovin::{
     properties::{Property, ShapeValue, Value},
-    shapes::SubPath,
+    shapes::{AnyShape, SubPath},
 };
 use kurbo::{BezPath, Point, Shape as KShape};
+use serde_json::json;
 use skrifa::outline::OutlinePen;
 
+use crate::error::Error;
+
 #[derive(Default)]
 pub struct SubPathPen {
     paths: Vec<BezPath>,
@@ -90,6 +93,13 @@ fn add_cubic(shape: &mut ShapeValue, c0: Point, c1: Point, end: Point) {
     //      End:            vertices[i + 1]
     // If closed 1 past the end of vertices is vertices[0]
 
+    // A path that doesn't start with a move implicitly starts at the origin
+    if shape.vertices.is_empty() {
+        shape.vertices.push(Point::ZERO.into());
+        shape.out_point.push(Point::ZERO.into());
+        shape.in_point.push(Point::ZERO.into());
+    }
+
     let start: Point = shape
         .vertices
         .last()
@@ -105,9 +115,7 @@ fn add_cubic(shape: &mut ShapeValue, c0: Point, c1: Point, end: Point) {
     shape.vertices.push(end.into());
 }
 
-fn bez_to_shape(path: &BezPath) -> SubPath {
-    eprintln!("bez to shape, cbox {:?}", path.control_box());
-
+fn bez_to_shape_value(path: &BezPath) -> ShapeValue {
     let mut value = ShapeValue::default();
     for el in path.iter() {
         let last_on: Point = value.vertices.last().cloned().unwrap_or_default().into();
@@ -134,18 +142,71 @@ fn bez_to_shape(path: &BezPath) -> SubPath {
                 == value.vertices.last().cloned().unwrap_or_default(),
         );
     }
+    value
+}
+
+fn direction(path: &BezPath) -> f64 {
+    // 1.0 = Clockwise = positive area
+    // 3.0 = Counter-Clockwise = negative area
+    if path.area() > 0.0 {
+        1.0
+    } else {
+        3.0
+    }
+}
+
+fn bez_to_shape(path: &BezPath) -> SubPath {
     SubPath {
         vertices: Property {
-            value: Value::Fixed(value),
+            value: Value::Fixed(bez_to_shape_value(path)),
             ..Default::default()
         },
-        // 1.0 = Clockwise = positive area
-        // 3.0 = Counter-Clockwise = negative area
-        direction: if path.area() > 0.0 {
-            Some(1.0)
-        } else {
-            Some(3.0)
-        },
+        direction: Some(direction(path)),
         ..Default::default()
     }
 }
+
+/// A [SubPath] whose vertices are keyframed to pass through each `(time, path)`
+///
+/// The paths must be point compatible, as is the case when drawing one glyph at
+/// several locations in a variable font.
+pub fn animated_subpath(keyframes: &[(f64, &BezPath)]) -> Result<SubPath, Error> {
+    let Some((_, first)) = keyframes.first() else {
+        return Err(Error::IncompatibleOutlines);
+    };
+    let values: Vec<_> = keyframes
+        .iter()
+        .map(|(_, path)| bez_to_shape_value(path))
+        .collect();
+    if values
+        .iter()
+        .any(|v| v.vertices.len() != values[0].vertices.len() || v.closed != values[0].closed)
+    {
+        return Err(Error::IncompatibleOutlines);
+    }
+
+    // Build json and let bodymovin parse it, animated shape values are rare enough
+    // that spelling out the keyframe structs isn't worth it
+    let keyframes = keyframes
+        .iter()
+        .zip(values)
+        .map(|((time, _), value)| {
+            Ok(json!({
+                "t": time,
+                "s": [serde_json::to_value(value).map_err(Error::JsonError)?],
+                // linear, samples are close enough together that easing between them isn't noticeable
+                "o": { "x": 0.0, "y": 0.0 },
+                "i": { "x": 1.0, "y": 1.0 },
+            }))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+    let shape = json!({
+        "ty": "sh",
+        "ks": { "a": 1, "k": keyframes },
+        "d": direction(first),
+    });
+    match serde_json::from_value(shape).map_err(Error::JsonError)? {
+        AnyShape::Shape(subpath) => Ok(subpath),
+        _ => unreachable!("We built a shape"),
+    }
+}
@@ -0,0 +1,159 @@
+//! Where generated files go: local files, zip archives, or (feature object_store) cloud buckets.
+
+use std::{
+    fs,
+    io::{Seek, Write},
+    path::PathBuf,
+};
+
+use zip::{write::FileOptions, ZipWriter};
+
+use crate::error::Error;
+
+pub trait Sink {
+    /// Write `bytes` as `name`, a relative path such as "alarm-pulse-parts.json"
+    fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), Error>;
+
+    /// Complete output; some sinks, such as zip archives, aren't usable until finished
+    fn finish(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Files under a directory on the local filesystem
+pub struct FsSink {
+    root: PathBuf,
+}
+
+impl FsSink {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FsSink { root: root.into() }
+    }
+}
+
+impl Sink for FsSink {
+    fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), Error> {
+        let path = self.root.join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(Error::IoError)?;
+        }
+        fs::write(path, bytes).map_err(Error::IoError)
+    }
+}
+
+/// Everything to one writer, such as stdout, one after another; names are ignored
+pub struct WriterSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> WriterSink<W> {
+    pub fn new(writer: W) -> Self {
+        WriterSink { writer }
+    }
+}
+
+impl<W: Write> Sink for WriterSink<W> {
+    fn write(&mut self, _name: &str, bytes: &[u8]) -> Result<(), Error> {
+        self.writer.write_all(bytes).map_err(Error::IoError)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        self.writer.flush().map_err(Error::IoError)
+    }
+}
+
+/// Entries in a zip archive
+pub struct ZipSink<W: Write + Seek> {
+    zip: ZipWriter<W>,
+    finished: Option<W>,
+}
+
+impl<W: Write + Seek> ZipSink<W> {
+    pub fn new(writer: W) -> Self {
+        ZipSink {
+            zip: ZipWriter::new(writer),
+            finished: None,
+        }
+    }
+
+    /// Finish, if that hasn't happened already, and return the underlying writer
+    pub fn into_inner(mut self) -> Result<W, Error> {
+        self.finish()?;
+        Ok(self.finished.take().expect("finish sets finished"))
+    }
+}
+
+impl<W: Write + Seek> Sink for ZipSink<W> {
+    fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), Error> {
+        self.zip
+            .start_file(name, FileOptions::default())
+            .map_err(Error::ZipError)?;
+        self.zip.write_all(bytes).map_err(Error::IoError)
+    }
+
+    fn finish(&mut self) -> Result<(), Error> {
+        if self.finished.is_none() {
+            self.finished = Some(self.zip.finish().map_err(Error::ZipError)?);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "object_store")]
+pub use cloud::ObjectStoreSink;
+
+#[cfg(feature = "object_store")]
+mod cloud {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use object_store::{path::Path, ObjectStore};
+    use tokio::runtime::Runtime;
+    use url::Url;
+
+    use super::Sink;
+    use crate::error::Error;
+
+    /// Objects under a prefix in a bucket, such as S3 or GCS, written synchronously
+    pub struct ObjectStoreSink {
+        store: Arc<dyn ObjectStore>,
+        prefix: Path,
+        runtime: Runtime,
+    }
+
+    impl ObjectStoreSink {
+        pub fn new(store: Arc<dyn ObjectStore>, prefix: &str) -> Result<Self, Error> {
+            Ok(ObjectStoreSink {
+                store,
+                prefix: Path::from(prefix),
+                runtime: Runtime::new().map_err(Error::IoError)?,
+            })
+        }
+
+        /// From a url such as s3://bucket/icons or gs://bucket/icons
+        ///
+        /// Credentials and region come from the usual environment variables,
+        /// e.g. AWS_ACCESS_KEY_ID or GOOGLE_SERVICE_ACCOUNT.
+        pub fn from_url(url: &str) -> Result<Self, Error> {
+            let url = Url::parse(url).map_err(|e| Error::BadUrl(format!("{url}: {e}")))?;
+            let options = std::env::vars().map(|(k, v)| (k.to_ascii_lowercase(), v));
+            let (store, prefix) =
+                object_store::parse_url_opts(&url, options).map_err(Error::ObjectStoreError)?;
+            Self::new(Arc::from(store), prefix.as_ref())
+        }
+    }
+
+    impl Sink for ObjectStoreSink {
+        fn write(&mut self, name: &str, bytes: &[u8]) -> Result<(), Error> {
+            let path = if self.prefix.as_ref().is_empty() {
+                Path::from(name)
+            } else {
+                Path::from(format!("{}/{name}", self.prefix))
+            };
+            self.runtime
+                .block_on(self.store.put(&path, Bytes::copy_from_slice(bytes)))
+                .map_err(Error::ObjectStoreError)?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,781 @@
+//! How generated shapes look: their fill, solid, a color per part or a gradient, an optional
+//! stroke, perhaps dashed, a drop shadow, a burst of ticks and which CPAL palettes color glyphs
+//! use.
+//!
+//! Animators fill each group they emit with [FillStyle::default]; once animated the shapes are
+//! restyled as requested, so animators needn't know about styling.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use bodymovin::{
+    layers::Shape as ShapeLayer,
+    properties::{MultiDimensionalKeyframe, Property, Value},
+    shapes::{AnyShape, Fill},
+};
+use kurbo::{Point, Rect, Vec2};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    animate::default_ease,
+    color::{gradient_fill, GradientStop},
+    effects::{add_layer_effect, drop_shadow},
+    error::Error,
+};
+
+/// Everything about how the glyph is drawn that isn't its outline or animation
+#[derive(Clone, Debug, Default)]
+pub struct Style {
+    pub fill: FillStyle,
+    /// Stroke the outline, in the fill's color, as well as or instead of filling it
+    pub stroke: Option<StrokeStyle>,
+    /// The CPAL palette color glyphs are colored from
+    pub palette: u16,
+    /// If set, color glyphs shift from [Style::palette] to this palette as they animate
+    pub palette_to: Option<u16>,
+    pub shadow: Option<ShadowStyle>,
+    pub burst: Option<BurstStyle>,
+}
+
+impl Style {
+    /// Restyle shapes from an [crate::animate::Animator], the nth top level group being the nth part
+    ///
+    /// Gradients span `bounds`, the glyph's box in the units of the shapes, and anything
+    /// animated by the style itself runs from `start` to `end`.
+    pub fn apply(
+        &self,
+        shapes: &mut [AnyShape],
+        bounds: Rect,
+        start: f64,
+        end: f64,
+    ) -> Result<(), Error> {
+        for (i, shape) in shapes.iter_mut().enumerate() {
+            self.apply_to_part(shape, i, bounds, start, end)?;
+        }
+        Ok(())
+    }
+
+    /// Whether to paint with a template placeholder's own fills and strokes, if it has any,
+    /// rather than the fills animators add
+    ///
+    /// Only when no fill color was chosen, [FillColor::ByPart] being the default.
+    pub fn inherits_placeholder_paint(&self) -> bool {
+        matches!(self.fill.color, FillColor::ByPart)
+    }
+
+    /// Add what goes around fully styled shapes: a shadow, if the style has one drawn with
+    /// shapes, and a burst
+    ///
+    /// `bounds`, `start` and `end` are as for [Style::apply].
+    pub fn add_accents(
+        &self,
+        shapes: &mut Vec<AnyShape>,
+        bounds: Rect,
+        start: f64,
+        end: f64,
+    ) -> Result<(), Error> {
+        // Earlier items draw on top, accents go beneath
+        if let Some(shadow) = self.shadow.as_ref().filter(|s| !s.effect) {
+            let shadow = shadow.shapes(shapes)?;
+            shapes.push(shadow);
+        }
+        if let Some(burst) = &self.burst {
+            shapes.push(burst.shapes(bounds, start, end)?);
+        }
+        Ok(())
+    }
+
+    /// Style the layer the glyph was placed in, adding effects such as a drop shadow
+    pub fn apply_to_layer(&self, layer: &mut ShapeLayer) -> Result<(), Error> {
+        if let Some(shadow) = self.shadow.as_ref().filter(|s| s.effect) {
+            add_layer_effect(layer, drop_shadow(shadow))?;
+        }
+        Ok(())
+    }
+
+    fn apply_to_part(
+        &self,
+        shape: &mut AnyShape,
+        part: usize,
+        bounds: Rect,
+        start: f64,
+        end: f64,
+    ) -> Result<(), Error> {
+        match shape {
+            AnyShape::Group(group) => {
+                for item in group.items.iter_mut() {
+                    self.apply_to_part(item, part, bounds, start, end)?;
+                }
+                let Some(stroke) = &self.stroke else {
+                    return Ok(());
+                };
+                let Some(at) = group.items.iter().position(|item| {
+                    matches!(item, AnyShape::Fill(..) | AnyShape::GradientFill(..))
+                }) else {
+                    return Ok(());
+                };
+                // A gradient stroke isn't supported, stroke in the gradient's first color
+                let color = match &group.items[at] {
+                    AnyShape::Fill(fill) => serde_json::to_value(&fill.color),
+                    _ => serde_json::to_value(&self.fill.fill(part).color),
+                }
+                .map_err(Error::JsonError)?;
+                let stroke_shape = stroke.stroke(color, self.fill.opacity, start, end)?;
+                // Earlier items draw on top, put the stroke over the fill
+                if stroke.replace_fill {
+                    group.items[at] = stroke_shape;
+                } else {
+                    group.items.insert(at, stroke_shape);
+                }
+            }
+            AnyShape::Fill(fill) => match &self.fill.color {
+                FillColor::Gradient(gradient) => {
+                    *shape = gradient.fill(bounds, self.fill.opacity)?;
+                }
+                _ => self.fill.restyle(fill, part),
+            },
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+/// Whether `shape` paints the shapes before it, as fills and strokes do
+pub(crate) fn is_paint(shape: &AnyShape) -> bool {
+    matches!(
+        shape,
+        AnyShape::Fill(..)
+            | AnyShape::Stroke(..)
+            | AnyShape::GradientFill(..)
+            | AnyShape::GradientStroke(..)
+    )
+}
+
+/// Drop the fills animators added so paint further out, such as a placeholder's, shows
+pub(crate) fn remove_fills(shapes: &mut Vec<AnyShape>) {
+    shapes.retain(|shape| !matches!(shape, AnyShape::Fill(..)));
+    for shape in shapes.iter_mut() {
+        if let AnyShape::Group(group) = shape {
+            remove_fills(&mut group.items);
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StrokeStyle {
+    pub width: f64,
+    /// If set, the width swells to this halfway through the animation and back
+    pub width_to: Option<f64>,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// Only stroke, for outline style icons, rather than stroking over the fill
+    pub replace_fill: bool,
+    pub dash: Option<DashStyle>,
+}
+
+/// A dashed stroke whose dashes march along the outline as it animates
+#[derive(Clone, Debug)]
+pub struct DashStyle {
+    pub dash: f64,
+    pub gap: f64,
+    /// How many dash and gap lengths the dashes move over the animation; whole numbers loop
+    /// seamlessly, 0 holds still
+    pub cycles: f64,
+}
+
+impl DashStyle {
+    /// The stroke's "d" property, dash, gap and offset, the offset keyframed linearly
+    fn to_json(&self, start: f64, end: f64) -> serde_json::Value {
+        let offset = if self.cycles == 0.0 {
+            json!({ "a": 0, "k": 0 })
+        } else {
+            json!({
+                "a": 1,
+                "k": [
+                    {
+                        "t": start,
+                        "s": [0],
+                        "o": { "x": [0], "y": [0] },
+                        "i": { "x": [1], "y": [1] },
+                    },
+                    { "t": end, "s": [-self.cycles * (self.dash + self.gap)] },
+                ],
+            })
+        };
+        json!([
+            { "n": "d", "nm": "dash", "v": { "a": 0, "k": self.dash } },
+            { "n": "g", "nm": "gap", "v": { "a": 0, "k": self.gap } },
+            { "n": "o", "nm": "offset", "v": offset },
+        ])
+    }
+}
+
+impl FromStr for DashStyle {
+    type Err = Error;
+
+    /// Parse dash and gap lengths, such as "4,2", moving one cycle
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || Error::BadDash(s.to_string());
+        let (dash, gap) = s.split_once(',').ok_or_else(bad)?;
+        let dash: f64 = dash.trim().parse().map_err(|_| bad())?;
+        let gap: f64 = gap.trim().parse().map_err(|_| bad())?;
+        if dash < 0.0 || gap < 0.0 || dash + gap <= 0.0 {
+            return Err(bad());
+        }
+        Ok(DashStyle {
+            dash,
+            gap,
+            cycles: 1.0,
+        })
+    }
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        StrokeStyle {
+            width: 2.0,
+            width_to: None,
+            cap: LineCap::Round,
+            join: LineJoin::Round,
+            replace_fill: false,
+            dash: None,
+        }
+    }
+}
+
+impl StrokeStyle {
+    /// A Lottie stroke, `color` being the json of a color property
+    fn stroke(
+        &self,
+        color: serde_json::Value,
+        opacity: f64,
+        start: f64,
+        end: f64,
+    ) -> Result<AnyShape, Error> {
+        let width = match self.width_to {
+            None => json!({ "a": 0, "k": self.width }),
+            Some(width_to) => {
+                let keyframe = |start_time, width| MultiDimensionalKeyframe {
+                    start_time,
+                    start_value: Some(vec![width]),
+                    bezier: Some(default_ease()),
+                    ..Default::default()
+                };
+                let keyframes = vec![
+                    keyframe(start, self.width),
+                    keyframe(start + 0.5 * (end - start), width_to),
+                    keyframe(end, self.width),
+                ];
+                json!({
+                    "a": 1,
+                    "k": serde_json::to_value(keyframes).map_err(Error::JsonError)?,
+                })
+            }
+        };
+        let mut stroke = json!({
+            "ty": "st",
+            "c": color,
+            "o": { "a": 0, "k": opacity },
+            "w": width,
+            "lc": self.cap as u8,
+            "lj": self.join as u8,
+            "ml": 4,
+        });
+        if let Some(dash) = &self.dash {
+            stroke["d"] = dash.to_json(start, end);
+        }
+        AnyShape::deserialize(stroke).map_err(Error::JsonError)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShadowStyle {
+    /// How far the shadow falls from the glyph, in the units of the template, y down
+    pub offset: Vec2,
+    /// How far the shadow's edge spreads
+    pub softness: f64,
+    /// 0 to 100
+    pub opacity: f64,
+    pub color: Rgb,
+    /// A drop shadow layer effect, blurred by players that draw effects, rather than an offset
+    /// copy of the glyph's shapes; the copy can't blur, softness widens it with a fainter edge
+    pub effect: bool,
+}
+
+impl Default for ShadowStyle {
+    fn default() -> Self {
+        ShadowStyle {
+            offset: Vec2::new(2.0, 2.0),
+            softness: 4.0,
+            opacity: 50.0,
+            color: Rgb([0.0, 0.0, 0.0]),
+            effect: false,
+        }
+    }
+}
+
+/// Ticks that fly out from the center and fade as the glyph enters, for a bit of celebration
+///
+/// One tick is drawn, a repeater copies it around the circle.
+#[derive(Clone, Debug)]
+pub struct BurstStyle {
+    pub ticks: u32,
+    pub color: Rgb,
+}
+
+impl Default for BurstStyle {
+    fn default() -> Self {
+        BurstStyle {
+            ticks: 8,
+            color: nth_part_color(0),
+        }
+    }
+}
+
+impl BurstStyle {
+    /// The burst, centered in `bounds`, playing over the first half of `start` to `end`
+    fn shapes(&self, bounds: Rect, start: f64, end: f64) -> Result<AnyShape, Error> {
+        let center = bounds.center();
+        let radius = 0.5 * bounds.width().min(bounds.height());
+        // Fly from 70% of the way out to the edge
+        let (from, to) = (0.7 * radius, radius);
+        let (length, width) = (0.12 * radius, 0.03 * radius);
+        let [r, g, b] = self.color.0;
+        let keyframes = |from: Vec<f64>, to: Vec<f64>| {
+            let keyframe = |start_time, value| MultiDimensionalKeyframe {
+                start_time,
+                start_value: Some(value),
+                bezier: Some(default_ease()),
+                ..Default::default()
+            };
+            serde_json::to_value(vec![
+                keyframe(start, from),
+                keyframe(start + 0.5 * (end - start), to),
+            ])
+            .map_err(Error::JsonError)
+        };
+        // Pointing up from the center, moving up; the repeater rotates copies about the center
+        let tick = json!({
+            "ty": "gr",
+            "nm": "tick",
+            "it": [
+                {
+                    "ty": "rc",
+                    "p": { "a": 0, "k": [center.x, center.y - from - 0.5 * length] },
+                    "s": { "a": 0, "k": [width, length] },
+                    "r": { "a": 0, "k": 0.5 * width },
+                },
+                {
+                    "ty": "fl",
+                    "c": { "a": 0, "k": [r, g, b] },
+                    "o": { "a": 0, "k": 100 },
+                    "r": 1,
+                },
+                {
+                    "ty": "tr",
+                    "a": { "a": 0, "k": [0, 0] },
+                    "p": { "a": 1, "k": keyframes(vec![0.0, 0.0], vec![0.0, from - to])? },
+                    "s": { "a": 0, "k": [100, 100] },
+                    "r": { "a": 0, "k": 0 },
+                    "o": { "a": 1, "k": keyframes(vec![100.0], vec![0.0])? },
+                },
+            ],
+        });
+        let repeater = json!({
+            "ty": "rp",
+            "c": { "a": 0, "k": self.ticks },
+            "o": { "a": 0, "k": 0 },
+            "m": 1,
+            "tr": {
+                "ty": "tr",
+                // Rotate each copy about the center; position would move each copy further by
+                // as much again
+                "a": { "a": 0, "k": [center.x, center.y] },
+                "p": { "a": 0, "k": [0, 0] },
+                "s": { "a": 0, "k": [100, 100] },
+                "r": { "a": 0, "k": 360.0 / self.ticks.max(1) as f64 },
+                "so": { "a": 0, "k": 100 },
+                "eo": { "a": 0, "k": 100 },
+            },
+        });
+        AnyShape::deserialize(json!({
+            "ty": "gr",
+            "nm": "burst",
+            "it": [tick, repeater, {
+                "ty": "tr",
+                "a": { "a": 0, "k": [0, 0] },
+                "p": { "a": 0, "k": [0, 0] },
+                "s": { "a": 0, "k": [100, 100] },
+                "r": { "a": 0, "k": 0 },
+                "o": { "a": 0, "k": 100 },
+            }],
+        }))
+        .map_err(Error::JsonError)
+    }
+}
+
+impl FromStr for ShadowStyle {
+    type Err = Error;
+
+    /// Parse the offset, such as "2,3", taking defaults for the rest
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || Error::BadShadow(s.to_string());
+        let (x, y) = s.split_once(',').ok_or_else(bad)?;
+        Ok(ShadowStyle {
+            offset: Vec2::new(
+                x.trim().parse().map_err(|_| bad())?,
+                y.trim().parse().map_err(|_| bad())?,
+            ),
+            ..Default::default()
+        })
+    }
+}
+
+impl ShadowStyle {
+    /// An offset copy of `shapes`, animation and all, filled in the shadow color
+    fn shapes(&self, shapes: &[AnyShape]) -> Result<AnyShape, Error> {
+        let mut items = serde_json::to_value(shapes).map_err(Error::JsonError)?;
+        self.recolor(&mut items);
+        let items = items.as_array_mut().unwrap();
+        let [r, g, b] = self.color.0;
+        if self.softness > 0.0 {
+            items.insert(
+                0,
+                json!({
+                    "ty": "st",
+                    "c": { "a": 0, "k": [r, g, b] },
+                    "o": { "a": 0, "k": 0.5 * self.opacity },
+                    "w": { "a": 0, "k": self.softness },
+                    "lc": LineCap::Round as u8,
+                    "lj": LineJoin::Round as u8,
+                }),
+            );
+        }
+        items.push(json!({
+            "ty": "tr",
+            "a": { "a": 0, "k": [0, 0] },
+            "p": { "a": 0, "k": [self.offset.x, self.offset.y] },
+            "s": { "a": 0, "k": [100, 100] },
+            "r": { "a": 0, "k": 0 },
+            "o": { "a": 0, "k": 100 },
+        }));
+        AnyShape::deserialize(json!({
+            "ty": "gr",
+            "nm": "shadow",
+            "it": items,
+        }))
+        .map_err(Error::JsonError)
+    }
+
+    /// Turn every fill and stroke, solid or gradient, the shadow color
+    fn recolor(&self, value: &mut serde_json::Value) {
+        let [r, g, b] = self.color.0;
+        match value {
+            serde_json::Value::Array(values) => values.iter_mut().for_each(|v| self.recolor(v)),
+            serde_json::Value::Object(fields) => {
+                let ty = fields
+                    .get("ty")
+                    .and_then(|ty| ty.as_str())
+                    .map(str::to_string);
+                match ty.as_deref() {
+                    Some("fl" | "gf") => {
+                        *value = json!({
+                            "ty": "fl",
+                            "c": { "a": 0, "k": [r, g, b] },
+                            "o": { "a": 0, "k": self.opacity },
+                            "r": 1,
+                        });
+                    }
+                    Some("st") => {
+                        fields.insert("c".to_string(), json!({ "a": 0, "k": [r, g, b] }));
+                        fields.insert("o".to_string(), json!({ "a": 0, "k": self.opacity }));
+                    }
+                    _ => fields.values_mut().for_each(|v| self.recolor(v)),
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// How the ends of open lines look, numbered as Lottie has them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineCap {
+    Butt = 1,
+    Round = 2,
+    Square = 3,
+}
+
+/// How corners look, numbered as Lottie has them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter = 1,
+    Round = 2,
+    Bevel = 3,
+}
+
+#[derive(Clone, Debug)]
+pub struct FillStyle {
+    pub color: FillColor,
+    /// 0 to 100
+    pub opacity: f64,
+}
+
+impl Default for FillStyle {
+    fn default() -> Self {
+        FillStyle {
+            color: FillColor::ByPart,
+            opacity: 100.0,
+        }
+    }
+}
+
+impl FillStyle {
+    /// Give an animator's fill of the nth part this style; [FillColor::ByPart] keeps its color
+    pub fn restyle(&self, fill: &mut Fill, part: usize) {
+        let color = fill.color.clone();
+        *fill = self.fill(part);
+        if let FillColor::ByPart = self.color {
+            fill.color = color;
+        }
+    }
+
+    /// The fill for the nth part
+    ///
+    /// A gradient can't be a [Fill], its first color is used instead; [Style::apply] makes
+    /// gradient fills.
+    pub fn fill(&self, part: usize) -> Fill {
+        let Rgb([r, g, b]) = match &self.color {
+            FillColor::ByPart => nth_part_color(part),
+            FillColor::Solid(rgb) => *rgb,
+            FillColor::Palette(palette) => palette
+                .color_for(part)
+                .unwrap_or_else(|| nth_part_color(part)),
+            FillColor::Gradient(gradient) => gradient
+                .stops
+                .first()
+                .map(|stop| stop.color)
+                .unwrap_or_else(|| nth_part_color(part)),
+        };
+        Fill {
+            opacity: Property {
+                value: Value::Fixed(self.opacity), // default of 0 is not helpful
+                ..Default::default()
+            },
+            color: Property {
+                value: Value::Fixed(vec![r, g, b]),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum FillColor {
+    /// A different color for each part, handy to see how the icon was split up
+    ByPart,
+    Solid(Rgb),
+    /// Colors chosen per part, see [PartPalette]
+    Palette(PartPalette),
+    /// One gradient across the whole glyph
+    Gradient(Gradient),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GradientKind {
+    /// Top to bottom
+    Linear,
+    /// Out from the center to the edges
+    Radial,
+}
+
+#[derive(Clone, Debug)]
+pub struct Gradient {
+    pub kind: GradientKind,
+    pub stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    /// A Lottie gradient fill spanning `bounds`
+    fn fill(&self, bounds: Rect, opacity: f64) -> Result<AnyShape, Error> {
+        let (start, end, gradient_type) = match self.kind {
+            GradientKind::Linear => (
+                Point::new(bounds.center().x, bounds.min_y()),
+                Point::new(bounds.center().x, bounds.max_y()),
+                1,
+            ),
+            GradientKind::Radial => (
+                bounds.center(),
+                bounds.center() + Vec2::new(0.5 * bounds.width().max(bounds.height()), 0.0),
+                2,
+            ),
+        };
+        gradient_fill(start, end, &self.stops, gradient_type, opacity)
+    }
+}
+
+impl FromStr for Gradient {
+    type Err = Error;
+
+    /// Parse comma separated colors, optionally with an offset from 0 to 1 and prefixed by the
+    /// kind, linear if absent: "#1A73E8,#A142F4" or "radial:#1A73E8@0.2,#A142F4"
+    ///
+    /// Stops without an offset are spaced evenly.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || Error::BadColor(s.to_string());
+        let (kind, stops) = match s.trim().split_once(':') {
+            Some(("linear", stops)) => (GradientKind::Linear, stops),
+            Some(("radial", stops)) => (GradientKind::Radial, stops),
+            Some(..) => return Err(bad()),
+            None => (GradientKind::Linear, s),
+        };
+        let stops: Vec<_> = stops
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .collect();
+        if stops.len() < 2 {
+            return Err(bad());
+        }
+        let stops = stops
+            .iter()
+            .enumerate()
+            .map(|(i, stop)| {
+                let (color, offset) = match stop.split_once('@') {
+                    Some((color, offset)) => {
+                        let offset: f64 = offset.trim().parse().map_err(|_| bad())?;
+                        if !(0.0..=1.0).contains(&offset) {
+                            return Err(bad());
+                        }
+                        (color, offset)
+                    }
+                    None => (*stop, i as f64 / (stops.len() - 1) as f64),
+                };
+                Ok(GradientStop {
+                    offset,
+                    color: color.parse()?,
+                    alpha: 1.0,
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Gradient { kind, stops })
+    }
+}
+
+/// Colors for parts, cycled through in order or assigned to specific parts
+///
+/// Parts in neither get the color [FillColor::ByPart] would.
+#[derive(Clone, Debug, Default)]
+pub struct PartPalette {
+    /// Part n gets colors[n % len] unless it's in by_part
+    pub colors: Vec<Rgb>,
+    pub by_part: BTreeMap<usize, Rgb>,
+}
+
+impl PartPalette {
+    pub fn color_for(&self, part: usize) -> Option<Rgb> {
+        self.by_part
+            .get(&part)
+            .copied()
+            .or_else(|| (!self.colors.is_empty()).then(|| self.colors[part % self.colors.len()]))
+    }
+}
+
+impl FromStr for PartPalette {
+    type Err = Error;
+
+    /// Parse comma separated colors, such as "#EF5350,#AB47BC" to cycle through, and
+    /// part=color assignments, such as "0=#EF5350,3=#AB47BC"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut palette = PartPalette::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            match entry.split_once('=') {
+                Some((part, color)) => {
+                    let part = part
+                        .trim()
+                        .parse()
+                        .map_err(|_| Error::BadColor(entry.to_string()))?;
+                    palette.by_part.insert(part, color.parse()?);
+                }
+                None => palette.colors.push(entry.parse()?),
+            }
+        }
+        Ok(palette)
+    }
+}
+
+/// A color with channels from 0 to 1, as Lottie has them
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rgb(pub [f64; 3]);
+
+impl FromStr for Rgb {
+    type Err = Error;
+
+    /// Parse #RRGGBB, the # being optional
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || Error::BadColor(s.to_string());
+        let hex = s.trim().strip_prefix('#').unwrap_or(s.trim());
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(bad());
+        }
+        let channel = |i: usize| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map(|c| c as f64 / 255.0)
+                .map_err(|_| bad())
+        };
+        Ok(Rgb([channel(0)?, channel(2)?, channel(4)?]))
+    }
+}
+
+fn nth_part_color(n: usize) -> Rgb {
+    // Taken from https://m2.material.io/design/color/the-color-system.html#tools-for-picking-colors
+    // "2014 Material Design color palettes"
+    const COLORS: &[(u8, u8, u8)] = &[
+        (0xEF, 0x53, 0x50),
+        (0xEC, 0x40, 0x7A),
+        (0xAB, 0x47, 0xBC),
+        (0xE5, 0x39, 0x35),
+        (0xD8, 0x1B, 0x60),
+        (0x8E, 0x24, 0xAA),
+        (0xC6, 0x28, 0x28),
+        (0xAD, 0x14, 0x57),
+        (0x6A, 0x1B, 0x9A),
+    ];
+
+    let (r, g, b) = COLORS[n % COLORS.len()];
+    Rgb([r as f64 / 255.0, g as f64 / 255.0, b as f64 / 255.0])
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Shape;
+
+    use super::*;
+    use crate::evaluate::Evaluator;
+
+    #[test]
+    fn burst_ticks_land_on_a_ring() {
+        let bounds = Rect::new(100.0, 100.0, 300.0, 300.0);
+        let burst = BurstStyle::default().shapes(bounds, 0.0, 60.0).unwrap();
+        let lottie = json!({
+            "fr": 60, "ip": 0, "op": 60, "w": 400, "h": 400,
+            "layers": [{
+                "ty": 4, "ind": 1, "ip": 0, "op": 60, "st": 0,
+                "ks": {},
+                "shapes": [serde_json::to_value(burst).unwrap()],
+            }],
+        });
+        let frame = Evaluator::from_json(lottie).unwrap().frame(0.0);
+        assert_eq!(frame.fills.len(), 8);
+        let distances: Vec<_> = frame
+            .fills
+            .iter()
+            .map(|fill| fill.path.bounding_box().center().distance(bounds.center()))
+            .collect();
+        for distance in distances.iter() {
+            assert!((distance - distances[0]).abs() < 0.01, "{distances:?}");
+        }
+        assert!(distances[0] < 0.5 * bounds.width(), "{distances:?}");
+    }
+}
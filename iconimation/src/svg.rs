@@ -0,0 +1,175 @@
+//! SVG drawings, read with usvg, as filled outlines.
+//!
+//! OT-SVG glyphs become [ColorLayer]s, one per filled path, so color icon fonts that ship an
+//! SVG table animate as COLR glyphs do; glyphs whose document can't be read fall back to their
+//! glyf or CFF outline, see [crate::Glyph::unusable_svg]. Standalone SVG icons, and bare path data, are read as an [SvgIcon] to
+//! animate with [crate::lottie_for_svg]. Strokes, images, text, clips and masks are ignored and
+//! gradients are approximated by their first color.
+
+use std::io::Read;
+
+use flate2::read::GzDecoder;
+use kurbo::{Affine, BezPath, Point, Rect, Shape};
+use skrifa::{
+    raw::{FontRef, TableProvider},
+    GlyphId,
+};
+use usvg::{tiny_skia_path::PathSegment, Node, Options, Tree};
+
+use crate::{
+    color::{ColorLayer, Paint},
+    error::Error,
+    style::Rgb,
+};
+
+/// An SVG icon flattened to filled paths
+pub struct SvgIcon {
+    /// Bottom to top, Y-up as font units are, with the bottom of the view box at 0
+    pub paths: Vec<(BezPath, Paint)>,
+    /// The view box, in the units of `paths`, as a font's em is the box its glyphs are drawn in
+    pub drawbox: Rect,
+}
+
+/// Read a standalone SVG document, such as an icon exported from a design tool
+pub fn read_svg(data: &[u8]) -> Result<SvgIcon, Error> {
+    let tree =
+        Tree::from_data(data, &Options::default()).map_err(|e| Error::BadSvg(e.to_string()))?;
+    let (width, height) = (tree.size().width() as f64, tree.size().height() as f64);
+    // SVG is Y-down, put the bottom of the view box at 0 as an icon font's em sits on the baseline
+    let transform = Affine::new([1.0, 0.0, 0.0, -1.0, 0.0, height]);
+    let mut paths = Vec::new();
+    for node in tree.root().children() {
+        collect(node, transform, &mut paths);
+    }
+    Ok(SvgIcon {
+        paths,
+        drawbox: Rect::new(0.0, 0.0, width, height),
+    })
+}
+
+/// A single path in SVG `d` syntax, such as "M4 4 L20 4 L12 20 Z", as an icon
+///
+/// There's no view box, so the path is drawn in a box from 0,0 to its furthest point, keeping
+/// any margin above and to its left; [crate::placement::Drawbox::Bounds] fits it snugly.
+pub fn path_icon(d: &str) -> Result<SvgIcon, Error> {
+    let path = BezPath::from_svg(d).map_err(|_| Error::BadPath(d.to_string()))?;
+    let bounds = path.control_box();
+    let (width, height) = (bounds.x1.max(0.0), bounds.y1.max(0.0));
+    // SVG is Y-down, flip it as read_svg does
+    let transform = Affine::new([1.0, 0.0, 0.0, -1.0, 0.0, height]);
+    Ok(SvgIcon {
+        paths: vec![(transform * path, Paint::Foreground { alpha: 1.0 })],
+        drawbox: Rect::new(0.0, 0.0, width, height),
+    })
+}
+
+/// The layers of the OT-SVG glyph for `gid`, in font units, bottom to top; empty if the font
+/// has no SVG document for it
+pub fn svg_layers(font: &FontRef, gid: GlyphId) -> Result<Vec<ColorLayer>, Error> {
+    let Ok(svg) = font.svg() else {
+        return Ok(Vec::new());
+    };
+    let Some(document) = svg.glyph_data(gid).map_err(Error::ReadError)? else {
+        return Ok(Vec::new());
+    };
+    // Documents may be gzipped
+    let mut unzipped = Vec::new();
+    let document = if document.starts_with(&[0x1F, 0x8B]) {
+        GzDecoder::new(document)
+            .read_to_end(&mut unzipped)
+            .map_err(|e| Error::BadSvg(format!("glyph {gid}: {e}")))?;
+        &unzipped[..]
+    } else {
+        document
+    };
+    let tree = Tree::from_data(document, &Options::default())
+        .map_err(|e| Error::BadSvg(format!("glyph {gid}: {e}")))?;
+    // One document may hold many glyphs, each an element with an id such as glyph12
+    let Some(node) = tree.node_by_id(&format!("glyph{}", gid.to_u16())) else {
+        return Ok(Vec::new());
+    };
+    // SVG is Y-down with the baseline at 0, fonts are Y-up
+    Ok(filled_paths(node, Affine::FLIP_Y)
+        .into_iter()
+        .map(|(path, paint)| ColorLayer {
+            gid,
+            transform: Affine::IDENTITY,
+            paint,
+            path: Some(path),
+        })
+        .collect())
+}
+
+/// Every filled path in `node`, bottom to top, with `transform` applied after their own
+pub(crate) fn filled_paths(node: &Node, transform: Affine) -> Vec<(BezPath, Paint)> {
+    let mut paths = Vec::new();
+    collect(node, transform, &mut paths);
+    paths
+}
+
+fn collect(node: &Node, transform: Affine, paths: &mut Vec<(BezPath, Paint)>) {
+    match node {
+        Node::Group(group) => {
+            for child in group.children() {
+                collect(child, transform, paths);
+            }
+        }
+        Node::Path(path) if path.is_visible() => {
+            let Some(fill) = path.fill() else {
+                return;
+            };
+            let Some((color, alpha)) = color_of(fill.paint()) else {
+                return;
+            };
+            let paint = Paint::Solid {
+                color,
+                alpha: alpha * fill.opacity().get() as f64,
+            };
+            let t = path.abs_transform();
+            let abs_transform = Affine::new([
+                t.sx as f64,
+                t.ky as f64,
+                t.kx as f64,
+                t.sy as f64,
+                t.tx as f64,
+                t.ty as f64,
+            ]);
+            paths.push((transform * abs_transform * bez_path(path.data()), paint));
+        }
+        _ => (),
+    }
+}
+
+/// A solid color for `paint`, the first stop of gradients, None for patterns
+fn color_of(paint: &usvg::Paint) -> Option<(Rgb, f64)> {
+    let rgb = |color: usvg::Color| {
+        Rgb([
+            color.red as f64 / 255.0,
+            color.green as f64 / 255.0,
+            color.blue as f64 / 255.0,
+        ])
+    };
+    let stops = match paint {
+        usvg::Paint::Color(color) => return Some((rgb(*color), 1.0)),
+        usvg::Paint::LinearGradient(gradient) => gradient.stops(),
+        usvg::Paint::RadialGradient(gradient) => gradient.stops(),
+        usvg::Paint::Pattern(..) => return None,
+    };
+    let stop = stops.first()?;
+    Some((rgb(stop.color()), stop.opacity().get() as f64))
+}
+
+fn bez_path(path: &usvg::tiny_skia_path::Path) -> BezPath {
+    let point = |p: usvg::tiny_skia_path::Point| Point::new(p.x as f64, p.y as f64);
+    let mut bez = BezPath::new();
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => bez.move_to(point(p)),
+            PathSegment::LineTo(p) => bez.line_to(point(p)),
+            PathSegment::QuadTo(c, p) => bez.quad_to(point(c), point(p)),
+            PathSegment::CubicTo(c0, c1, p) => bez.curve_to(point(c0), point(c1), point(p)),
+            PathSegment::Close => bez.close_path(),
+        }
+    }
+    bez
+}
@@ -0,0 +1,238 @@
+//! Build templates in code rather than authoring Lottie by hand.
+//!
+//! [crate::default_template_with] varies the one placeholder template used when none is given,
+//! [TemplateBuilder] lays out templates of any shape.
+//!
+//! ```ignore
+//! // A before and after card, icons for placeholder:1 and placeholder:2 side by side
+//! let card = TemplateBuilder::new(1024.0, 512.0)
+//!     .with_duration(2.0)
+//!     .with_background(Rgb([1.0, 1.0, 1.0]))
+//!     .with_slot(Slot::named("1", Rect::new(64.0, 64.0, 448.0, 448.0)))
+//!     .with_slot(Slot::named("2", Rect::new(576.0, 64.0, 960.0, 448.0)))
+//!     .build()?;
+//! ```
+
+use bodymovin::Bodymovin as Lottie;
+use kurbo::Rect;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{error::Error, placement::PLACEHOLDER, style::Rgb};
+
+/// How [crate::default_template_with] varies the default template
+#[derive(Clone, Debug)]
+pub struct TemplateOptions {
+    pub frame_rate: f64,
+    /// In seconds
+    pub duration: f64,
+    /// The width and height of the canvas, the font's drawbox if None; the placeholder stays
+    /// the drawbox's size
+    pub canvas: Option<(u32, u32)>,
+    /// Fill the canvas with this color behind the glyph
+    pub background: Option<Rgb>,
+    /// Paint the glyph this color unless the style says otherwise
+    pub fill: Option<Rgb>,
+}
+
+impl Default for TemplateOptions {
+    /// A drawbox sized canvas playing for a second at 60fps
+    fn default() -> Self {
+        TemplateOptions {
+            frame_rate: 60.0,
+            duration: 1.0,
+            canvas: None,
+            background: None,
+            fill: None,
+        }
+    }
+}
+
+/// A placeholder for [TemplateBuilder] to add
+#[derive(Clone, Debug)]
+pub struct Slot {
+    /// Such as "2" to be named `placeholder:2`, None to be named `placeholder`
+    pub name: Option<String>,
+    /// Where the glyph goes, in canvas units
+    pub bounds: Rect,
+    /// Paint the glyph this color unless the style says otherwise
+    pub fill: Option<Rgb>,
+}
+
+impl Slot {
+    /// A slot named such as `placeholder:2` for `name` "2", filling `bounds`
+    pub fn named(name: &str, bounds: Rect) -> Self {
+        Slot {
+            name: Some(name.to_string()),
+            bounds,
+            fill: None,
+        }
+    }
+}
+
+/// Composes a canvas, its timing, a background and placeholders into a template
+#[derive(Clone, Debug)]
+pub struct TemplateBuilder {
+    width: f64,
+    height: f64,
+    frame_rate: f64,
+    /// In seconds
+    duration: f64,
+    background: Option<Rgb>,
+    slots: Vec<Slot>,
+}
+
+impl TemplateBuilder {
+    /// A `width` by `height` canvas playing for a second at 60fps, with nothing on it
+    pub fn new(width: f64, height: f64) -> Self {
+        TemplateBuilder {
+            width,
+            height,
+            frame_rate: 60.0,
+            duration: 1.0,
+            background: None,
+            slots: Vec::new(),
+        }
+    }
+
+    pub fn with_frame_rate(mut self, frame_rate: f64) -> Self {
+        self.frame_rate = frame_rate;
+        self
+    }
+
+    /// Play for `duration` seconds
+    pub fn with_duration(mut self, duration: f64) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Fill the canvas with `color` behind everything else
+    pub fn with_background(mut self, color: Rgb) -> Self {
+        self.background = Some(color);
+        self
+    }
+
+    /// Add a placeholder, named `placeholder`, filling `bounds`
+    pub fn with_placeholder(self, bounds: Rect) -> Self {
+        self.with_slot(Slot {
+            name: None,
+            bounds,
+            fill: None,
+        })
+    }
+
+    /// Add a placeholder, each on a layer of its own above those added before
+    pub fn with_slot(mut self, slot: Slot) -> Self {
+        self.slots.push(slot);
+        self
+    }
+
+    pub fn build(&self) -> Result<Lottie, Error> {
+        let frames = (self.duration * self.frame_rate).round();
+        if !(self.width > 0.0 && self.height > 0.0 && frames > 0.0) {
+            return Err(Error::UnsupportedTemplate(format!(
+                "a {}x{} canvas playing {} frames is empty",
+                self.width, self.height, frames
+            )));
+        }
+        let layer = |index: usize, shapes: Vec<Value>| {
+            json!({
+                "ddd": 0,
+                "ind": index,
+                "ty": 4,
+                "sr": 1,
+                "ks": {
+                    "o": fixed(json!(100)),
+                    "r": fixed(json!(0)),
+                    "p": fixed(json!([0, 0, 0])),
+                    "a": fixed(json!([0, 0, 0])),
+                    "s": fixed(json!([100, 100, 100])),
+                },
+                "ao": 0,
+                "ip": 0,
+                "op": frames,
+                "st": 0,
+                "bm": 0,
+                "shapes": shapes,
+            })
+        };
+
+        // Earlier layers draw on top, the background goes last
+        let mut layers: Vec<_> = self
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(i, slot)| {
+                let name = match &slot.name {
+                    Some(name) => format!("{PLACEHOLDER}:{name}"),
+                    None => PLACEHOLDER.to_string(),
+                };
+                let mut items = vec![rect(slot.bounds)];
+                items.extend(slot.fill.map(fill));
+                items.push(transform());
+                // Left unnamed to be named for the glyph placed in it
+                layer(i + 1, vec![group(&name, items)])
+            })
+            .collect();
+        if let Some(background) = self.background {
+            let canvas = Rect::new(0.0, 0.0, self.width, self.height);
+            let items = vec![rect(canvas), fill(background), transform()];
+            let mut background = layer(layers.len() + 1, vec![group("background", items)]);
+            background["nm"] = json!("background");
+            layers.push(background);
+        }
+
+        Lottie::deserialize(json!({
+            "v": "5.7.0",
+            "fr": self.frame_rate,
+            "ip": 0,
+            "op": frames,
+            "w": self.width.round() as i64,
+            "h": self.height.round() as i64,
+            "ddd": 0,
+            "assets": [],
+            "layers": layers,
+        }))
+        .map_err(Error::JsonError)
+    }
+}
+
+fn fixed(value: Value) -> Value {
+    json!({ "a": 0, "k": value })
+}
+
+fn rect(bounds: Rect) -> Value {
+    // Rects are positioned by their center
+    let center = bounds.center();
+    json!({
+        "ty": "rc",
+        "p": fixed(json!([center.x, center.y])),
+        "s": fixed(json!([bounds.width(), bounds.height()])),
+        "r": fixed(json!(0)),
+    })
+}
+
+fn fill(color: Rgb) -> Value {
+    let [r, g, b] = color.0;
+    json!({
+        "ty": "fl",
+        "c": fixed(json!([r, g, b, 1])),
+        "o": fixed(json!(100)),
+        "r": 1,
+    })
+}
+
+fn transform() -> Value {
+    json!({
+        "ty": "tr",
+        "p": fixed(json!([0, 0])),
+        "a": fixed(json!([0, 0])),
+        "s": fixed(json!([100, 100])),
+        "r": fixed(json!(0)),
+        "o": fixed(json!(100)),
+    })
+}
+
+fn group(name: &str, items: Vec<Value>) -> Value {
+    json!({ "ty": "gr", "nm": name, "it": items })
+}
@@ -0,0 +1,237 @@
+//! Lay several glyphs out on a line so short words and labels animate as one.
+//!
+//! [layout_by_advance] sets each character's glyph after the last. With the `shaping` feature,
+//! [shape] applies the font's kerning, ligatures and mark positioning as well, so icon fonts
+//! can be driven by ligature names such as "arrow_forward".
+//!
+//! Right-to-left text, such as Hebrew or Arabic, is set in visual order, its first character
+//! rightmost. Only shaping joins Arabic letters into their contextual forms. Lines are treated
+//! as a single run, so text mixing directions isn't reordered as the bidi algorithm would.
+//!
+//! [gid_for_sequence] resolves sequences, such as emoji joined with ZWJ or given a skin tone,
+//! to the one glyph the font composes for them.
+//!
+//! A character followed by a variation selector, such as VS15 asking for text presentation or
+//! VS16 for emoji, gets the glyph the cmap format 14 subtable maps the pair to. Fonts with no
+//! such mapping have only the one presentation, so the character's usual glyph is used.
+
+use kurbo::Vec2;
+use skrifa::{
+    charmap::{Charmap, MapVariant},
+    instance::Size,
+    raw::FontRef,
+    GlyphId, MetadataProvider,
+};
+
+use crate::{error::Error, DesignLocation};
+
+/// Glyphs positioned on a line, in font units
+#[derive(Clone, Debug, Default)]
+pub struct Line {
+    /// The text set
+    pub text: String,
+    /// Each glyph and where its origin goes
+    pub glyphs: Vec<(GlyphId, Vec2)>,
+    /// Where the line ends, the pen position after the last glyph
+    pub advance: f64,
+}
+
+/// Which way text runs
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Whichever way the first letter of the text runs
+    #[default]
+    Auto,
+    LeftToRight,
+    RightToLeft,
+}
+
+impl Direction {
+    /// Whether `text` runs right-to-left
+    fn is_rtl(self, text: &str) -> bool {
+        match self {
+            Direction::Auto => text
+                .chars()
+                .find_map(|c| {
+                    if is_rtl(c) {
+                        Some(true)
+                    } else {
+                        c.is_alphabetic().then_some(false)
+                    }
+                })
+                .unwrap_or_default(),
+            Direction::LeftToRight => false,
+            Direction::RightToLeft => true,
+        }
+    }
+}
+
+/// Whether `c` is from a right-to-left script, such as Hebrew, Arabic, Syriac or Thaana
+fn is_rtl(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x0590..=0x08FF | 0xFB1D..=0xFDFF | 0xFE70..=0xFEFF | 0x10800..=0x10FFF | 0x1E800..=0x1EFFF
+    )
+}
+
+/// The glyphs the charmap maps the characters of `text` to, one after another by their
+/// advance widths at `location`
+pub fn layout_by_advance(
+    font: &FontRef,
+    text: &str,
+    location: &DesignLocation,
+    direction: Direction,
+) -> Result<Line, Error> {
+    let charmap = font.charmap();
+    let location = font.axes().location(location.0.iter().copied());
+    let metrics = font.glyph_metrics(Size::unscaled(), &location);
+    let mut line = Line {
+        text: text.to_string(),
+        ..Default::default()
+    };
+    let mut gids = Vec::new();
+    let mut chars = text.chars().map(|c| c as u32).peekable();
+    while let Some(c) = chars.next() {
+        let gid = match chars.next_if(|next| is_variation_selector(*next)) {
+            Some(selector) => map_variant(&charmap, c, selector),
+            None => charmap.map(c),
+        };
+        gids.push(gid.ok_or(Error::NoGlyphForCodepoint(c))?);
+    }
+    if direction.is_rtl(text) {
+        gids.reverse();
+    }
+    for gid in gids {
+        line.glyphs.push((gid, Vec2::new(line.advance, 0.0)));
+        line.advance += metrics.advance_width(gid).unwrap_or_default() as f64;
+    }
+    if line.glyphs.is_empty() {
+        return Err(Error::EmptyText);
+    }
+    Ok(line)
+}
+
+/// Whether `c` is a variation selector, such as VS15 or VS16
+fn is_variation_selector(c: u32) -> bool {
+    matches!(c, 0xFE00..=0xFE0F | 0xE0100..=0xE01EF)
+}
+
+/// The glyph for `codepoint` followed by `selector`, from the cmap format 14 subtable if it maps
+/// the pair, else the glyph for `codepoint` alone
+fn map_variant(charmap: &Charmap, codepoint: u32, selector: u32) -> Option<GlyphId> {
+    match charmap.map_variant(codepoint, selector) {
+        Some(MapVariant::Variant(gid)) => Some(gid),
+        Some(MapVariant::UseDefault) | None => charmap.map(codepoint),
+    }
+}
+
+/// The glyph the font draws `sequence` with, such as the one glyph of a ZWJ emoji sequence
+///
+/// A codepoint and a variation selector resolve through the charmap, longer sequences are
+/// shaped, so need the `shaping` feature.
+#[cfg_attr(not(feature = "shaping"), allow(unused_variables))]
+pub fn gid_for_sequence(
+    font: &FontRef,
+    sequence: &[u32],
+    location: &DesignLocation,
+) -> Result<GlyphId, Error> {
+    let charmap = font.charmap();
+    match sequence {
+        [codepoint] => {
+            return charmap
+                .map(*codepoint)
+                .ok_or(Error::NoGlyphForCodepoint(*codepoint))
+        }
+        [codepoint, selector] if is_variation_selector(*selector) => {
+            return map_variant(&charmap, *codepoint, *selector)
+                .ok_or(Error::NoGlyphForCodepoint(*codepoint))
+        }
+        _ => (),
+    }
+    let unresolved = || {
+        let hex: Vec<_> = sequence.iter().map(|c| format!("U+{c:04X}")).collect();
+        Error::NoGlyphForSequence(hex.join(" "))
+    };
+    #[cfg(feature = "shaping")]
+    {
+        let text: String = sequence.iter().filter_map(|c| char::from_u32(*c)).collect();
+        let line = shape(font, &text, location, Direction::Auto)?;
+        if let [(gid, _)] = line.glyphs[..] {
+            if gid != GlyphId::NOTDEF {
+                return Ok(gid);
+            }
+        }
+    }
+    Err(unresolved())
+}
+
+/// `text` shaped by rustybuzz with the font's default features at `location`
+///
+/// Characters the font has no glyph for shape to .notdef, as they would in any text stack.
+/// [Direction::Auto] leaves rustybuzz to pick the direction from the script of the text.
+#[cfg(feature = "shaping")]
+pub fn shape(
+    font: &FontRef,
+    text: &str,
+    location: &DesignLocation,
+    direction: Direction,
+) -> Result<Line, Error> {
+    use rustybuzz::{ttf_parser::Tag, Face, UnicodeBuffer, Variation};
+
+    let data = font.data().as_bytes();
+    let mut face = Face::from_slice(data, collection_index(font)).ok_or(Error::ShapingFontError)?;
+    let variations: Vec<_> = location
+        .0
+        .iter()
+        .map(|setting| Variation {
+            tag: Tag::from_bytes(&setting.selector.to_be_bytes()),
+            value: setting.value,
+        })
+        .collect();
+    face.set_variations(&variations);
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    match direction {
+        Direction::Auto => (),
+        Direction::LeftToRight => buffer.set_direction(rustybuzz::Direction::LeftToRight),
+        Direction::RightToLeft => buffer.set_direction(rustybuzz::Direction::RightToLeft),
+    }
+    let shaped = rustybuzz::shape(&face, &[], buffer);
+
+    let mut line = Line {
+        text: text.to_string(),
+        ..Default::default()
+    };
+    let mut pen = Vec2::ZERO;
+    for (info, position) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+        let offset = Vec2::new(position.x_offset as f64, position.y_offset as f64);
+        line.glyphs
+            .push((GlyphId::new(info.glyph_id as u16), pen + offset));
+        pen += Vec2::new(position.x_advance as f64, position.y_advance as f64);
+    }
+    line.advance = pen.x;
+    if line.glyphs.is_empty() {
+        return Err(Error::EmptyText);
+    }
+    Ok(line)
+}
+
+/// Which font of its file `font` is, 0 unless the file is a collection
+#[cfg(feature = "shaping")]
+fn collection_index(font: &FontRef) -> u32 {
+    use skrifa::raw::FileRef;
+
+    let FileRef::Collection(collection) = FileRef::new(font.data().as_bytes()) else {
+        return 0;
+    };
+    // Fonts of a collection share the file's data, each has its own table directory
+    let directory = font.table_directory.offset_data().as_bytes().as_ptr();
+    (0..collection.len())
+        .find(|i| {
+            collection.get(*i).is_ok_and(|other| {
+                other.table_directory.offset_data().as_bytes().as_ptr() == directory
+            })
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,102 @@
+//! Telegram animated stickers: gzipped Lottie within Telegram's limits.
+//!
+//! See <https://core.telegram.org/stickers#animated-stickers>.
+
+use std::{cmp::Ordering, io::Write};
+
+use bodymovin::Bodymovin as Lottie;
+use flate2::{write::GzEncoder, Compression};
+use serde_json::Value;
+
+use crate::{
+    error::Error,
+    optimize::optimize,
+    output::{round_numbers, OutputOptions},
+};
+
+pub const SIZE: i64 = 512;
+pub const MAX_FRAME_RATE: f64 = 60.0;
+pub const MAX_SECONDS: f64 = 3.0;
+/// The limit on the gzipped file
+pub const MAX_BYTES: usize = 64 * 1024;
+
+/// Everything about `lottie` that Telegram won't accept, empty if it's fine
+pub fn violations(lottie: &Lottie) -> Result<Vec<String>, Error> {
+    let mut violations = Vec::new();
+    if (lottie.width, lottie.height) != (SIZE, SIZE) {
+        violations.push(format!(
+            "must be {SIZE}x{SIZE}, is {}x{}; use a {SIZE}x{SIZE} template",
+            lottie.width, lottie.height
+        ));
+    }
+    if lottie.frame_rate > MAX_FRAME_RATE {
+        violations.push(format!(
+            "must be at most {MAX_FRAME_RATE}fps, is {}fps",
+            lottie.frame_rate
+        ));
+    }
+    let seconds = (lottie.out_point - lottie.in_point) / lottie.frame_rate;
+    // also catches a NaN length, from a frame rate of 0
+    if !matches!(
+        seconds.partial_cmp(&MAX_SECONDS),
+        Some(Ordering::Less | Ordering::Equal)
+    ) {
+        violations.push(format!(
+            "must last at most {MAX_SECONDS}s, lasts {seconds:.2}s"
+        ));
+    }
+    if has_expressions(&serde_json::to_value(lottie).map_err(Error::JsonError)?) {
+        violations.push("must not use expressions".to_string());
+    }
+    Ok(violations)
+}
+
+/// Animated properties carry their expression, if any, as a string in "x"
+fn has_expressions(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => fields
+            .iter()
+            .any(|(k, v)| (k == "x" && v.is_string()) || has_expressions(v)),
+        Value::Array(values) => values.iter().any(has_expressions),
+        _ => false,
+    }
+}
+
+/// The bytes of a .tgs file, or [Error::NotTgs] listing what Telegram would reject
+///
+/// Only `options.precision`, `options.optimize` and `options.template` apply, stickers are
+/// always compact.
+pub fn to_tgs(lottie: &Lottie, options: &OutputOptions) -> Result<Vec<u8>, Error> {
+    let mut violations = violations(lottie)?;
+    if !violations.is_empty() {
+        return Err(Error::NotTgs(violations));
+    }
+
+    let mut value = serde_json::to_value(lottie).map_err(Error::JsonError)?;
+    if let Some(template) = &options.template {
+        template.unmodeled().restore(&mut value);
+    }
+    if let Some(precision) = options.precision {
+        round_numbers(&mut value, precision);
+    }
+    if options.optimize {
+        optimize(&mut value);
+    }
+    if let Value::Object(fields) = &mut value {
+        fields.insert("tgs".to_string(), Value::from(1));
+    }
+    let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+    serde_json::to_writer(&mut gz, &value).map_err(Error::JsonError)?;
+    gz.flush().map_err(Error::IoError)?;
+    let bytes = gz.finish().map_err(Error::IoError)?;
+
+    if bytes.len() > MAX_BYTES {
+        violations.push(format!(
+            "must be at most {}KB gzipped, is {:.1}KB; try lowering precision or optimizing",
+            MAX_BYTES / 1024,
+            bytes.len() as f64 / 1024.0
+        ));
+        return Err(Error::NotTgs(violations));
+    }
+    Ok(bytes)
+}
@@ -0,0 +1,258 @@
+//! Keep what bodymovin doesn't model when a template goes through it.
+//!
+//! Templates are parsed into bodymovin's types to replace the placeholder, dropping any field
+//! those types don't have, such as newer Lottie features or an editor's own metadata. What
+//! would be dropped is found by comparing a template with itself parsed and written back out,
+//! and restored to the output afterwards: to the same object, found by path, array items by
+//! their name and type where they have them, by index where they don't.
+
+use bodymovin::Bodymovin as Lottie;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::error::Error;
+
+/// The fields of a template that bodymovin drops
+#[derive(Clone, Debug, Default)]
+pub struct Unmodeled {
+    root: Option<Part>,
+}
+
+/// What's dropped from a json value or within it
+#[derive(Clone, Debug)]
+enum Part {
+    Object {
+        /// Dropped entirely, to be restored as is
+        fields: Map<String, Value>,
+        /// Kept, with something dropped within
+        within: Vec<(String, Part)>,
+    },
+    Array {
+        len: usize,
+        items: Vec<Item>,
+    },
+}
+
+/// An item of an array with something dropped within
+#[derive(Clone, Debug)]
+struct Item {
+    index: usize,
+    /// To find the item again once others are added or removed around it
+    name: Option<Value>,
+    ty: Option<Value>,
+    part: Part,
+}
+
+impl Unmodeled {
+    /// What bodymovin drops from `template`
+    pub fn of(template: &Value) -> Result<Self, Error> {
+        let lottie = Lottie::deserialize(template).map_err(Error::JsonError)?;
+        let kept = serde_json::to_value(lottie).map_err(Error::JsonError)?;
+        Ok(Unmodeled {
+            root: dropped(template, &kept),
+        })
+    }
+
+    /// Whether bodymovin keeps everything
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Put what was dropped back into `lottie`, made from the template, wherever what held it
+    /// still is; fields `lottie` has a value for are left alone
+    pub fn restore(&self, lottie: &mut Value) {
+        if let Some(root) = &self.root {
+            restore(root, lottie);
+        }
+    }
+}
+
+/// What `original` has that `kept`, it parsed and written back out, doesn't
+fn dropped(original: &Value, kept: &Value) -> Option<Part> {
+    match (original, kept) {
+        (Value::Object(original), Value::Object(kept)) => {
+            let mut fields = Map::new();
+            let mut within = Vec::new();
+            for (key, value) in original.iter() {
+                match kept.get(key) {
+                    None => {
+                        fields.insert(key.clone(), value.clone());
+                    }
+                    Some(kept) => {
+                        if let Some(part) = dropped(value, kept) {
+                            within.push((key.clone(), part));
+                        }
+                    }
+                }
+            }
+            (!fields.is_empty() || !within.is_empty()).then_some(Part::Object { fields, within })
+        }
+        // Items dropped whole leave nothing to line the rest up by
+        (Value::Array(original), Value::Array(kept)) if original.len() == kept.len() => {
+            let items: Vec<_> = original
+                .iter()
+                .zip(kept.iter())
+                .enumerate()
+                .filter_map(|(index, (original, kept))| {
+                    Some(Item {
+                        index,
+                        name: original.get("nm").cloned(),
+                        ty: original.get("ty").cloned(),
+                        part: dropped(original, kept)?,
+                    })
+                })
+                .collect();
+            (!items.is_empty()).then_some(Part::Array {
+                len: original.len(),
+                items,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn restore(part: &Part, value: &mut Value) {
+    match (part, value) {
+        (Part::Object { fields, within }, Value::Object(object)) => {
+            for (key, value) in fields.iter() {
+                object.entry(key.clone()).or_insert_with(|| value.clone());
+            }
+            for (key, part) in within.iter() {
+                if let Some(value) = object.get_mut(key) {
+                    restore(part, value);
+                }
+            }
+        }
+        (Part::Array { len, items }, Value::Array(values)) => {
+            let mut used = vec![false; values.len()];
+            for item in items.iter() {
+                let found = match &item.name {
+                    Some(name) => (0..values.len()).find(|i| {
+                        !used[*i]
+                            && values[*i].get("nm") == Some(name)
+                            && values[*i].get("ty") == item.ty.as_ref()
+                    }),
+                    // Unnamed, such as keyframes, only where nothing was added or removed
+                    None => (values.len() == *len
+                        && values[item.index].get("ty") == item.ty.as_ref())
+                    .then_some(item.index),
+                };
+                if let Some(i) = found {
+                    used[i] = true;
+                    restore(&item.part, &mut values[i]);
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// The builtin plain template with an editor's metadata on its unnamed layer, on its
+    /// placeholder's box and transform, and on the first keyframe of the layer's opacity
+    fn template() -> Value {
+        let mut template: Value =
+            serde_json::from_str(include_str!("../resources/templates/plain.json")).unwrap();
+        let layer = &mut template["layers"][0];
+        layer["editor"] = json!({ "locked": true });
+        layer["ks"]["o"] = json!({ "a": 1, "k": [
+            {
+                "t": 0, "s": [0],
+                "i": { "x": [0.4], "y": [1] }, "o": { "x": [0.6], "y": [0] },
+                "editor": { "ease": "smooth" },
+            },
+            { "t": 30, "s": [100] },
+        ] });
+        let items = &mut layer["shapes"][0]["it"];
+        items[0]["editor"] = json!({ "role": "box" });
+        items[1]["editor"] = json!({ "role": "transform" });
+        template
+    }
+
+    /// `template` as bodymovin writes it back out, as the output made from it starts
+    fn kept(template: &Value) -> Value {
+        serde_json::to_value(Lottie::deserialize(template).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn restores_fields_of_a_layer_named_since() {
+        let template = template();
+        let mut output = kept(&template);
+        assert!(output["layers"][0].get("editor").is_none());
+        // As unnamed layers are named for their glyph
+        output["layers"][0]["nm"] = json!("glyph U+E87C");
+
+        Unmodeled::of(&template).unwrap().restore(&mut output);
+        assert_eq!(output["layers"][0]["editor"], json!({ "locked": true }));
+    }
+
+    #[test]
+    fn restores_fields_of_shapes_beside_the_replaced_box() {
+        let template = template();
+        let mut output = kept(&template);
+        let Value::Array(items) = &mut output["layers"][0]["shapes"][0]["it"] else {
+            panic!("The placeholder has no items");
+        };
+        items.splice(
+            0..1,
+            [
+                json!({ "ty": "gr", "nm": "part-0", "it": [] }),
+                json!({ "ty": "gr", "nm": "part-1", "it": [] }),
+            ],
+        );
+
+        Unmodeled::of(&template).unwrap().restore(&mut output);
+        let items = &output["layers"][0]["shapes"][0]["it"];
+        assert_eq!(items[2]["nm"], "transform");
+        assert_eq!(items[2]["editor"], json!({ "role": "transform" }));
+        // The box is gone, what replaced it isn't the box
+        assert!(items[0].get("editor").is_none(), "{items}");
+        assert!(items[1].get("editor").is_none(), "{items}");
+    }
+
+    #[test]
+    fn restores_fields_of_keyframes() {
+        let template = template();
+        let mut output = kept(&template);
+
+        Unmodeled::of(&template).unwrap().restore(&mut output);
+        let keyframes = &output["layers"][0]["ks"]["o"]["k"];
+        assert_eq!(keyframes[0]["editor"], json!({ "ease": "smooth" }));
+        assert!(keyframes[1].get("editor").is_none(), "{keyframes}");
+    }
+
+    #[test]
+    fn restores_nothing_by_index_once_lengths_differ() {
+        let template = template();
+        let unmodeled = Unmodeled::of(&template).unwrap();
+
+        let mut output = kept(&template);
+        let Value::Array(keyframes) = &mut output["layers"][0]["ks"]["o"]["k"] else {
+            panic!("Opacity isn't keyframed");
+        };
+        keyframes.push(json!({ "t": 60, "s": [0] }));
+        unmodeled.restore(&mut output);
+        // The layers still line up, the keyframes don't
+        assert_eq!(output["layers"][0]["editor"], json!({ "locked": true }));
+        let keyframes = &output["layers"][0]["ks"]["o"]["k"];
+        for keyframe in keyframes.as_array().unwrap() {
+            assert!(keyframe.get("editor").is_none(), "{keyframes}");
+        }
+
+        let mut output = kept(&template);
+        let layer = output["layers"][0].clone();
+        let Value::Array(layers) = &mut output["layers"] else {
+            panic!("The output has no layers");
+        };
+        layers.push(layer);
+        unmodeled.restore(&mut output);
+        for layer in output["layers"].as_array().unwrap() {
+            assert!(layer.get("editor").is_none(), "{layer}");
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Check a template before animating into it.
+//!
+//! Replacing shapes stops at the first thing wrong with a template, or reports only that
+//! nothing was replaced. Validation instead finds every placeholder and everything wrong with
+//! each, so a template author can fix them all at once.
+
+use std::cmp::Ordering;
+
+use bodymovin::{layers::AnyLayer, shapes::AnyShape, sources::Asset, Bodymovin as Lottie};
+use serde::Serialize;
+
+use crate::{
+    layer_name, layer_time_range, placeholder_box, placeholder_layer_to_group, placement::Placement,
+};
+
+/// A placeholder found in a template
+#[derive(Clone, Debug, Serialize)]
+pub struct FoundPlaceholder {
+    /// Where it is, such as `layers[1] "card" > placeholder:2`
+    pub path: String,
+    /// The slot, such as "2" for `placeholder:2`, if it has one
+    pub slot: Option<String>,
+    /// The boxes glyphs are placed in, as [x0, y0, x1, y1] in the units of the layer
+    pub boxes: Vec<[f64; 4]>,
+    /// The frames, in the layer's own time, between which glyphs animate; None if the layer
+    /// doesn't play forwards
+    pub frames: Option<(f64, f64)>,
+    /// What's wrong with the placeholder, empty if glyphs can be placed in it
+    pub problems: Vec<String>,
+}
+
+/// Every placeholder in `template`, in its layers and those of its precomps
+///
+/// A template is usable if this finds at least one placeholder and none have problems.
+pub fn validate_template(template: &Lottie, placement: &Placement) -> Vec<FoundPlaceholder> {
+    let mut found = Vec::new();
+    validate_layers(&template.layers, "layers", placement, &mut found);
+    for (i, asset) in template.assets.iter().enumerate() {
+        if let Asset::PreComp(precomp) = asset {
+            let path = format!("assets[{i}].layers");
+            validate_layers(&precomp.layers, &path, placement, &mut found);
+        }
+    }
+    found
+}
+
+fn validate_layers(
+    layers: &[AnyLayer],
+    path: &str,
+    placement: &Placement,
+    found: &mut Vec<FoundPlaceholder>,
+) {
+    for (i, layer) in layers.iter().enumerate() {
+        let AnyLayer::Shape(layer) = layer else {
+            continue;
+        };
+        let mut layer = layer.clone();
+        // Problems with the layer are problems for every placeholder in it
+        let mut problems = Vec::new();
+        let name = layer_name(&layer).unwrap_or_else(|e| {
+            problems.push(e.to_string());
+            None
+        });
+        let path = match &name {
+            Some(name) => format!("{path}[{i}] {name:?}"),
+            None => format!("{path}[{i}]"),
+        };
+        if layer.in_point.partial_cmp(&layer.out_point) != Some(Ordering::Less) {
+            problems.push(format!(
+                "layer plays from {} to {}, it must end after it starts",
+                layer.in_point, layer.out_point
+            ));
+        }
+        let frames = match layer_time_range(&layer) {
+            Ok(Some(frames)) => Some(frames),
+            Ok(None) => {
+                problems.push(
+                    "layer has a time stretch that isn't positive, it must play forwards"
+                        .to_string(),
+                );
+                None
+            }
+            Err(e) => {
+                problems.push(e.to_string());
+                None
+            }
+        };
+        if let Some(name) = name.filter(|n| placement.is_placeholder(Some(n.as_str()))) {
+            placeholder_layer_to_group(&mut layer, name);
+        }
+        let context = LayerContext {
+            placement,
+            frames,
+            problems: &problems,
+        };
+        validate_shapes(&layer.mixin.shapes, &path, &context, found);
+    }
+}
+
+/// What placeholders in a layer share
+struct LayerContext<'a> {
+    placement: &'a Placement,
+    frames: Option<(f64, f64)>,
+    problems: &'a [String],
+}
+
+fn validate_shapes(
+    shapes: &[AnyShape],
+    path: &str,
+    layer: &LayerContext,
+    found: &mut Vec<FoundPlaceholder>,
+) {
+    for (i, shape) in shapes.iter().enumerate() {
+        let AnyShape::Group(group) = shape else {
+            continue;
+        };
+        let path = match &group.name {
+            Some(name) => format!("{path} > {name}"),
+            None => format!("{path} > group {i}"),
+        };
+        let mut problems = layer.problems.to_vec();
+        let placeholder = match layer.placement.placeholder(group.name.as_deref()) {
+            Ok(Some(placeholder)) => placeholder,
+            Ok(None) => {
+                validate_shapes(&group.items, &path, layer, found);
+                continue;
+            }
+            Err(e) => {
+                problems.push(e.to_string());
+                Default::default()
+            }
+        };
+        let padding = placeholder.padding.unwrap_or(layer.placement.padding);
+        let mut boxes = Vec::new();
+        for item in group.items.iter() {
+            match placeholder_box(item) {
+                Ok(Some(rect)) => {
+                    // Negated so NaN is rejected too
+                    let padded = padding.inset(rect);
+                    if !(rect.width() > 0.0 && rect.height() > 0.0 && rect.is_finite()) {
+                        problems.push(format!("box {rect:?} must have a finite, positive size"));
+                    } else if !(padded.width() > 0.0 && padded.height() > 0.0) {
+                        problems.push(format!(
+                            "padding {padding:?} leaves no room in box {rect:?}"
+                        ));
+                    }
+                    boxes.push([rect.x0, rect.y0, rect.x1, rect.y1]);
+                }
+                Ok(None) => (),
+                Err(e) => problems.push(e.to_string()),
+            }
+        }
+        if boxes.is_empty() {
+            problems.push("no usable rectangle or path to place the glyph in".to_string());
+        }
+        found.push(FoundPlaceholder {
+            path,
+            slot: placeholder.slot,
+            boxes,
+            frames: layer.frames,
+            problems,
+        });
+    }
+}
@@ -0,0 +1,134 @@
+//! Video, MP4 or WebM, for places that take neither Lottie nor GIF.
+//!
+//! Frames are rendered as for [crate::raster::frames] and piped, as raw RGBA, to ffmpeg, which
+//! must be on the PATH, to encode: H.264 for MP4 and VP9, keeping transparency, for WebM.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+};
+
+use bodymovin::Bodymovin as Lottie;
+use tiny_skia::Pixmap;
+
+use crate::{
+    error::Error,
+    raster::{self, frames},
+    style::Rgb,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Container {
+    /// H.264, which has no transparency, so frames are drawn over a background
+    #[default]
+    Mp4,
+    /// VP9, with transparency
+    WebM,
+}
+
+#[derive(Clone, Debug)]
+pub struct VideoOptions {
+    pub container: Container,
+    /// How many pixels square; H.264 needs an even size
+    pub size: u32,
+    /// Frames per second, by default the animation's
+    pub frame_rate: Option<f64>,
+    /// What to draw transparent parts over in containers without transparency
+    pub background: Rgb,
+}
+
+impl Default for VideoOptions {
+    fn default() -> Self {
+        VideoOptions {
+            container: Default::default(),
+            size: 512,
+            frame_rate: None,
+            background: Rgb([1.0, 1.0, 1.0]),
+        }
+    }
+}
+
+/// `lottie` encoded as video, playing once
+pub fn to_video(lottie: &Lottie, options: &VideoOptions) -> Result<Vec<u8>, Error> {
+    if options.container == Container::Mp4 && options.size % 2 == 1 {
+        return Err(Error::VideoError(format!(
+            "MP4 needs an even size, not {}",
+            options.size
+        )));
+    }
+    let (frames, frame_rate) = frames(lottie, options.size, options.frame_rate)?;
+    let size = format!("{0}x{0}", options.size);
+    let frame_rate = frame_rate.to_string();
+    let mut ffmpeg = Command::new("ffmpeg");
+    ffmpeg.args(["-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"]);
+    ffmpeg.args(["-s", &size, "-r", &frame_rate, "-i", "-"]);
+    match options.container {
+        // Written to a pipe, so the index goes up front rather than being seeked back to
+        Container::Mp4 => ffmpeg.args([
+            "-c:v",
+            "libx264",
+            "-pix_fmt",
+            "yuv420p",
+            "-movflags",
+            "frag_keyframe+empty_moov",
+            "-f",
+            "mp4",
+        ]),
+        Container::WebM => {
+            ffmpeg.args(["-c:v", "libvpx-vp9", "-pix_fmt", "yuva420p", "-f", "webm"])
+        }
+    };
+    let mut child = ffmpeg
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| Error::VideoError(format!("unable to run ffmpeg: {e}")))?;
+
+    // Feed frames from another thread so ffmpeg never blocks on a full stdout
+    let mut stdin = child.stdin.take().unwrap();
+    let background = (options.container == Container::Mp4).then_some(options.background);
+    let feeder = thread::spawn(move || -> std::io::Result<()> {
+        for pixmap in frames.iter() {
+            stdin.write_all(&rgba(pixmap, background))?;
+        }
+        Ok(())
+    });
+    let output = child.wait_with_output().map_err(Error::IoError)?;
+    let fed = feeder
+        .join()
+        .map_err(|_| Error::VideoError("feeding frames to ffmpeg failed".to_string()))?;
+    if !output.status.success() {
+        return Err(Error::VideoError(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    fed.map_err(Error::IoError)?;
+    Ok(output.stdout)
+}
+
+/// Straight RGBA, over `background` if there is one
+fn rgba(pixmap: &Pixmap, background: Option<Rgb>) -> Vec<u8> {
+    let Some(Rgb(background)) = background else {
+        return raster::rgba(pixmap);
+    };
+    pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            // Premultiplied color plus as much of the background as shows through
+            let behind = 1.0 - pixel.alpha() as f64 / 255.0;
+            let over = |channel: u8, background: f64| {
+                (channel as f64 + background * 255.0 * behind).round() as u8
+            };
+            [
+                over(pixel.red(), background[0]),
+                over(pixel.green(), background[1]),
+                over(pixel.blue(), background[2]),
+                255,
+            ]
+        })
+        .collect()
+}
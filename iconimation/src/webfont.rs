@@ -0,0 +1,102 @@
+//! Web fonts, WOFF and WOFF2, unpacked to the SFNT they wrap.
+//!
+//! Many icon fonts are only distributed as web fonts. WOFF is zlib compressed tables, WOFF2
+//! Brotli compressed and transformed tables; unpacking WOFF2 needs the `woff2` feature.
+//!
+//! See <https://www.w3.org/TR/WOFF/> and <https://www.w3.org/TR/WOFF2/>.
+
+use std::io::Read;
+
+use flate2::read::ZlibDecoder;
+
+use crate::error::Error;
+
+const WOFF: &[u8] = b"wOFF";
+const WOFF2: &[u8] = b"wOF2";
+
+/// `font` as SFNT, unpacked if it's WOFF or WOFF2 and as is otherwise
+pub fn to_sfnt(font: Vec<u8>) -> Result<Vec<u8>, Error> {
+    if font.starts_with(WOFF) {
+        return unpack_woff(&font);
+    }
+    if font.starts_with(WOFF2) {
+        #[cfg(feature = "woff2")]
+        return woff2::decode::convert_woff2_to_ttf(&mut font.as_slice())
+            .map_err(|e| Error::BadWebFont(format!("unable to unpack WOFF2: {e}")));
+        #[cfg(not(feature = "woff2"))]
+        return Err(Error::BadWebFont(
+            "unpacking WOFF2 needs the woff2 feature".to_string(),
+        ));
+    }
+    Ok(font)
+}
+
+fn unpack_woff(woff: &[u8]) -> Result<Vec<u8>, Error> {
+    let truncated = || Error::BadWebFont("truncated WOFF".to_string());
+    let u16_at = |at: usize| -> Result<u16, Error> {
+        let bytes = woff.get(at..at + 2).ok_or_else(truncated)?;
+        Ok(u16::from_be_bytes(bytes.try_into().unwrap()))
+    };
+    let u32_at = |at: usize| -> Result<u32, Error> {
+        let bytes = woff.get(at..at + 4).ok_or_else(truncated)?;
+        Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+    };
+
+    let flavor = u32_at(4)?;
+    let num_tables = u16_at(12)?;
+    // The header is 44 bytes, each table directory entry 20
+    let mut tables = Vec::with_capacity(num_tables as usize);
+    for i in 0..num_tables as usize {
+        let entry = 44 + 20 * i;
+        let (tag, offset, compressed_length, length, checksum) = (
+            u32_at(entry)?,
+            u32_at(entry + 4)? as usize,
+            u32_at(entry + 8)? as usize,
+            u32_at(entry + 12)? as usize,
+            u32_at(entry + 16)?,
+        );
+        let data = woff
+            .get(offset..offset + compressed_length)
+            .ok_or_else(truncated)?;
+        // Tables that don't compress smaller are stored as is
+        let data = if compressed_length < length {
+            let mut unpacked = Vec::with_capacity(length);
+            ZlibDecoder::new(data)
+                .read_to_end(&mut unpacked)
+                .map_err(|e| Error::BadWebFont(format!("unable to inflate a WOFF table: {e}")))?;
+            unpacked
+        } else {
+            data.to_vec()
+        };
+        if data.len() != length {
+            return Err(Error::BadWebFont(format!(
+                "a WOFF table unpacked to {} bytes, expected {length}",
+                data.len()
+            )));
+        }
+        tables.push((tag, checksum, data));
+    }
+
+    // The offset table, then a 16 byte record per table, then the tables, each 4-byte aligned
+    let entry_selector = num_tables.max(1).ilog2() as u16;
+    let search_range = (1u16 << entry_selector) * 16;
+    let mut sfnt = Vec::new();
+    sfnt.extend(flavor.to_be_bytes());
+    sfnt.extend(num_tables.to_be_bytes());
+    sfnt.extend(search_range.to_be_bytes());
+    sfnt.extend(entry_selector.to_be_bytes());
+    sfnt.extend((num_tables * 16 - search_range).to_be_bytes());
+    let mut offset = 12 + 16 * tables.len();
+    for (tag, checksum, data) in tables.iter() {
+        sfnt.extend(tag.to_be_bytes());
+        sfnt.extend(checksum.to_be_bytes());
+        sfnt.extend((offset as u32).to_be_bytes());
+        sfnt.extend((data.len() as u32).to_be_bytes());
+        offset += data.len().next_multiple_of(4);
+    }
+    for (_, _, data) in tables {
+        sfnt.extend(data);
+        sfnt.resize(sfnt.len().next_multiple_of(4), 0);
+    }
+    Ok(sfnt)
+}